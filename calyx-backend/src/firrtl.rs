@@ -0,0 +1,456 @@
+//! FIRRTL backend for the Calyx compiler.
+//!
+//! Transforms a fully lowered [`ir::Context`](calyx::ir::Context) into a
+//! FIRRTL circuit. Each component becomes a module. `std_add`, `std_reg`,
+//! and the `std_mem_d*` memories are implemented natively while all other
+//! primitives are instantiated as `extmodule`s that refer to their Verilog
+//! definitions.
+use crate::traits::Backend;
+use crate::verilog::{validate_control, validate_structure};
+use calyx::errors::{CalyxResult, Error};
+use calyx::ir::{self, Guard, RRC};
+use itertools::Itertools;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::rc::Rc;
+
+/// Implements a FIRRTL backend. Accepts the same programs as the Verilog
+/// backend: components may only use continuous assignments and no control.
+#[derive(Default)]
+pub struct FirrtlBackend;
+
+impl Backend for FirrtlBackend {
+    fn name(&self) -> &'static str {
+        "firrtl"
+    }
+
+    fn validate(&self, ctx: &ir::Context) -> CalyxResult<()> {
+        for comp in &ctx.components {
+            validate_structure(comp.groups.iter())?;
+            validate_control(&comp.control.borrow())?;
+            let sig = comp.signature.borrow();
+            if let Some(port) = sig
+                .ports
+                .iter()
+                .find(|p| p.borrow().direction == ir::Direction::Inout)
+            {
+                return Err(Error::malformed_structure(format!(
+                    "Port `{}` of component `{}` is an inout port which cannot be turned into FIRRTL",
+                    port.borrow().name,
+                    comp.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Primitives without a native implementation are emitted as
+    /// `extmodule`s which refer to the Verilog definitions by name instead of
+    /// including them.
+    fn link_externs(
+        &self,
+        _prog: &ir::Context,
+        _write: &mut dyn io::Write,
+    ) -> CalyxResult<()> {
+        Ok(())
+    }
+
+    fn emit(
+        &self,
+        ctx: &ir::Context,
+        mut f: &mut dyn io::Write,
+    ) -> CalyxResult<()> {
+        writeln!(f, "circuit {} :", ctx.entrypoint)?;
+        let mut extmodules = BTreeMap::new();
+        for comp in &ctx.components {
+            writeln!(f)?;
+            Self::write_component(comp, &mut extmodules, &mut f)?;
+        }
+        for extmodule in extmodules.values() {
+            writeln!(f)?;
+            write!(f, "{}", extmodule)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns true if the port carries a clock signal.
+fn is_clock(port: &ir::Port) -> bool {
+    port.attributes.has("clk")
+}
+
+/// FIRRTL type of a port.
+fn port_type(port: &ir::Port) -> String {
+    if is_clock(port) {
+        "Clock".to_string()
+    } else {
+        format!("UInt<{}>", port.width)
+    }
+}
+
+/// Number of bits needed to represent `val`. At least one.
+fn bits_needed(val: u64) -> u64 {
+    (64 - val.leading_zeros() as u64).max(1)
+}
+
+impl FirrtlBackend {
+    /// Formats and writes the component as a FIRRTL module. The `extmodule`s
+    /// used by the component are added to `extmodules`.
+    fn write_component<F: io::Write>(
+        comp: &ir::Component,
+        extmodules: &mut BTreeMap<String, String>,
+        f: &mut F,
+    ) -> io::Result<()> {
+        writeln!(f, "  module {} :", comp.name)?;
+        let sig = comp.signature.borrow();
+        for port in &sig.ports {
+            let port = port.borrow();
+            // NOTE: The signature port definitions are reversed inside the
+            // component.
+            let dir = match port.direction {
+                ir::Direction::Input => "output",
+                _ => "input",
+            };
+            writeln!(f, "    {} {} : {}", dir, port.name, port_type(&port))?;
+        }
+        writeln!(f)?;
+        for port in &sig.ports {
+            let port = port.borrow();
+            if port.direction == ir::Direction::Input {
+                writeln!(f, "    {} is invalid", port.name)?;
+            }
+        }
+
+        for cell in comp.cells.iter() {
+            Self::write_cell(&cell.borrow(), extmodules, f)?;
+        }
+
+        // gather assignments keyed by destination
+        let mut map: HashMap<_, (RRC<ir::Port>, Vec<_>)> = HashMap::new();
+        for asgn in &comp.continuous_assignments {
+            map.entry(asgn.dst.borrow().canonical())
+                .and_modify(|(_, v)| v.push(asgn))
+                .or_insert((Rc::clone(&asgn.dst), vec![asgn]));
+        }
+        for (dst, asgns) in map
+            .values()
+            .sorted_by_key(|(port, _)| port.borrow().canonical())
+        {
+            Self::write_assignment(&dst.borrow(), asgns, f)?;
+        }
+        Ok(())
+    }
+
+    /// Declares a wire for each port of the cell and connects the wires to
+    /// the implementation of the cell.
+    fn write_cell<F: io::Write>(
+        cell: &ir::Cell,
+        extmodules: &mut BTreeMap<String, String>,
+        f: &mut F,
+    ) -> io::Result<()> {
+        let prim = match &cell.prototype {
+            ir::CellType::Primitive { name, .. } => Some(name.as_ref()),
+            ir::CellType::Component { .. } => None,
+            ir::CellType::ThisComponent | ir::CellType::Constant { .. } => {
+                return Ok(())
+            }
+        };
+
+        let name = cell.name();
+        for port in &cell.ports {
+            let port = port.borrow();
+            writeln!(
+                f,
+                "    wire {}_{} : {}",
+                name,
+                port.name,
+                port_type(&port)
+            )?;
+            if port.direction == ir::Direction::Input {
+                writeln!(f, "    {}_{} is invalid", name, port.name)?;
+            }
+        }
+
+        match prim {
+            Some("std_add") => writeln!(
+                f,
+                "    {c}_out <= tail(add({c}_left, {c}_right), 1)",
+                c = name
+            ),
+            Some("std_reg") => Self::write_reg(cell, f),
+            Some("std_mem_d1" | "std_mem_d2" | "std_mem_d3" | "std_mem_d4") => {
+                Self::write_mem(cell, f)
+            }
+            _ => {
+                let module = match prim {
+                    Some(prim) => {
+                        let (module, def) = Self::extmodule(prim, cell);
+                        extmodules.entry(module.clone()).or_insert(def);
+                        module
+                    }
+                    None => cell.type_name().unwrap().to_string(),
+                };
+                writeln!(f, "    inst {} of {}", name, module)?;
+                for port in &cell.ports {
+                    let port = port.borrow();
+                    if port.direction == ir::Direction::Input {
+                        writeln!(
+                            f,
+                            "    {c}.{p} <= {c}_{p}",
+                            c = name,
+                            p = port.name
+                        )?;
+                    } else {
+                        writeln!(
+                            f,
+                            "    {c}_{p} <= {c}.{p}",
+                            c = name,
+                            p = port.name
+                        )?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Generates a register with a synchronous reset:
+    /// ```text
+    /// reg r_reg : UInt<32>, r_clk with : (reset => (r_reset, UInt<32>(0)))
+    /// reg r_done_reg : UInt<1>, r_clk with : (reset => (r_reset, UInt<1>(0)))
+    /// r_done_reg <= r_write_en
+    /// when r_write_en :
+    ///   r_reg <= r_in
+    /// r_out <= r_reg
+    /// r_done <= r_done_reg
+    /// ```
+    fn write_reg<F: io::Write>(cell: &ir::Cell, f: &mut F) -> io::Result<()> {
+        let c = cell.name();
+        let width = cell.get_parameter("WIDTH").unwrap();
+        writeln!(
+            f,
+            "    reg {c}_reg : UInt<{w}>, {c}_clk with : (reset => ({c}_reset, UInt<{w}>(0)))",
+            c = c,
+            w = width
+        )?;
+        writeln!(
+            f,
+            "    reg {c}_done_reg : UInt<1>, {c}_clk with : (reset => ({c}_reset, UInt<1>(0)))",
+            c = c
+        )?;
+        writeln!(f, "    {c}_done_reg <= {c}_write_en", c = c)?;
+        writeln!(f, "    when {c}_write_en :", c = c)?;
+        writeln!(f, "      {c}_reg <= {c}_in", c = c)?;
+        writeln!(f, "    {c}_out <= {c}_reg", c = c)?;
+        writeln!(f, "    {c}_done <= {c}_done_reg", c = c)
+    }
+
+    /// Generates a memory with combinational reads and a write latency of
+    /// one cycle. Multi-dimensional memories are flattened in row-major
+    /// order.
+    fn write_mem<F: io::Write>(cell: &ir::Cell, f: &mut F) -> io::Result<()> {
+        let c = cell.name();
+        let width = cell.get_parameter("WIDTH").unwrap();
+        let sizes = if cell.is_primitive(Some("std_mem_d1")) {
+            vec![cell.get_parameter("SIZE").unwrap()]
+        } else {
+            (0..)
+                .map_while(|d| cell.get_parameter(format!("D{}_SIZE", d)))
+                .collect_vec()
+        };
+        let depth: u64 = sizes.iter().product();
+        let addr_width = bits_needed(depth.saturating_sub(1));
+
+        writeln!(f, "    mem {}_mem :", c)?;
+        writeln!(f, "      data-type => UInt<{}>", width)?;
+        writeln!(f, "      depth => {}", depth)?;
+        writeln!(f, "      read-latency => 0")?;
+        writeln!(f, "      write-latency => 1")?;
+        writeln!(f, "      read-under-write => undefined")?;
+        writeln!(f, "      reader => r")?;
+        writeln!(f, "      writer => w")?;
+
+        let addr = sizes.iter().enumerate().skip(1).fold(
+            format!("{}_addr0", c),
+            |acc, (d, size)| {
+                format!(
+                    "add(mul({}, UInt<{}>({})), {}_addr{})",
+                    acc,
+                    bits_needed(*size),
+                    size,
+                    c,
+                    d
+                )
+            },
+        );
+        writeln!(
+            f,
+            "    node {c}_addr = bits(pad({a}, {w}), {m}, 0)",
+            c = c,
+            a = addr,
+            w = addr_width,
+            m = addr_width - 1
+        )?;
+        writeln!(f, "    {c}_mem.r.addr <= {c}_addr", c = c)?;
+        writeln!(f, "    {c}_mem.r.en <= UInt<1>(1)", c = c)?;
+        writeln!(f, "    {c}_mem.r.clk <= {c}_clk", c = c)?;
+        writeln!(f, "    {c}_read_data <= {c}_mem.r.data", c = c)?;
+        writeln!(f, "    {c}_mem.w.addr <= {c}_addr", c = c)?;
+        writeln!(f, "    {c}_mem.w.en <= {c}_write_en", c = c)?;
+        writeln!(f, "    {c}_mem.w.clk <= {c}_clk", c = c)?;
+        writeln!(f, "    {c}_mem.w.data <= {c}_write_data", c = c)?;
+        writeln!(f, "    {c}_mem.w.mask <= UInt<1>(1)", c = c)?;
+        writeln!(f, "    reg {c}_done_reg : UInt<1>, {c}_clk", c = c)?;
+        writeln!(f, "    {c}_done_reg <= {c}_write_en", c = c)?;
+        writeln!(f, "    {c}_done <= {c}_done_reg", c = c)
+    }
+
+    /// Name and definition of the `extmodule` for the primitive `prim` with
+    /// the parameters of `cell`. Each parameter binding gets its own
+    /// `extmodule` because the widths of the ports depend on them.
+    fn extmodule(prim: &str, cell: &ir::Cell) -> (String, String) {
+        let params = match &cell.prototype {
+            ir::CellType::Primitive { param_binding, .. } => {
+                param_binding.iter().collect_vec()
+            }
+            _ => unreachable!("extmodule for a non-primitive cell"),
+        };
+        let module = std::iter::once(prim.to_string())
+            .chain(params.iter().map(|(_, val)| val.to_string()))
+            .join("_");
+
+        let mut def = format!("  extmodule {} :\n", module);
+        for port in &cell.ports {
+            let port = port.borrow();
+            let dir = match port.direction {
+                ir::Direction::Input => "input",
+                _ => "output",
+            };
+            def.push_str(&format!(
+                "    {} {} : {}\n",
+                dir,
+                port.name,
+                port_type(&port)
+            ));
+        }
+        def.push_str(&format!("    defname = {}\n", prim));
+        for (name, val) in params {
+            def.push_str(&format!("    parameter {} = {}\n", name, val));
+        }
+        (module, def)
+    }
+
+    /// Generates a connection that uses `mux` expressions to select the
+    /// source of the first assignment with an active guard. The destination
+    /// is zero when none of the guards are active.
+    /// ```text
+    /// a_in <= mux(foo, UInt<2>("h0"), mux(bar, UInt<2>("h1"), UInt<2>(0)))
+    /// ```
+    /// Clock ports are converted to and from `UInt<1>` when they are not
+    /// driven by a single unguarded clock.
+    fn write_assignment<F: io::Write>(
+        dst: &ir::Port,
+        assignments: &[&ir::Assignment],
+        f: &mut F,
+    ) -> io::Result<()> {
+        if let [asgn] = assignments {
+            let src = asgn.src.borrow();
+            if is_clock(dst)
+                && is_clock(&src)
+                && src.select.is_none()
+                && matches!(*asgn.guard, Guard::True)
+            {
+                return writeln!(
+                    f,
+                    "    {} <= {}",
+                    port_name(dst),
+                    port_name(&src)
+                );
+            }
+        }
+
+        let init = format!("UInt<{}>(0)", dst.width);
+        let rhs = assignments.iter().rfold(init, |acc, asgn| {
+            let src = port_to_expr(&asgn.src.borrow());
+            match &*asgn.guard {
+                Guard::True => src,
+                guard => {
+                    format!("mux({}, {}, {})", guard_to_expr(guard), src, acc)
+                }
+            }
+        });
+        if is_clock(dst) {
+            writeln!(f, "    {} <= asClock({})", port_name(dst), rhs)
+        } else {
+            writeln!(f, "    {} <= {}", port_name(dst), rhs)
+        }
+    }
+}
+
+/// Name of the component port or the wire that represents the port.
+fn port_name(port: &ir::Port) -> String {
+    let parent_ref = port.cell_parent();
+    let parent = parent_ref.borrow();
+    match parent.prototype {
+        ir::CellType::ThisComponent => port.name.to_string(),
+        _ => format!("{}_{}", parent.name(), port.name),
+    }
+}
+
+/// Expression that reads the port as a `UInt`. Bit-selects use `bits`.
+fn port_to_expr(port: &ir::Port) -> String {
+    let parent_ref = port.cell_parent();
+    let parent = parent_ref.borrow();
+    if let ir::CellType::Constant { val, .. } = &parent.prototype {
+        let val = match port.select {
+            Some((_, lsb)) => {
+                let mask = (ir::UBig::from(1u8) << port.width as usize)
+                    - ir::UBig::from(1u8);
+                (val >> lsb as usize) & mask
+            }
+            None => val.clone(),
+        };
+        return format!("UInt<{}>(\"h{:x}\")", port.width, val);
+    }
+
+    let name = port_name(port);
+    let name = if is_clock(port) {
+        format!("asUInt({})", name)
+    } else {
+        name
+    };
+    match port.select {
+        Some((msb, lsb)) => format!("bits({}, {}, {})", name, msb, lsb),
+        None => name,
+    }
+}
+
+fn guard_to_expr(guard: &ir::Guard) -> String {
+    match guard {
+        Guard::Or(l, r) => {
+            format!("or({}, {})", guard_to_expr(l), guard_to_expr(r))
+        }
+        Guard::And(l, r) => {
+            format!("and({}, {})", guard_to_expr(l), guard_to_expr(r))
+        }
+        Guard::Not(g) => format!("not({})", guard_to_expr(g)),
+        Guard::True => "UInt<1>(1)".to_string(),
+        Guard::Port(p) => port_to_expr(&p.borrow()),
+        Guard::CompOp(op, l, r) => {
+            let op = match op {
+                ir::PortComp::Eq => "eq",
+                ir::PortComp::Neq => "neq",
+                ir::PortComp::Gt => "gt",
+                ir::PortComp::Lt => "lt",
+                ir::PortComp::Geq => "geq",
+                ir::PortComp::Leq => "leq",
+            };
+            format!(
+                "{}({}, {})",
+                op,
+                port_to_expr(&l.borrow()),
+                port_to_expr(&r.borrow())
+            )
+        }
+    }
+}
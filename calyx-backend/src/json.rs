@@ -0,0 +1,39 @@
+//! JSON backend for the Calyx compiler.
+//!
+//! Emits the program using the versioned JSON representation defined by
+//! [calyx::ir::json]. The output can be read back using `--input-format json`.
+use crate::traits::Backend;
+use calyx::errors::CalyxResult;
+use calyx::ir;
+use std::io;
+
+#[derive(Default)]
+pub struct JsonBackend;
+
+impl Backend for JsonBackend {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn validate(&self, _prog: &ir::Context) -> CalyxResult<()> {
+        Ok(())
+    }
+
+    /// The primitive definitions are part of the JSON representation.
+    fn link_externs(
+        &self,
+        _prog: &ir::Context,
+        _write: &mut dyn io::Write,
+    ) -> CalyxResult<()> {
+        Ok(())
+    }
+
+    fn emit(
+        &self,
+        prog: &ir::Context,
+        write: &mut dyn io::Write,
+    ) -> CalyxResult<()> {
+        writeln!(write, "{}", prog.to_json())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+//! Backends for the Calyx compiler.
+//!
+//! A [Backend] turns a fully compiled [calyx::ir::Context] into a program in
+//! another language and writes it to any [std::io::Write]:
+//! ```ignore
+//! let backend = calyx_backend::find_backend("verilog").unwrap();
+//! let mut out = Vec::new();
+//! backend.run(&ctx, &mut out)?;
+//! ```
+pub mod firrtl;
+pub mod json;
+pub mod mlir;
+pub mod traits;
+pub mod verilog;
+pub mod xilinx;
+
+pub use traits::Backend;
+
+/// All the backends provided by this crate.
+pub fn backends() -> Vec<Box<dyn Backend>> {
+    vec![
+        Box::new(verilog::VerilogBackend::default()),
+        Box::new(firrtl::FirrtlBackend::default()),
+        Box::new(xilinx::XilinxInterfaceBackend::default()),
+        Box::new(xilinx::XilinxXmlBackend::default()),
+        Box::new(mlir::MlirBackend::default()),
+        Box::new(json::JsonBackend::default()),
+    ]
+}
+
+/// The backend called `name`, if one exists.
+pub fn find_backend(name: &str) -> Option<Box<dyn Backend>> {
+    backends().into_iter().find(|b| b.name() == name)
+}
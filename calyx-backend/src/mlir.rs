@@ -1,12 +1,12 @@
-use calyx::errors::Error;
+use calyx::errors::{CalyxResult, Error};
 use calyx::ir::GetAttributes;
 
-use crate::ir::{self, RRC};
+use calyx::ir::{self, RRC};
 use std::collections::HashMap;
 use std::io;
 use std::rc::Rc;
 
-use super::traits::Backend;
+use crate::traits::Backend;
 
 #[derive(Default)]
 pub struct MlirBackend;
@@ -16,41 +16,99 @@ impl Backend for MlirBackend {
         "mlir"
     }
 
-    fn validate(_prog: &ir::Context) -> calyx::errors::CalyxResult<()> {
+    fn validate(&self, prog: &ir::Context) -> CalyxResult<()> {
+        // The MLIR dialect has no bit-select operation.
+        for comp in &prog.components {
+            let has_select = |assigns: &[ir::Assignment]| {
+                assigns.iter().any(|assign| {
+                    std::iter::once(&assign.src)
+                        .chain(assign.guard.all_ports().iter())
+                        .any(|port| port.borrow().select.is_some())
+                })
+            };
+            let found = has_select(&comp.continuous_assignments)
+                || comp
+                    .groups
+                    .iter()
+                    .any(|group| has_select(&group.borrow().assignments))
+                || comp
+                    .comb_groups
+                    .iter()
+                    .any(|group| has_select(&group.borrow().assignments));
+            if found {
+                return Err(Error::misc(format!(
+                    "Component `{}` uses bit-selects which are not supported by the MLIR backend. Run the `lower-selects` pass first.",
+                    comp.name
+                )));
+            }
+            Self::validate_control(&comp.control.borrow())?;
+        }
         Ok(())
     }
 
     fn emit(
+        &self,
         ctx: &ir::Context,
-        file: &mut calyx::utils::OutputFile,
-    ) -> calyx::errors::CalyxResult<()> {
-        let res = {
-            let f = &mut file.get_write();
-            writeln!(f, "calyx.program \"{}\" {{\n", ctx.entrypoint)?;
-            ctx.components.iter().try_for_each(|comp| {
-                Self::write_component(comp, f)?;
-                writeln!(f)
-            })?;
-            write!(f, "\n}}\n")
-        };
-        res.map_err(|err| {
-            let std::io::Error { .. } = err;
-            Error::write_error(format!(
-                "File not found: {}",
-                file.as_path_string()
-            ))
-        })
+        mut f: &mut dyn io::Write,
+    ) -> CalyxResult<()> {
+        writeln!(f, "calyx.program \"{}\" {{\n", ctx.entrypoint)?;
+        for comp in &ctx.components {
+            Self::write_component(comp, &mut f)?;
+            writeln!(f)?;
+        }
+        write!(f, "\n}}\n")?;
+        Ok(())
     }
 
     fn link_externs(
+        &self,
         _prog: &ir::Context,
-        _write: &mut calyx::utils::OutputFile,
-    ) -> calyx::errors::CalyxResult<()> {
+        _write: &mut dyn io::Write,
+    ) -> CalyxResult<()> {
         Ok(())
     }
 }
 
 impl MlirBackend {
+    /// Returns an error if the control program uses statements that the
+    /// MLIR dialect cannot represent.
+    fn validate_control(control: &ir::Control) -> CalyxResult<()> {
+        match control {
+            ir::Control::StaticSeq(..)
+            | ir::Control::StaticPar(..)
+            | ir::Control::StaticIf(..) => Err(Self::static_control_error()),
+            ir::Control::Seq(ir::Seq { stmts, .. })
+            | ir::Control::Par(ir::Par { stmts, .. }) => {
+                stmts.iter().try_for_each(Self::validate_control)
+            }
+            ir::Control::If(ir::If {
+                tbranch, fbranch, ..
+            }) => {
+                Self::validate_control(tbranch)?;
+                Self::validate_control(fbranch)
+            }
+            ir::Control::While(ir::While { body, .. }) => {
+                Self::validate_control(body)
+            }
+            ir::Control::Repeat(..) => Err(Self::repeat_error()),
+            ir::Control::Enable(..)
+            | ir::Control::Invoke(..)
+            | ir::Control::Empty(..) => Ok(()),
+        }
+    }
+
+    fn repeat_error() -> Error {
+        Error::misc(
+            "The MLIR backend does not support `repeat`. Compile the control using the `compile` alias first.".to_string(),
+        )
+    }
+
+    fn static_control_error() -> Error {
+        Error::misc(
+            "The MLIR backend does not support static control. Run the `compile-static` pass first.".to_string(),
+        )
+    }
+
     fn format_attributes(attrs: &ir::Attributes) -> String {
         if attrs.is_empty() {
             "".to_string()
@@ -86,7 +144,7 @@ impl MlirBackend {
     pub fn write_component<F: io::Write>(
         comp: &ir::Component,
         f: &mut F,
-    ) -> io::Result<()> {
+    ) -> CalyxResult<()> {
         let sig = comp.signature.borrow();
         let (inputs, outputs): (Vec<_>, Vec<_>) =
             sig.ports.iter().map(Rc::clone).partition(|p| {
@@ -133,7 +191,8 @@ impl MlirBackend {
             writeln!(f, "  }}")?;
         }
 
-        write!(f, "}}")
+        write!(f, "}}")?;
+        Ok(())
     }
 
     pub fn write_prototype_sig<F: io::Write>(
@@ -202,7 +261,7 @@ impl MlirBackend {
                     prim => write!(f, "calyx.{} @{}", prim, cell_name)
                 }
             }
-            ir::CellType::Component { name } => {
+            ir::CellType::Component { name, .. } => {
                 write!(f, "calyx.instance @{} of @{}", cell_name, name)
             }
             ir::CellType::Constant { val, .. } => {
@@ -317,7 +376,7 @@ impl MlirBackend {
         control: &ir::Control,
         indent_level: usize,
         f: &mut F,
-    ) -> io::Result<()> {
+    ) -> CalyxResult<()> {
         write!(f, "{}", " ".repeat(indent_level))?;
         match control {
             ir::Control::Enable(ir::Enable { group, .. }) => {
@@ -326,6 +385,12 @@ impl MlirBackend {
             ir::Control::Invoke(ir::Invoke { .. }) => {
                 todo!("invoke operator for MLIR backend")
             }
+            ir::Control::Repeat(..) => return Err(Self::repeat_error()),
+            ir::Control::StaticSeq(..)
+            | ir::Control::StaticPar(..)
+            | ir::Control::StaticIf(..) => {
+                return Err(Self::static_control_error())
+            }
             ir::Control::Seq(ir::Seq { stmts, .. }) => {
                 writeln!(f, "calyx.seq {{")?;
                 for stmt in stmts {
@@ -386,7 +451,8 @@ impl MlirBackend {
         if let Some(attr) = control.get_attributes() {
             write!(f, "{}", Self::format_attributes(attr))?;
         }
-        writeln!(f)
+        writeln!(f)?;
+        Ok(())
     }
 
     /// Get the port access expression.
@@ -395,9 +461,9 @@ impl MlirBackend {
             ir::PortParent::Cell(cell_wref) => {
                 let cell_ref = cell_wref.upgrade();
                 let cell = cell_ref.borrow();
-                match cell.prototype {
+                match &cell.prototype {
                     ir::CellType::Constant { val, width } => {
-                        format!("%{}.out", ir::Cell::constant_name(val, width))
+                        format!("%{}.out", ir::Cell::constant_name(val, *width))
                     }
                     ir::CellType::ThisComponent => {
                         format!("%{}", port.name)
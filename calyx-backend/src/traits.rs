@@ -0,0 +1,45 @@
+//! Interface for a Calyx backend.
+use calyx::ir::traversal::{PassOpt, PassOpts};
+use calyx::{errors::CalyxResult, ir};
+use std::io;
+
+/// A backend for Calyx.
+pub trait Backend {
+    /// The name of this backend.
+    fn name(&self) -> &'static str;
+    /// Options accepted by the backend using `-x <backend>:<key>=<value>`.
+    fn opts(&self) -> Vec<PassOpt> {
+        vec![]
+    }
+    /// Parse the values of the options declared by [Backend::opts] from the
+    /// extra options in the context.
+    fn get_opts(&self, prog: &ir::Context) -> CalyxResult<PassOpts> {
+        PassOpts::parse(self.name(), &self.opts(), &prog.extra_opts)
+    }
+    /// Validate this program for emitting using this backend. Returns an
+    /// Err(..) if the program has unexpected constructs.
+    fn validate(&self, prog: &ir::Context) -> CalyxResult<()>;
+    /// Transforms the program into a formatted string representing a valid
+    /// and write it to `write`.
+    fn emit(
+        &self,
+        prog: &ir::Context,
+        write: &mut dyn io::Write,
+    ) -> CalyxResult<()>;
+    /// Link the extern collected while parsing the program.
+    fn link_externs(
+        &self,
+        prog: &ir::Context,
+        write: &mut dyn io::Write,
+    ) -> CalyxResult<()>;
+    /// Convience function to validate and emit the program.
+    fn run(
+        &self,
+        prog: &ir::Context,
+        write: &mut dyn io::Write,
+    ) -> CalyxResult<()> {
+        self.validate(prog)?;
+        self.link_externs(prog, write)?;
+        self.emit(prog, write)
+    }
+}
@@ -1,26 +1,47 @@
 //! SystemVerilog backend for the Calyx compiler.
 //!
-//! Transforms an [`ir::Context`](crate::ir::Context) into a formatted string that represents a
+//! Transforms an [`ir::Context`](calyx::ir::Context) into a formatted string that represents a
 //! valid SystemVerilog program.
 
-use crate::backend::traits::Backend;
+use crate::traits::Backend;
 use calyx::{
-    errors::{CalyxResult, Error},
-    ir,
-    utils::OutputFile,
+    errors::{CalyxResult, Error, Span, WithPos},
+    ir::{
+        self,
+        traversal::{ParseVal, PassOpt},
+    },
 };
 use ir::{Control, Group, Guard, RRC};
 use itertools::Itertools;
 use std::fs::File;
 use std::io;
 use std::{collections::HashMap, rc::Rc};
-use vast::v17::ast as v;
+use vast::{v05::ast as v05, v17::ast as v};
 
 /// Implements a simple Verilog backend. The backend only accepts Calyx programs with no control
 /// and no groups.
 #[derive(Default)]
 pub struct VerilogBackend;
 
+/// Values of the options of [VerilogBackend].
+struct VerilogOpts {
+    /// Omit the memory initialization and the guard checks which are only
+    /// meaningful in simulation.
+    synthesis: bool,
+    /// Check that the guards of the assignments to a port are disjoint.
+    verify: bool,
+    /// Initialize the input ports of cells to zero.
+    init: bool,
+    /// Annotate the generated code with source locations.
+    src_locations: bool,
+    /// Prefix added to the module names of components.
+    prefix: String,
+    /// Generate SystemVerilog instead of Verilog-2005.
+    systemverilog: bool,
+    /// The reset of the entrypoint is asynchronous.
+    async_reset: bool,
+}
+
 /// Checks to make sure that there are no holes being
 /// used in a guard.
 fn validate_guard(guard: &ir::Guard) -> bool {
@@ -38,7 +59,7 @@ fn validate_guard(guard: &ir::Guard) -> bool {
 }
 
 /// Returns `Ok` if there are no groups defined.
-fn validate_structure<'a, I>(groups: I) -> CalyxResult<()>
+pub(crate) fn validate_structure<'a, I>(groups: I) -> CalyxResult<()>
 where
     I: Iterator<Item = &'a RRC<Group>>,
 {
@@ -67,7 +88,7 @@ where
 
 /// Returns `Ok` if the control for `comp` is either a single `enable`
 /// or `empty`.
-fn validate_control(ctrl: &ir::Control) -> CalyxResult<()> {
+pub(crate) fn validate_control(ctrl: &ir::Control) -> CalyxResult<()> {
     match ctrl {
         Control::Empty(_) => Ok(()),
         c => Err(Error::malformed_structure(
@@ -82,7 +103,55 @@ impl Backend for VerilogBackend {
         "verilog"
     }
 
-    fn validate(ctx: &ir::Context) -> CalyxResult<()> {
+    fn opts(&self) -> Vec<PassOpt> {
+        vec![
+            PassOpt::new(
+                "synthesis",
+                "omit simulation-only code such as memory initialization",
+                ParseVal::Bool(false),
+            ),
+            PassOpt::new(
+                "verify",
+                "check that the guards of assignments to a port are disjoint",
+                ParseVal::Bool(true),
+            ),
+            PassOpt::new(
+                "init",
+                "initialize the input ports of cells to zero",
+                ParseVal::Bool(true),
+            ),
+            PassOpt::new(
+                "src-locations",
+                "annotate the code with the source locations of cells and assignments",
+                ParseVal::Bool(false),
+            ),
+            PassOpt::new(
+                "prefix",
+                "prefix added to the module names of components",
+                ParseVal::String(String::new()),
+            ),
+            PassOpt::new(
+                "systemverilog",
+                "generate SystemVerilog instead of synthesizable Verilog-2005",
+                ParseVal::Bool(true),
+            ),
+            PassOpt::new(
+                "reset",
+                "reset style of the entrypoint, either `sync` or `async`",
+                ParseVal::String("sync".to_string()),
+            ),
+        ]
+    }
+
+    fn validate(&self, ctx: &ir::Context) -> CalyxResult<()> {
+        let opts = self.get_opts(ctx)?;
+        let reset = opts["reset"].string();
+        if reset != "sync" && reset != "async" {
+            return Err(Error::misc(format!(
+                "Unknown reset style `{}' for `verilog'. Valid options are `sync` or `async`",
+                reset
+            )));
+        }
         for component in &ctx.components {
             validate_structure(component.groups.iter())?;
             validate_control(&component.control.borrow())?;
@@ -94,56 +163,64 @@ impl Backend for VerilogBackend {
     /// A possible alternative in the future is to use SystemVerilog `include`
     /// statement.
     fn link_externs(
+        &self,
         ctx: &ir::Context,
-        file: &mut OutputFile,
+        write: &mut dyn io::Write,
     ) -> CalyxResult<()> {
         for extern_path in ctx.lib.extern_paths() {
-            // The extern file is guaranteed to exist by the frontend.
-            let mut ext = File::open(extern_path).unwrap();
-            io::copy(&mut ext, &mut file.get_write()).map_err(|err| {
-                let std::io::Error { .. } = err;
-                Error::write_error(format!(
-                    "File not found: {}",
-                    file.as_path_string()
+            let mut ext = File::open(extern_path).map_err(|err| {
+                Error::invalid_file(format!(
+                    "Failed to open {}: {}",
+                    extern_path.display(),
+                    err
                 ))
             })?;
+            io::copy(&mut ext, write)?;
         }
         Ok(())
     }
 
-    fn emit(ctx: &ir::Context, file: &mut OutputFile) -> CalyxResult<()> {
+    fn emit(
+        &self,
+        ctx: &ir::Context,
+        write: &mut dyn io::Write,
+    ) -> CalyxResult<()> {
+        let opts = self.get_opts(ctx)?;
+        let systemverilog = opts["systemverilog"].bool();
+        let opts = VerilogOpts {
+            // The simulation-only code requires SystemVerilog.
+            synthesis: opts["synthesis"].bool() || !systemverilog,
+            verify: opts["verify"].bool(),
+            init: opts["init"].bool() && systemverilog,
+            src_locations: opts["src-locations"].bool(),
+            prefix: opts["prefix"].string().to_string(),
+            systemverilog,
+            async_reset: opts["reset"].string() == "async",
+        };
         let modules = &ctx
             .components
             .iter()
             .map(|comp| {
-                emit_component(
-                    comp,
-                    ctx.bc.synthesis_mode,
-                    ctx.bc.enable_verification,
-                    ctx.bc.initialize_inputs,
-                )
-                .to_string()
+                let mut module = emit_component(comp, &opts);
+                if opts.async_reset && comp.name == ctx.entrypoint {
+                    synchronize_reset(comp, &mut module, opts.systemverilog);
+                }
+                if opts.systemverilog {
+                    module.to_string()
+                } else {
+                    to_verilog_2005(module).to_string()
+                }
             })
             .collect::<Vec<_>>();
 
-        write!(file.get_write(), "{}", modules.join("\n")).map_err(|err| {
-            let std::io::Error { .. } = err;
-            Error::write_error(format!(
-                "File not found: {}",
-                file.as_path_string()
-            ))
-        })?;
+        write!(write, "{}", modules.join("\n"))?;
         Ok(())
     }
 }
 
-fn emit_component(
-    comp: &ir::Component,
-    synthesis_mode: bool,
-    enable_verification: bool,
-    initialize_inputs: bool,
-) -> v::Module {
-    let mut module = v::Module::new(comp.name.as_ref());
+fn emit_component(comp: &ir::Component, opts: &VerilogOpts) -> v::Module {
+    let mut module =
+        v::Module::new(&format!("{}{}", opts.prefix, comp.name.as_ref()));
     let sig = comp.signature.borrow();
     for port_ref in &sig.ports {
         let port = port_ref.borrow();
@@ -162,7 +239,7 @@ fn emit_component(
     }
 
     // Add memory initial and final blocks
-    if !synthesis_mode {
+    if !opts.synthesis {
         memory_read_write(comp).into_iter().for_each(|stmt| {
             module.add_stmt(stmt);
         });
@@ -179,7 +256,9 @@ fn emit_component(
     });
 
     // Generate initial assignments for all input ports in defined cells.
-    if initialize_inputs {
+    // Combinational components drive all their cells using continuous
+    // assignments and do not need them.
+    if opts.init && !comp.is_comb {
         let mut initial = v::ParallelProcess::new_initial();
         wires.iter().for_each(|(name, width, dir)| {
             if *dir == ir::Direction::Input {
@@ -196,12 +275,17 @@ fn emit_component(
     }
 
     // cell instances
-    comp.cells
-        .iter()
-        .filter_map(|cell| cell_instance(&cell.borrow()))
-        .for_each(|instance| {
+    for cell in comp.cells.iter() {
+        let cell = cell.borrow();
+        if let Some(instance) = cell_instance(&cell, &opts.prefix) {
+            if opts.src_locations {
+                if let Some(loc) = src_locations(vec![cell.copy_span()]) {
+                    module.add_stmt(loc);
+                }
+            }
             module.add_instance(instance);
-        });
+        }
+    }
 
     // gather assignments keyed by destination
     let mut map: HashMap<_, (RRC<ir::Port>, Vec<_>)> = HashMap::new();
@@ -217,21 +301,166 @@ fn emit_component(
     map.values()
         .sorted_by_key(|(port, _)| port.borrow().canonical())
         .for_each(|asgns| {
+            if opts.src_locations {
+                let spans = asgns.1.iter().map(|a| a.attributes.copy_span());
+                if let Some(loc) = src_locations(spans.collect()) {
+                    module.add_stmt(loc);
+                }
+            }
             module.add_stmt(v::Stmt::new_parallel(emit_assignment(asgns)));
             // If verification generation is enabled, emit disjointness check.
-            if enable_verification {
+            if opts.verify {
                 if let Some(check) = emit_guard_disjoint_check(asgns) {
                     checks.add_seq(check);
                 };
             }
         });
 
-    if !synthesis_mode {
+    if !opts.synthesis {
         module.add_process(checks);
     }
     module
 }
 
+/// Converts a module that only uses synthesizable constructs into
+/// Verilog-2005 by declaring its ports and signals as wires.
+fn to_verilog_2005(module: v::Module) -> v05::Module {
+    let decl = |decl: v::Decl| match decl {
+        v::Decl::Logic(name, v::Ty::Width(width)) => {
+            v05::Decl::new_wire(&name, width)
+        }
+        d => unreachable!("Unexpected declaration in Verilog-2005: {:?}", d),
+    };
+    let mut out = v05::Module::new(&module.name);
+    for port in module.ports {
+        out.add_port(match port {
+            v::Port::Input(d) => v05::Port::Input(decl(d)),
+            v::Port::Output(d) => v05::Port::Output(decl(d)),
+        });
+    }
+    for stmt in module.body {
+        match stmt {
+            v::Stmt::Decl(d) => out.add_decl(decl(d)),
+            v::Stmt::Parallel(v::Parallel::Inst(inst)) => {
+                out.add_instance(inst)
+            }
+            v::Stmt::Parallel(v::Parallel::ParAssign(lhs, rhs)) => {
+                out.add_stmt(v05::Parallel::Assign(lhs, rhs))
+            }
+            v::Stmt::Parallel(v::Parallel::Process(_)) => {
+                unreachable!("Processes are only generated for SystemVerilog")
+            }
+            v::Stmt::RawStr(s) => out.add_stmt(v05::Stmt::RawStr(s)),
+        }
+    }
+    out
+}
+
+/// Synchronizes the deassertion of the asynchronous reset of `comp` with its
+/// clock. Cells and assignments in the component use the synchronized reset:
+/// ```verilog
+/// logic [1:0] reset_sync;
+/// always_ff @(posedge clk or posedge reset) begin
+///     if (reset) reset_sync <= 2'b11;
+///     else reset_sync <= {reset_sync[0], 1'b0};
+/// end
+/// ```
+fn synchronize_reset(
+    comp: &ir::Component,
+    module: &mut v::Module,
+    systemverilog: bool,
+) {
+    let sig = comp.signature.borrow();
+    let (clk, reset) =
+        match (sig.find_with_attr("clk"), sig.find_with_attr("reset")) {
+            (Some(clk), Some(reset)) => (clk, reset),
+            _ => return,
+        };
+    let clk = clk.borrow().name.to_string();
+    let reset = reset.borrow().name.to_string();
+    let synced = "reset_sync[1]";
+
+    for stmt in &mut module.body {
+        match stmt {
+            v::Stmt::Parallel(v::Parallel::ParAssign(_, rhs)) => {
+                rename_ref(rhs, &reset, synced)
+            }
+            v::Stmt::Parallel(v::Parallel::Inst(inst)) => {
+                inst.ports
+                    .values_mut()
+                    .for_each(|e| rename_ref(e, &reset, synced));
+            }
+            _ => (),
+        }
+    }
+
+    let (decl, always) = if systemverilog {
+        ("logic", "always_ff")
+    } else {
+        ("reg", "always")
+    };
+    let lines = [
+        format!("{} [1:0] reset_sync;", decl),
+        format!("{} @(posedge {} or posedge {}) begin", always, clk, reset),
+        format!("    if ({}) reset_sync <= 2'b11;", reset),
+        "    else reset_sync <= {reset_sync[0], 1'b0};".to_string(),
+        "end".to_string(),
+    ];
+    module
+        .body
+        .splice(0..0, lines.into_iter().map(v::Stmt::new_rawstr));
+}
+
+/// Replaces the references to `from` in `expr` with `to`.
+fn rename_ref(expr: &mut v::Expr, from: &str, to: &str) {
+    let rename = |e: &mut Rc<v::Expr>| rename_ref(Rc::make_mut(e), from, to);
+    match expr {
+        v::Expr::Ref(name) if name == from => *name = to.to_string(),
+        v::Expr::Signed(e) | v::Expr::Unop(_, e) | v::Expr::Repeat(_, e) => {
+            rename(e)
+        }
+        v::Expr::Binop(_, l, r) => {
+            rename(l);
+            rename(r);
+        }
+        v::Expr::Terop(_, a, b, c) => {
+            rename(a);
+            rename(b);
+            rename(c);
+        }
+        v::Expr::Concat(c) => {
+            c.exprs.iter_mut().for_each(|e| rename_ref(e, from, to))
+        }
+        v::Expr::Call(_, args) => {
+            args.iter_mut().for_each(|e| rename_ref(e, from, to))
+        }
+        v::Expr::Ref(_)
+        | v::Expr::Int(_)
+        | v::Expr::ULit(..)
+        | v::Expr::Str(_)
+        | v::Expr::IPath(..) => (),
+    }
+}
+
+/// Generates a comment with the distinct source locations in `spans`:
+/// ```verilog
+/// // src: main.futil:12, main.futil:20
+/// ```
+/// Returns `None` if none of the spans are known.
+fn src_locations(spans: Vec<Option<Span>>) -> Option<v::Stmt> {
+    let locs = spans
+        .into_iter()
+        .flatten()
+        .map(|span| format!("{}:{}", span.file(), span.line_col().0))
+        .unique()
+        .collect_vec();
+    if locs.is_empty() {
+        None
+    } else {
+        Some(v::Stmt::new_rawstr(format!("// src: {}", locs.join(", "))))
+    }
+}
+
 fn wire_decls(cell: &ir::Cell) -> Vec<(String, u64, ir::Direction)> {
     cell.ports
         .iter()
@@ -258,11 +487,17 @@ fn wire_decls(cell: &ir::Cell) -> Vec<(String, u64, ir::Direction)> {
         .collect()
 }
 
-fn cell_instance(cell: &ir::Cell) -> Option<v::Instance> {
+/// Instances of components use the module names with `prefix`.
+fn cell_instance(cell: &ir::Cell, prefix: &str) -> Option<v::Instance> {
     match cell.type_name() {
         Some(ty_name) => {
-            let mut inst =
-                v::Instance::new(cell.name().as_ref(), ty_name.as_ref());
+            let module = match cell.prototype {
+                ir::CellType::Component { .. } => {
+                    format!("{}{}", prefix, ty_name.as_ref())
+                }
+                _ => ty_name.to_string(),
+            };
+            let mut inst = v::Instance::new(cell.name().as_ref(), &module);
 
             if let ir::CellType::Primitive {
                 name,
@@ -373,21 +608,41 @@ fn emit_assignment(
     v::Parallel::ParAssign(port_to_ref(Rc::clone(dst_ref)), rhs)
 }
 
+/// Bit-selects are emitted as native Verilog part-selects.
 fn port_to_ref(port_ref: RRC<ir::Port>) -> v::Expr {
     let port = port_ref.borrow();
+    let select = match port.select {
+        Some((msb, lsb)) if msb == lsb => format!("[{}]", msb),
+        Some((msb, lsb)) => format!("[{}:{}]", msb, lsb),
+        None => String::new(),
+    };
     match &port.parent {
         ir::PortParent::Cell(cell) => {
             let parent_ref = cell.upgrade();
             let parent = parent_ref.borrow();
-            match parent.prototype {
-                ir::CellType::Constant { val, width } => {
-                    v::Expr::new_ulit_dec(width as u32, &val.to_string())
+            match &parent.prototype {
+                ir::CellType::Constant { val, width } => match port.select {
+                    Some((_, lsb)) => {
+                        let mask = (ir::UBig::from(1u8) << port.width as usize)
+                            - ir::UBig::from(1u8);
+                        let val = (val >> lsb as usize) & mask;
+                        v::Expr::new_ulit_dec(
+                            port.width as u32,
+                            &val.to_string(),
+                        )
+                    }
+                    None => {
+                        v::Expr::new_ulit_dec(*width as u32, &val.to_string())
+                    }
+                },
+                ir::CellType::ThisComponent => {
+                    v::Expr::Ref(format!("{}{}", port.name.as_ref(), select))
                 }
-                ir::CellType::ThisComponent => v::Expr::new_ref(&port.name),
                 _ => v::Expr::Ref(format!(
-                    "{}_{}",
+                    "{}_{}{}",
                     parent.name().as_ref(),
-                    port.name.as_ref()
+                    port.name.as_ref(),
+                    select
                 )),
             }
         }
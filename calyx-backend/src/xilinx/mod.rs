@@ -0,0 +1,30 @@
+//! Backend for generating synthesiable code for Xilinx FPGAs
+mod axi;
+mod axi_address_space;
+mod control_axi;
+mod fsm;
+mod memory_axi;
+mod toplevel;
+mod utils;
+mod xml;
+
+use calyx::ir::traversal::{ParseVal, PassOpt};
+
+pub use toplevel::XilinxInterfaceBackend;
+pub use xml::XilinxXmlBackend;
+
+/// Options for the AXI control interface shared by the Xilinx backends.
+fn axi_control_opts() -> Vec<PassOpt> {
+    vec![
+        PassOpt::new(
+            "addr-width",
+            "address width of the AXI control interface",
+            ParseVal::Num(12),
+        ),
+        PassOpt::new(
+            "data-width",
+            "data width of the AXI control interface",
+            ParseVal::Num(32),
+        ),
+    ]
+}
@@ -2,11 +2,12 @@ use super::{
     axi, control_axi::ControlInterface, fsm, memory_axi::bram,
     memory_axi::MemoryInterface, utils,
 };
-use crate::backend::traits::Backend;
+use crate::traits::Backend;
 use calyx::{
     errors::{CalyxResult, Error},
-    ir,
+    ir::{self, traversal::PassOpt},
 };
+use std::io;
 use vast::v05::ast as v;
 
 /// A backend that generates the Xilinx interfacing for a Calyx program.
@@ -15,23 +16,30 @@ pub struct XilinxInterfaceBackend;
 
 impl Backend for XilinxInterfaceBackend {
     fn name(&self) -> &'static str {
-        "xilinx-axi"
+        "xilinx"
     }
 
-    fn validate(_ctx: &ir::Context) -> CalyxResult<()> {
+    fn opts(&self) -> Vec<PassOpt> {
+        super::axi_control_opts()
+    }
+
+    fn validate(&self, ctx: &ir::Context) -> CalyxResult<()> {
+        self.get_opts(ctx)?;
         Ok(())
     }
 
     fn link_externs(
+        &self,
         _lib: &ir::Context,
-        _write: &mut calyx::utils::OutputFile,
+        _write: &mut dyn io::Write,
     ) -> CalyxResult<()> {
         Ok(())
     }
 
     fn emit(
+        &self,
         prog: &ir::Context,
-        file: &mut calyx::utils::OutputFile,
+        write: &mut dyn io::Write,
     ) -> CalyxResult<()> {
         let toplevel = prog
             .components
@@ -46,10 +54,18 @@ impl Backend for XilinxInterfaceBackend {
                     " Please make sure that at least one memory is marked as @external."));
         }
 
+        let opts = self.get_opts(prog)?;
+        let addr_width = opts["addr-width"].num();
+        let data_width = opts["data-width"].num();
         let mut modules = vec![
-            top_level(12, 32, &memories),
+            top_level(addr_width, data_width, &memories),
             bram(32, 32, 5),
-            axi::AxiInterface::control_module("Control_axi", 12, 32, &memories),
+            axi::AxiInterface::control_module(
+                "Control_axi",
+                addr_width,
+                data_width,
+                &memories,
+            ),
         ];
 
         for (i, _mem) in memories.iter().enumerate() {
@@ -68,7 +84,7 @@ impl Backend for XilinxInterfaceBackend {
             .join("\n");
 
         write!(
-            file.get_write(),
+            write,
             r#"`default_nettype none
 /* verilator lint_off DECLFILENAME */
 {}`default_nettype wire"#,
@@ -84,7 +100,7 @@ fn external_memories(comp: &ir::Component) -> Vec<String> {
     comp.cells
         .iter()
         .filter(|cell_ref| {
-            matches!(cell_ref.borrow().get_attribute("external"), Some(&1))
+            matches!(cell_ref.borrow().get_attribute("external"), Some(1))
         })
         .map(|cell_ref| cell_ref.borrow().name().to_string())
         .collect()
@@ -1,6 +1,10 @@
-use crate::backend::traits::Backend;
-use calyx::{errors::CalyxResult, ir};
+use crate::traits::Backend;
+use calyx::{
+    errors::{CalyxResult, Error},
+    ir::{self, traversal::PassOpt},
+};
 use serde::Serialize;
+use std::io;
 
 /// Backend that generates XML that Xilinx needs to define the address
 /// space for a kernel.
@@ -83,20 +87,33 @@ impl Backend for XilinxXmlBackend {
         "xilinx-xml"
     }
 
-    fn validate(_ctx: &ir::Context) -> CalyxResult<()> {
+    fn opts(&self) -> Vec<PassOpt> {
+        super::axi_control_opts()
+    }
+
+    fn validate(&self, ctx: &ir::Context) -> CalyxResult<()> {
+        let addr_width = self.get_opts(ctx)?["addr-width"].num();
+        if addr_width >= 64 {
+            return Err(Error::misc(format!(
+                "Address width {} of the AXI control interface must be less than 64",
+                addr_width
+            )));
+        }
         Ok(())
     }
 
     fn link_externs(
+        &self,
         _prog: &ir::Context,
-        _write: &mut calyx::utils::OutputFile,
+        _write: &mut dyn io::Write,
     ) -> CalyxResult<()> {
         Ok(())
     }
 
     fn emit(
+        &self,
         prog: &ir::Context,
-        file: &mut calyx::utils::OutputFile,
+        write: &mut dyn io::Write,
     ) -> CalyxResult<()> {
         let toplevel = prog
             .components
@@ -104,11 +121,13 @@ impl Backend for XilinxXmlBackend {
             .find(|comp| comp.name == prog.entrypoint)
             .unwrap();
 
+        let opts = self.get_opts(prog)?;
+        let range = format!("{:#x}", 1u64 << opts["addr-width"].num());
         let mut ports = vec![Port {
             name: "s_axi_control",
             mode: "slave",
-            range: "0x1000",
-            data_width: 32,
+            range: &range,
+            data_width: opts["data-width"].num(),
             port_type: "addressable",
             base: "0x0",
         }];
@@ -129,7 +148,7 @@ impl Backend for XilinxXmlBackend {
             .cells
             .iter()
             .filter(|cell_ref| {
-                matches!(cell_ref.borrow().get_attribute("external"), Some(&1))
+                matches!(cell_ref.borrow().get_attribute("external"), Some(1))
             })
             .enumerate()
             .map(|(i, cell_ref)| {
@@ -185,7 +204,7 @@ impl Backend for XilinxXmlBackend {
             },
         };
         write!(
-            file.get_write(),
+            write,
             "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}",
             quick_xml::se::to_string(&root).expect("XML Serialization failed")
         )?;
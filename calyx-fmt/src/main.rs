@@ -0,0 +1,72 @@
+//! Formatter for Calyx programs.
+//!
+//! Reformats files while preserving their comments. With `--check`, reports
+//! the files that are not formatted instead.
+use argh::FromArgs;
+use calyx::errors::CalyxResult;
+use calyx::frontend::format_source;
+use std::path::PathBuf;
+use std::{fs, io::Read, process};
+
+#[derive(FromArgs)]
+/// Format Calyx programs.
+struct Opts {
+    /// files to format. Reads from stdin when no files are given.
+    #[argh(positional)]
+    files: Vec<PathBuf>,
+
+    /// do not format the files but report the ones that are not formatted
+    #[argh(switch)]
+    check: bool,
+
+    /// overwrite the files with their formatted versions
+    #[argh(switch, short = 'i')]
+    in_place: bool,
+}
+
+fn read(file: &Option<PathBuf>) -> CalyxResult<String> {
+    let mut buf = String::new();
+    match file {
+        Some(path) => buf = fs::read_to_string(path)?,
+        None => {
+            std::io::stdin().read_to_string(&mut buf)?;
+        }
+    }
+    Ok(buf)
+}
+
+fn main() -> CalyxResult<()> {
+    let opts: Opts = argh::from_env();
+    let files: Vec<Option<PathBuf>> = if opts.files.is_empty() {
+        vec![None]
+    } else {
+        opts.files.into_iter().map(Some).collect()
+    };
+
+    let mut unformatted = false;
+    for file in files {
+        let source = read(&file)?;
+        let name = file
+            .as_ref()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_else(|| "<stdin>".to_string());
+        let formatted = format_source(&source, name.as_ref())?;
+        if opts.check {
+            if formatted != source {
+                println!("{}", name);
+                unformatted = true;
+            }
+        } else if let (true, Some(path)) = (opts.in_place, &file) {
+            if formatted != source {
+                fs::write(path, formatted)?;
+            }
+        } else {
+            print!("{}", formatted);
+        }
+    }
+
+    if unformatted {
+        process::exit(1);
+    }
+    Ok(())
+}
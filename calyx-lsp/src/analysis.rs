@@ -0,0 +1,410 @@
+//! Queries on the AST of a [Workspace] used to answer editor requests.
+use calyx::{
+    errors::{Span, WithPos},
+    frontend::{ast, Workspace},
+    ir::{self, Direction, Id, PortDef, Width},
+};
+
+/// A port on a cell along with its width under the cell's parameters.
+pub struct PortInfo {
+    pub name: Id,
+    pub width: Width,
+    pub direction: Direction,
+}
+
+impl PortInfo {
+    /// Describe this port like a signature entry.
+    pub fn describe(&self) -> String {
+        let dir = match self.direction {
+            Direction::Input => "input",
+            Direction::Output => "output",
+            Direction::Inout => "inout",
+        };
+        format!("{} {}: {}", dir, self.name, self.width)
+    }
+}
+
+/// The identifier under the cursor.
+pub enum Symbol<'a> {
+    /// A plain name such as a cell, group, or component.
+    Name(&'a str),
+    /// `cell.port`
+    Port { cell: &'a str, port: &'a str },
+}
+
+fn is_ident_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_' || ch == '-' || ch == '\''
+}
+
+/// Start of the identifier that ends at `offset`.
+fn ident_start(text: &str, offset: usize) -> usize {
+    text[..offset]
+        .rfind(|ch| !is_ident_char(ch))
+        .map(|idx| idx + 1)
+        .unwrap_or(0)
+}
+
+/// Find the symbol at byte `offset` in `text`.
+pub fn symbol_at(text: &str, offset: usize) -> Option<Symbol<'_>> {
+    let start = ident_start(text, offset);
+    let end = text[offset..]
+        .find(|ch| !is_ident_char(ch))
+        .map(|idx| offset + idx)
+        .unwrap_or(text.len());
+    if start == end {
+        return None;
+    }
+    let name = &text[start..end];
+    match cell_before_dot(text, start) {
+        Some(cell) => Some(Symbol::Port { cell, port: name }),
+        None => Some(Symbol::Name(name)),
+    }
+}
+
+/// If the identifier starting at `start` is preceded by `cell.`, return the
+/// name of the cell.
+fn cell_before_dot(text: &str, start: usize) -> Option<&str> {
+    let dot = start.checked_sub(1)?;
+    if &text[dot..start] != "." {
+        return None;
+    }
+    let cell_start = ident_start(text, dot);
+    if cell_start == dot {
+        None
+    } else {
+        Some(&text[cell_start..dot])
+    }
+}
+
+/// Name of the cell whose ports are being completed at byte `offset`, i.e.,
+/// the text before the cursor looks like `cell.` or `cell.pa`.
+pub fn completion_cell(text: &str, offset: usize) -> Option<&str> {
+    cell_before_dot(text, ident_start(text, offset))
+}
+
+/// The component defined in `file` that contains byte `offset`. Components
+/// are ordered in the file so this is the last one that starts before
+/// `offset`.
+pub fn enclosing_component<'a>(
+    ws: &'a Workspace,
+    file: &str,
+    offset: usize,
+) -> Option<&'a ast::ComponentDef> {
+    ws.components
+        .iter()
+        .filter_map(|comp| {
+            let span = comp.name.copy_span()?;
+            (span.file() == file && span.range().0 <= offset)
+                .then(|| (span.range().0, comp))
+        })
+        .max_by_key(|(start, _)| *start)
+        .map(|(_, comp)| comp)
+}
+
+fn find_component<'a>(
+    ws: &'a Workspace,
+    name: &str,
+) -> Option<&'a ast::ComponentDef> {
+    ws.components
+        .iter()
+        .chain(ws.declarations.iter())
+        .find(|comp| comp.name == name)
+}
+
+fn find_primitive<'a>(
+    ws: &'a Workspace,
+    name: &str,
+) -> Option<&'a ir::Primitive> {
+    ws.externs
+        .iter()
+        .flat_map(|(_, prims)| prims.iter())
+        .find(|prim| prim.name == name)
+}
+
+/// Instantiate the ports in `signature` using the `args` bound to `params`.
+fn instantiate(
+    signature: &[PortDef],
+    params: &[Id],
+    args: &[Width],
+) -> Vec<PortInfo> {
+    signature
+        .iter()
+        .map(|pd| {
+            let width = match &pd.width {
+                Width::Param { value } => params
+                    .iter()
+                    .position(|param| param == value)
+                    .and_then(|idx| args.get(idx))
+                    .cloned()
+                    .unwrap_or_else(|| pd.width.clone()),
+                Width::Const { .. } => pd.width.clone(),
+            };
+            PortInfo {
+                name: pd.name.clone(),
+                width,
+                direction: pd.direction.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Ports of the component `comp` as seen from outside it.
+fn component_ports(comp: &ast::ComponentDef, args: &[Width]) -> Vec<PortInfo> {
+    let mut signature = comp.signature.clone();
+    if !comp.is_comb {
+        ir::from_ast::extend_signature(&mut signature);
+    }
+    instantiate(&signature, &comp.params, args)
+}
+
+/// Ports of the cell named `cell` defined in `comp`.
+pub fn cell_ports(
+    ws: &Workspace,
+    comp: &ast::ComponentDef,
+    cell: &str,
+) -> Option<Vec<PortInfo>> {
+    let cell = comp.cells.iter().find(|c| c.name == cell)?;
+    let proto = &cell.prototype;
+    if let Some(prim) = find_primitive(ws, proto.name.as_ref()) {
+        Some(instantiate(&prim.signature, &prim.params, &proto.params))
+    } else {
+        find_component(ws, proto.name.as_ref())
+            .map(|def| component_ports(def, &proto.params))
+    }
+}
+
+/// Ports of `comp` as seen from inside it.
+fn this_ports(comp: &ast::ComponentDef) -> Vec<PortInfo> {
+    let mut signature = comp.signature.clone();
+    if !comp.is_comb {
+        ir::from_ast::extend_signature(&mut signature);
+    }
+    signature
+        .into_iter()
+        .map(|pd| PortInfo {
+            name: pd.name,
+            width: pd.width,
+            direction: pd.direction,
+        })
+        .collect()
+}
+
+/// Find the port referred to by `symbol` in `comp`.
+pub fn resolve_port(
+    ws: &Workspace,
+    comp: &ast::ComponentDef,
+    symbol: &Symbol,
+) -> Option<PortInfo> {
+    match symbol {
+        Symbol::Port { cell, port } => cell_ports(ws, comp, cell)?
+            .into_iter()
+            .find(|p| p.name == *port),
+        Symbol::Name(name) => {
+            this_ports(comp).into_iter().find(|p| p.name == *name)
+        }
+    }
+}
+
+/// Find the location of the definition of `symbol` used in `comp`.
+pub fn definition(
+    ws: &Workspace,
+    comp: &ast::ComponentDef,
+    symbol: &Symbol,
+) -> Option<Span> {
+    match symbol {
+        Symbol::Port { cell, port } => {
+            // Ports are defined in the signature of the cell's prototype.
+            let proto = &comp.cells.iter().find(|c| c.name == *cell)?.prototype;
+            let signature = match find_primitive(ws, proto.name.as_ref()) {
+                Some(prim) => &prim.signature,
+                None => &find_component(ws, proto.name.as_ref())?.signature,
+            };
+            signature
+                .iter()
+                .find(|pd| pd.name == *port)
+                .and_then(|pd| pd.name.copy_span())
+        }
+        Symbol::Name(name) => {
+            let cells = comp.cells.iter().map(|c| &c.name);
+            let groups = comp.groups.iter().map(|g| &g.name);
+            let ports = comp.signature.iter().map(|pd| &pd.name);
+            let comps = ws
+                .components
+                .iter()
+                .chain(ws.declarations.iter())
+                .map(|c| &c.name);
+            let prims = ws
+                .externs
+                .iter()
+                .flat_map(|(_, prims)| prims.iter().map(|p| &p.name));
+            cells
+                .chain(groups)
+                .chain(ports)
+                .chain(comps)
+                .chain(prims)
+                .find(|id| *id == *name)
+                .and_then(|id| id.copy_span())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    const FILE: &str = "test.futil";
+    const SRC: &str = r#"import "primitives/core.futil";
+component add_one(in: 32) -> (out: 32) {
+  cells {
+    add = std_add(32);
+  }
+  wires {
+    add.left = in;
+    add.right = 32'd1;
+    out = add.out;
+  }
+  control {}
+}
+component main() -> () {
+  cells {
+    r = std_reg(32);
+    a = add_one();
+  }
+  wires {
+    group upd {
+      r.in = a.out;
+      r.write_en = 1'd1;
+      upd[done] = r.done;
+    }
+  }
+  control { upd; }
+}
+"#;
+
+    fn workspace() -> Workspace {
+        let lib = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        Workspace::construct_from_source(SRC, Path::new(FILE), &lib).unwrap()
+    }
+
+    /// Offset of the `nth` occurrence of `pat` in [SRC].
+    fn offset_of(pat: &str, nth: usize) -> usize {
+        SRC.match_indices(pat).nth(nth).unwrap().0
+    }
+
+    fn main_comp(ws: &Workspace) -> &ast::ComponentDef {
+        enclosing_component(ws, FILE, offset_of("upd;", 0)).unwrap()
+    }
+
+    #[test]
+    fn symbol_names_and_ports() {
+        let offset = offset_of("upd;", 0) + 1;
+        assert!(matches!(symbol_at(SRC, offset), Some(Symbol::Name("upd"))));
+
+        // Anywhere in `write_en`, including its first character.
+        let start = offset_of("write_en", 0);
+        for offset in [start, start + 3, start + "write_en".len()] {
+            assert!(matches!(
+                symbol_at(SRC, offset),
+                Some(Symbol::Port {
+                    cell: "r",
+                    port: "write_en"
+                })
+            ));
+        }
+
+        // Hole names are ports of the group.
+        let offset = offset_of("upd[done]", 0) + "upd[".len();
+        assert!(matches!(symbol_at(SRC, offset), Some(Symbol::Name("done"))));
+    }
+
+    #[test]
+    fn no_symbol_on_punctuation() {
+        assert!(symbol_at("a = b;", 2).is_none());
+        assert!(symbol_at("", 0).is_none());
+    }
+
+    #[test]
+    fn completion_after_dot() {
+        let text = "r.wr";
+        assert_eq!(completion_cell(text, text.len()), Some("r"));
+        assert_eq!(completion_cell(text, 2), Some("r"));
+        assert_eq!(completion_cell("r", 1), None);
+        assert_eq!(completion_cell(".in", 1), None);
+    }
+
+    #[test]
+    fn enclosing_components() {
+        let ws = workspace();
+        let at = |pat| {
+            enclosing_component(&ws, FILE, offset_of(pat, 0))
+                .map(|comp| comp.name.to_string())
+        };
+        assert_eq!(at("add.left").as_deref(), Some("add_one"));
+        assert_eq!(at("r.in").as_deref(), Some("main"));
+        assert_eq!(at("import"), None);
+        assert!(enclosing_component(&ws, "other.futil", SRC.len()).is_none());
+    }
+
+    #[test]
+    fn port_widths() {
+        let ws = workspace();
+        let main = main_comp(&ws);
+        let port = Symbol::Port {
+            cell: "r",
+            port: "in",
+        };
+        let info = resolve_port(&ws, main, &port).unwrap();
+        assert_eq!(info.describe(), "input in: 32");
+
+        // Ports of component instances include the implicit interface.
+        let ports = cell_ports(&ws, main, "a").unwrap();
+        let names =
+            ports.iter().map(|p| p.name.to_string()).collect::<Vec<_>>();
+        assert!(names.contains(&"out".to_string()));
+        assert!(names.contains(&"go".to_string()));
+
+        assert!(cell_ports(&ws, main, "missing").is_none());
+    }
+
+    #[test]
+    fn definition_of_names() {
+        let ws = workspace();
+        let main = main_comp(&ws);
+        let def = |name| definition(&ws, main, &Symbol::Name(name)).unwrap();
+
+        let span = def("r");
+        assert_eq!(span.file(), FILE);
+        assert_eq!(span.range().0, offset_of("r = std_reg", 0));
+
+        let span = def("upd");
+        assert_eq!(span.range().0, offset_of("upd {", 0));
+
+        let span = def("add_one");
+        assert_eq!(span.range().0, offset_of("add_one(in", 0));
+
+        // Primitives are defined in the library.
+        assert!(def("std_reg").file().ends_with("core.futil"));
+
+        assert!(definition(&ws, main, &Symbol::Name("missing")).is_none());
+    }
+
+    #[test]
+    fn definition_of_ports() {
+        let ws = workspace();
+        let main = main_comp(&ws);
+        let port = Symbol::Port {
+            cell: "a",
+            port: "out",
+        };
+        let span = definition(&ws, main, &port).unwrap();
+        assert_eq!(span.range().0, offset_of("out: 32", 0));
+
+        let port = Symbol::Port {
+            cell: "r",
+            port: "write_en",
+        };
+        let span = definition(&ws, main, &port).unwrap();
+        assert!(span.file().ends_with("core.futil"));
+    }
+}
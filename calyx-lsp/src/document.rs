@@ -0,0 +1,208 @@
+//! Open documents and their analysis results.
+use calyx::{
+    errors::{self, Span},
+    frontend::Workspace,
+    ir,
+    pass_manager::PassManager,
+};
+use lsp_types as lsp;
+use std::path::{Path, PathBuf};
+
+/// A Calyx file open in the editor.
+pub struct Document {
+    /// Path of the file backing this document.
+    path: PathBuf,
+    /// Current contents of the document.
+    text: String,
+    /// Workspace built from the last version of the document that parsed.
+    /// Queries use this while the current text does not parse, which is the
+    /// common case while typing.
+    workspace: Option<Workspace>,
+}
+
+impl Document {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            text: String::new(),
+            workspace: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn workspace(&self) -> Option<&Workspace> {
+        self.workspace.as_ref()
+    }
+
+    /// Replace the contents of the document and return the diagnostics for
+    /// the new contents.
+    pub fn update(
+        &mut self,
+        text: String,
+        lib_path: &Path,
+        pm: &PassManager,
+    ) -> Vec<lsp::Diagnostic> {
+        self.text = text;
+        let result =
+            Workspace::construct_from_source(&self.text, &self.path, lib_path)
+                .and_then(|ws| {
+                    self.workspace = Some(ws.clone());
+                    let mut ctx = ir::from_ast::ast_to_ir(ws)?;
+                    pm.execute_plan(&mut ctx, &["well-formed".to_string()], &[])
+                });
+        match result {
+            Ok(()) => vec![],
+            Err(err) => err
+                .diagnostics()
+                .into_iter()
+                .map(|diag| self.convert_diagnostic(diag))
+                .collect(),
+        }
+    }
+
+    /// Convert a compiler diagnostic into an LSP diagnostic. Errors without a
+    /// position in this document are reported at its start.
+    fn convert_diagnostic(&self, diag: errors::Diagnostic) -> lsp::Diagnostic {
+        let in_document = diag
+            .file
+            .as_ref()
+            .map(|file| Path::new(file) == self.path)
+            .unwrap_or(true);
+        let range = match (diag.start, diag.end) {
+            (Some(start), Some(end)) if in_document => lsp::Range::new(
+                self.position_at(start),
+                self.position_at(end.max(start)),
+            ),
+            _ => lsp::Range::default(),
+        };
+        let message = match diag.file {
+            Some(file) if !in_document => format!("{}: {}", file, diag.message),
+            _ => diag.message,
+        };
+        lsp::Diagnostic {
+            range,
+            severity: Some(lsp::DiagnosticSeverity::ERROR),
+            code: Some(lsp::NumberOrString::String(diag.kind.to_string())),
+            source: Some("calyx".to_string()),
+            message,
+            ..Default::default()
+        }
+    }
+
+    /// Position of the byte `offset` in the document.
+    pub fn position_at(&self, offset: usize) -> lsp::Position {
+        let offset = offset.min(self.text.len());
+        let before = &self.text[..offset];
+        let line = before.matches('\n').count();
+        let line_start = before.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+        let character = before[line_start..].encode_utf16().count();
+        lsp::Position::new(line as u32, character as u32)
+    }
+
+    /// Byte offset of `pos` in the document. Positions past the end of a line
+    /// are clamped to the end of the line.
+    pub fn offset_at(&self, pos: lsp::Position) -> usize {
+        let mut line_start = 0;
+        for _ in 0..pos.line {
+            match self.text[line_start..].find('\n') {
+                Some(idx) => line_start += idx + 1,
+                None => return self.text.len(),
+            }
+        }
+        let line = self.text[line_start..].split('\n').next().unwrap_or("");
+        let mut units = 0;
+        for (idx, ch) in line.char_indices() {
+            if units >= pos.character as usize {
+                return line_start + idx;
+            }
+            units += ch.len_utf16();
+        }
+        line_start + line.len()
+    }
+}
+
+/// Range covered by `span` in the file it belongs to. Calyx identifiers
+/// never span multiple lines.
+pub fn span_range(span: &Span) -> lsp::Range {
+    let (line, column) = span.line_col();
+    let (start, end) = span.range();
+    let line = line as u32 - 1;
+    let column = column as u32 - 1;
+    lsp::Range::new(
+        lsp::Position::new(line, column),
+        lsp::Position::new(line, column + (end - start) as u32),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(text: &str) -> Document {
+        let mut doc = Document::new(PathBuf::from("test.futil"));
+        doc.text = text.to_string();
+        doc
+    }
+
+    #[test]
+    fn positions_of_offsets() {
+        let doc = document("ab\ncd\n");
+        assert_eq!(doc.position_at(0), lsp::Position::new(0, 0));
+        assert_eq!(doc.position_at(2), lsp::Position::new(0, 2));
+        assert_eq!(doc.position_at(3), lsp::Position::new(1, 0));
+        assert_eq!(doc.position_at(4), lsp::Position::new(1, 1));
+        assert_eq!(doc.position_at(6), lsp::Position::new(2, 0));
+        // Offsets past the end are clamped.
+        assert_eq!(doc.position_at(100), lsp::Position::new(2, 0));
+    }
+
+    #[test]
+    fn offsets_of_positions() {
+        let doc = document("ab\ncd\n");
+        assert_eq!(doc.offset_at(lsp::Position::new(0, 1)), 1);
+        assert_eq!(doc.offset_at(lsp::Position::new(1, 0)), 3);
+        assert_eq!(doc.offset_at(lsp::Position::new(1, 2)), 5);
+        // Past the end of a line or the document.
+        assert_eq!(doc.offset_at(lsp::Position::new(0, 10)), 2);
+        assert_eq!(doc.offset_at(lsp::Position::new(5, 0)), 6);
+    }
+
+    #[test]
+    fn positions_count_utf16_units() {
+        // `é` is two bytes and one UTF-16 unit; `😀` is four bytes and two.
+        let doc = document("// é😀x\ny");
+        let x = doc.text.find('x').unwrap();
+        assert_eq!(doc.position_at(x), lsp::Position::new(0, 6));
+        assert_eq!(doc.offset_at(lsp::Position::new(0, 6)), x);
+        for offset in 0..doc.text.len() {
+            if doc.text.is_char_boundary(offset) {
+                assert_eq!(doc.offset_at(doc.position_at(offset)), offset);
+            }
+        }
+    }
+
+    #[test]
+    fn diagnostics_for_undefined_group() {
+        let text = "component main() -> () {\n  cells {}\n  wires {}\n  control { upd; }\n}\n";
+        let lib = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let pm = PassManager::default_passes().unwrap();
+        let mut doc = Document::new(PathBuf::from("test.futil"));
+        let diags = doc.update(text.to_string(), &lib, &pm);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].range.start, lsp::Position::new(3, 12));
+        assert!(doc.workspace().is_some());
+
+        // The last workspace that parsed is kept while the text does not.
+        let diags = doc.update("component".to_string(), &lib, &pm);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(doc.text(), "component");
+        assert!(doc.workspace().is_some());
+    }
+}
@@ -0,0 +1,263 @@
+//! Language server for Calyx programs.
+//!
+//! Supports diagnostics, go-to-definition for cells, groups, components and
+//! ports, hover for port widths, and completion of port names after `cell.`.
+mod analysis;
+mod document;
+
+use analysis::Symbol;
+use argh::FromArgs;
+use calyx::frontend::{ast::ComponentDef, Workspace};
+use calyx::pass_manager::PassManager;
+use document::Document;
+use lsp_server::{Connection, Message, Notification, Request, Response};
+use lsp_types::{
+    self as lsp,
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as _, PublishDiagnostics,
+    },
+    request::{Completion, GotoDefinition, HoverRequest, Request as _},
+};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+type ServerResult<T> = Result<T, Box<dyn Error + Sync + Send>>;
+
+#[derive(FromArgs)]
+/// Language server for Calyx programs. Communicates over stdin and stdout.
+struct Opts {
+    /// path to the primitives library
+    #[argh(option, short = 'l', default = "PathBuf::from(\".\")")]
+    lib_path: PathBuf,
+}
+
+/// State of the language server.
+struct Server {
+    connection: Connection,
+    lib_path: PathBuf,
+    pm: PassManager,
+    /// Documents currently open in the editor.
+    documents: HashMap<lsp::Url, Document>,
+}
+
+fn capabilities() -> lsp::ServerCapabilities {
+    lsp::ServerCapabilities {
+        text_document_sync: Some(lsp::TextDocumentSyncCapability::Kind(
+            lsp::TextDocumentSyncKind::FULL,
+        )),
+        definition_provider: Some(lsp::OneOf::Left(true)),
+        hover_provider: Some(lsp::HoverProviderCapability::Simple(true)),
+        completion_provider: Some(lsp::CompletionOptions {
+            trigger_characters: Some(vec![".".to_string()]),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+fn main() -> ServerResult<()> {
+    let opts: Opts = argh::from_env();
+    let (connection, io_threads) = Connection::stdio();
+    connection.initialize(serde_json::to_value(capabilities())?)?;
+
+    let mut server = Server {
+        connection,
+        lib_path: opts.lib_path,
+        pm: PassManager::default_passes()
+            .map_err(|err| format!("{:?}", err))?,
+        documents: HashMap::new(),
+    };
+    server.run()?;
+    io_threads.join()?;
+    Ok(())
+}
+
+impl Server {
+    fn run(&mut self) -> ServerResult<()> {
+        while let Ok(msg) = self.connection.receiver.recv() {
+            match msg {
+                Message::Request(req) => {
+                    if self.connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    self.handle_request(req)?;
+                }
+                Message::Notification(not) => self.handle_notification(not)?,
+                Message::Response(_) => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, req: Request) -> ServerResult<()> {
+        let id = req.id.clone();
+        let result = match req.method.as_str() {
+            GotoDefinition::METHOD => {
+                let params: lsp::GotoDefinitionParams =
+                    serde_json::from_value(req.params)?;
+                serde_json::to_value(
+                    self.definition(params.text_document_position_params),
+                )?
+            }
+            HoverRequest::METHOD => {
+                let params: lsp::HoverParams =
+                    serde_json::from_value(req.params)?;
+                serde_json::to_value(
+                    self.hover(params.text_document_position_params),
+                )?
+            }
+            Completion::METHOD => {
+                let params: lsp::CompletionParams =
+                    serde_json::from_value(req.params)?;
+                serde_json::to_value(
+                    self.completion(params.text_document_position),
+                )?
+            }
+            _ => {
+                let resp = Response::new_err(
+                    id,
+                    lsp_server::ErrorCode::MethodNotFound as i32,
+                    format!("Unsupported request: {}", req.method),
+                );
+                self.connection.sender.send(resp.into())?;
+                return Ok(());
+            }
+        };
+        self.connection
+            .sender
+            .send(Response::new_ok(id, result).into())?;
+        Ok(())
+    }
+
+    fn handle_notification(&mut self, not: Notification) -> ServerResult<()> {
+        match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: lsp::DidOpenTextDocumentParams =
+                    serde_json::from_value(not.params)?;
+                let doc = params.text_document;
+                self.update(doc.uri, doc.text)?;
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: lsp::DidChangeTextDocumentParams =
+                    serde_json::from_value(not.params)?;
+                // We only ask for full document syncs so the last change
+                // contains the entire document.
+                if let Some(change) = params.content_changes.into_iter().last()
+                {
+                    self.update(params.text_document.uri, change.text)?;
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: lsp::DidCloseTextDocumentParams =
+                    serde_json::from_value(not.params)?;
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                self.publish_diagnostics(uri, vec![])?;
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Replace the contents of the document at `uri` and publish the new
+    /// diagnostics.
+    fn update(&mut self, uri: lsp::Url, text: String) -> ServerResult<()> {
+        let path = uri
+            .to_file_path()
+            .unwrap_or_else(|_| PathBuf::from(uri.path()));
+        let doc = self
+            .documents
+            .entry(uri.clone())
+            .or_insert_with(|| Document::new(path));
+        let diagnostics = doc.update(text, &self.lib_path, &self.pm);
+        self.publish_diagnostics(uri, diagnostics)
+    }
+
+    fn publish_diagnostics(
+        &self,
+        uri: lsp::Url,
+        diagnostics: Vec<lsp::Diagnostic>,
+    ) -> ServerResult<()> {
+        let params = lsp::PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        let not =
+            Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        self.connection.sender.send(not.into())?;
+        Ok(())
+    }
+
+    /// Run `query` with the symbol at `pos` and the component containing it.
+    fn with_symbol<T, F>(
+        &self,
+        pos: &lsp::TextDocumentPositionParams,
+        query: F,
+    ) -> Option<T>
+    where
+        F: FnOnce(&Workspace, &ComponentDef, &Symbol) -> Option<T>,
+    {
+        let doc = self.documents.get(&pos.text_document.uri)?;
+        let ws = doc.workspace()?;
+        let offset = doc.offset_at(pos.position);
+        let symbol = analysis::symbol_at(doc.text(), offset)?;
+        let file = doc.path().to_string_lossy();
+        let comp = analysis::enclosing_component(ws, &file, offset)?;
+        query(ws, comp, &symbol)
+    }
+
+    fn definition(
+        &self,
+        pos: lsp::TextDocumentPositionParams,
+    ) -> Option<lsp::GotoDefinitionResponse> {
+        self.with_symbol(&pos, |ws, comp, symbol| {
+            let span = analysis::definition(ws, comp, symbol)?;
+            let uri = lsp::Url::from_file_path(span.file()).ok()?;
+            Some(lsp::GotoDefinitionResponse::Scalar(lsp::Location::new(
+                uri,
+                document::span_range(&span),
+            )))
+        })
+    }
+
+    fn hover(
+        &self,
+        pos: lsp::TextDocumentPositionParams,
+    ) -> Option<lsp::Hover> {
+        self.with_symbol(&pos, |ws, comp, symbol| {
+            let port = analysis::resolve_port(ws, comp, symbol)?;
+            Some(lsp::Hover {
+                contents: lsp::HoverContents::Markup(lsp::MarkupContent {
+                    kind: lsp::MarkupKind::Markdown,
+                    value: format!("```\n{}\n```", port.describe()),
+                }),
+                range: None,
+            })
+        })
+    }
+
+    fn completion(
+        &self,
+        pos: lsp::TextDocumentPositionParams,
+    ) -> Option<lsp::CompletionResponse> {
+        let doc = self.documents.get(&pos.text_document.uri)?;
+        let ws = doc.workspace()?;
+        let offset = doc.offset_at(pos.position);
+        let cell = analysis::completion_cell(doc.text(), offset)?;
+        let file = doc.path().to_string_lossy();
+        let comp = analysis::enclosing_component(ws, &file, offset)?;
+        let items = analysis::cell_ports(ws, comp, cell)?
+            .into_iter()
+            .map(|port| lsp::CompletionItem {
+                label: port.name.to_string(),
+                kind: Some(lsp::CompletionItemKind::FIELD),
+                detail: Some(port.describe()),
+                ..Default::default()
+            })
+            .collect();
+        Some(lsp::CompletionResponse::Array(items))
+    }
+}
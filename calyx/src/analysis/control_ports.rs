@@ -119,8 +119,19 @@ impl<const INVOKE_MAP: bool> ControlPorts<INVOKE_MAP> {
                 }
                 self.construct(body);
             }
+            ir::Control::Repeat(ir::Repeat { body, .. }) => {
+                self.construct(body);
+            }
+            ir::Control::StaticIf(ir::StaticIf {
+                tbranch, fbranch, ..
+            }) => {
+                self.construct(tbranch);
+                self.construct(fbranch);
+            }
             ir::Control::Seq(ir::Seq { stmts, .. })
-            | ir::Control::Par(ir::Par { stmts, .. }) => {
+            | ir::Control::Par(ir::Par { stmts, .. })
+            | ir::Control::StaticSeq(ir::StaticSeq { stmts, .. })
+            | ir::Control::StaticPar(ir::StaticPar { stmts, .. }) => {
                 stmts.iter().for_each(|con| self.construct(con));
             }
         }
@@ -174,6 +174,18 @@ impl GraphAnalysis {
         }
     }
 
+    /// Returns an iterator over the ports that are not written to by any
+    /// other port in the graph.
+    pub fn sources(&self) -> PortIterator<'_> {
+        PortIterator {
+            port_iter: Box::new(
+                self.graph
+                    .externals(Incoming)
+                    .map(move |node_idx| Rc::clone(&self.graph[node_idx])),
+            ),
+        }
+    }
+
     /// Return a Vec of paths from `start` to `finish`, each path a Vec of ports.
     pub fn paths(
         &self,
@@ -424,17 +424,24 @@ fn build_live_ranges(
             lr.live.insert(group.clone_name(), &alive | &writes);
             (alive, &gens | &reads, &kills | &writes)
         }
-        ir::Control::Seq(ir::Seq { stmts, .. }) => stmts.iter().rev().fold(
-            (alive, gens, kills),
-            |(alive, gens, kills), e| {
+        ir::Control::Seq(ir::Seq { stmts, .. })
+        | ir::Control::StaticSeq(ir::StaticSeq { stmts, .. }) => stmts
+            .iter()
+            .rev()
+            .fold((alive, gens, kills), |(alive, gens, kills), e| {
                 build_live_ranges(e, alive, gens, kills, lr)
-            },
-        ),
+            }),
         ir::Control::If(ir::If {
             tbranch,
             fbranch,
             port,
             ..
+        })
+        | ir::Control::StaticIf(ir::StaticIf {
+            tbranch,
+            fbranch,
+            port,
+            ..
         }) => {
             // compute each branch
             let (t_alive, t_gens, t_kills) = build_live_ranges(
@@ -458,7 +465,8 @@ fn build_live_ranges(
             }
             (alive, gens, kills)
         }
-        ir::Control::Par(ir::Par { stmts, .. }) => {
+        ir::Control::Par(ir::Par { stmts, .. })
+        | ir::Control::StaticPar(ir::StaticPar { stmts, .. }) => {
             let (alive, gens, kills) = stmts
                 .iter()
                 .rev()
@@ -493,5 +501,10 @@ fn build_live_ranges(
             }
             build_live_ranges(body, alive, gens, kills, lr)
         }
+        ir::Control::Repeat(ir::Repeat { body, .. }) => {
+            let (alive, gens, kills) =
+                build_live_ranges(body, alive, gens, kills, lr);
+            build_live_ranges(body, alive, gens, kills, lr)
+        }
     }
 }
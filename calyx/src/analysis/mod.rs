@@ -13,6 +13,7 @@ mod port_interface;
 pub mod reaching_defns;
 mod read_write_set;
 mod schedule_conflicts;
+mod static_latency;
 mod variable_detection;
 
 pub use control_order::ControlOrder;
@@ -24,4 +25,5 @@ pub use live_range_analysis::LiveRangeAnalysis;
 pub use port_interface::PortInterface;
 pub use read_write_set::ReadWriteSet;
 pub use schedule_conflicts::ScheduleConflicts;
+pub use static_latency::StaticLatency;
 pub use variable_detection::VariableDetection;
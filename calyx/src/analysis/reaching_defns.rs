@@ -293,14 +293,14 @@ fn build_reaching_def(
     counter: &mut u64,
 ) -> (DefSet, KilledSet) {
     match c {
-        ir::Control::Seq(ir::Seq { stmts, .. }) => {
-            stmts
-                .iter()
-                .fold((reach, killed), |(acc, killed), inner_c| {
-                    build_reaching_def(inner_c, acc, killed, rd, counter)
-                })
-        }
-        ir::Control::Par(ir::Par { stmts, .. }) => {
+        ir::Control::Seq(ir::Seq { stmts, .. })
+        | ir::Control::StaticSeq(ir::StaticSeq { stmts, .. }) => stmts
+            .iter()
+            .fold((reach, killed), |(acc, killed), inner_c| {
+                build_reaching_def(inner_c, acc, killed, rd, counter)
+            }),
+        ir::Control::Par(ir::Par { stmts, .. })
+        | ir::Control::StaticPar(ir::StaticPar { stmts, .. }) => {
             let (defs, par_killed): (Vec<DefSet>, Vec<KilledSet>) = stmts
                 .iter()
                 .map(|ctrl| {
@@ -329,6 +329,9 @@ fn build_reaching_def(
         }
         ir::Control::If(ir::If {
             tbranch, fbranch, ..
+        })
+        | ir::Control::StaticIf(ir::StaticIf {
+            tbranch, fbranch, ..
         }) => {
             let (post_cond_def, post_cond_killed) = build_reaching_def(
                 &ir::Control::empty(),
@@ -353,7 +356,8 @@ fn build_reaching_def(
             );
             (&t_case_def | &f_case_def, &t_case_killed | &f_case_killed)
         }
-        ir::Control::While(ir::While { body, .. }) => {
+        ir::Control::While(ir::While { body, .. })
+        | ir::Control::Repeat(ir::Repeat { body, .. }) => {
             let (post_cond_def, post_cond_killed) = build_reaching_def(
                 &ir::Control::empty(),
                 reach.clone(),
@@ -153,7 +153,9 @@ impl ReadWriteSet {
             }
 
             ir::Control::Seq(ir::Seq { stmts, .. })
-            | ir::Control::Par(ir::Par { stmts, .. }) => {
+            | ir::Control::Par(ir::Par { stmts, .. })
+            | ir::Control::StaticSeq(ir::StaticSeq { stmts, .. })
+            | ir::Control::StaticPar(ir::StaticPar { stmts, .. }) => {
                 let (mut reads, mut writes) = (vec![], vec![]);
                 for stmt in stmts {
                     let (mut read, mut write) =
@@ -191,6 +193,24 @@ impl ReadWriteSet {
                 }
                 (reads, writes)
             }
+            ir::Control::StaticIf(ir::StaticIf {
+                port,
+                tbranch,
+                fbranch,
+                ..
+            }) => {
+                let (mut reads, mut writes) = (vec![], vec![]);
+                let (mut treads, mut twrites) =
+                    Self::control_port_read_write_set(tbranch);
+                let (mut freads, mut fwrites) =
+                    Self::control_port_read_write_set(fbranch);
+                reads.append(&mut treads);
+                reads.append(&mut freads);
+                reads.push(Rc::clone(port));
+                writes.append(&mut twrites);
+                writes.append(&mut fwrites);
+                (reads, writes)
+            }
             ir::Control::While(ir::While {
                 port, cond, body, ..
             }) => {
@@ -208,6 +228,9 @@ impl ReadWriteSet {
                 }
                 (reads, writes)
             }
+            ir::Control::Repeat(ir::Repeat { body, .. }) => {
+                Self::control_port_read_write_set(body)
+            }
         }
     }
 }
@@ -98,9 +98,16 @@ fn build_conflict_graph(
             confs.add_node(group.borrow().name());
             all_enables.push(group.clone_name());
         }
-        ir::Control::Seq(ir::Seq { stmts, .. }) => stmts
+        ir::Control::Seq(ir::Seq { stmts, .. })
+        | ir::Control::StaticSeq(ir::StaticSeq { stmts, .. }) => stmts
             .iter()
             .for_each(|c| build_conflict_graph(c, confs, all_enables)),
+        ir::Control::StaticIf(ir::StaticIf {
+            tbranch, fbranch, ..
+        }) => {
+            build_conflict_graph(tbranch, confs, all_enables);
+            build_conflict_graph(fbranch, confs, all_enables);
+        }
         ir::Control::If(ir::If {
             cond,
             tbranch,
@@ -125,7 +132,11 @@ fn build_conflict_graph(
             }
             build_conflict_graph(body, confs, all_enables);
         }
-        ir::Control::Par(ir::Par { stmts, .. }) => {
+        ir::Control::Repeat(ir::Repeat { body, .. }) => {
+            build_conflict_graph(body, confs, all_enables);
+        }
+        ir::Control::Par(ir::Par { stmts, .. })
+        | ir::Control::StaticPar(ir::StaticPar { stmts, .. }) => {
             let enables = stmts
                 .iter()
                 .map(|c| {
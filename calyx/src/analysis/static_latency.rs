@@ -0,0 +1,314 @@
+use crate::analysis::{GraphAnalysis, ReadWriteSet};
+use crate::ir::{self, LibrarySignatures, RRC};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Infers the number of cycles a group takes from the `"static"` latencies of
+/// the primitives it uses.
+///
+/// Inference only succeeds for groups where the `done` signal relies only on
+/// other `done` signals of primitives with a known latency.
+pub struct StaticLatency {
+    /// primitive name -> (go signal, done signal, latency)
+    latency_data: HashMap<ir::Id, (ir::Id, ir::Id, u64)>,
+}
+
+impl From<&LibrarySignatures> for StaticLatency {
+    fn from(lib: &LibrarySignatures) -> Self {
+        let mut latency_data = HashMap::new();
+        // Construct latency_data for each primitive
+        for prim in lib.signatures() {
+            if let Some(time) = prim.attributes.get("static") {
+                let mut go_port = None;
+                let mut done_port = None;
+                for port in &prim.signature {
+                    if port.attributes.has("go") {
+                        go_port = Some(port.name.clone());
+                    }
+                    if port.attributes.has("done") {
+                        done_port = Some(port.name.clone());
+                    }
+                }
+                if let (Some(go), Some(done)) = (go_port, done_port) {
+                    latency_data.insert(prim.name.clone(), (go, done, time));
+                }
+            }
+        }
+        StaticLatency { latency_data }
+    }
+}
+
+impl StaticLatency {
+    /// Latencies of the primitives with a `go` and a `done` port.
+    pub fn primitive_latencies(&self) -> HashMap<ir::Id, u64> {
+        self.latency_data
+            .iter()
+            .map(|(name, (_, _, time))| (name.clone(), *time))
+            .collect()
+    }
+
+    /// Return true if the edge (`src`, `dst`) meet one these criteria, and false otherwise:
+    ///   - `src` is an "out" port of a constant, and `dst` is a "go" port
+    ///   - `src` is a "done" port, and `dst` is a "go" port
+    ///   - `src` is a "done" port, and `dst` is the "done" port of a group
+    fn mem_wrt_dep_graph(&self, src: &ir::Port, dst: &ir::Port) -> bool {
+        match (&src.parent, &dst.parent) {
+            (
+                ir::PortParent::Cell(src_cell),
+                ir::PortParent::Cell(dst_cell),
+            ) => {
+                // A cell's `done` port only feeds its own `go` port in the
+                // `c.go = !c.done ? 1'd1` idiom, which does not add latency.
+                if Rc::ptr_eq(&src_cell.upgrade(), &dst_cell.upgrade()) {
+                    return false;
+                }
+                if let (
+                    ir::CellType::Primitive {
+                        name: dst_cell_prim_type,
+                        ..
+                    },
+                    ir::CellType::Primitive {
+                        name: src_cell_prim_type,
+                        ..
+                    },
+                ) = (
+                    &dst_cell.upgrade().borrow().prototype,
+                    &src_cell.upgrade().borrow().prototype,
+                ) {
+                    let data_dst = self.latency_data.get(dst_cell_prim_type);
+                    let data_src = self.latency_data.get(src_cell_prim_type);
+                    if let (Some((go_dst, _, _)), Some((_, done_src, _))) =
+                        (data_dst, data_src)
+                    {
+                        if dst.name == *go_dst && src.name == *done_src {
+                            return true;
+                        }
+                    }
+                }
+
+                // A constant writes to a cell: to be added to the graph, the cell needs to be a "done" port.
+                if let (
+                    ir::CellType::Primitive {
+                        name: dst_cell_prim_type,
+                        ..
+                    },
+                    ir::CellType::Constant { .. },
+                ) = (
+                    &dst_cell.upgrade().borrow().prototype,
+                    &src_cell.upgrade().borrow().prototype,
+                ) {
+                    let data = self.latency_data.get(dst_cell_prim_type);
+                    if let Some((go, _, _)) = data {
+                        if dst.name == *go {
+                            return true;
+                        }
+                    }
+                }
+
+                false
+            }
+
+            // Something is written to a group: to be added to the graph, this needs to be a "done" port.
+            // Constants only show up as the source of a guarded write like
+            // `g[done] = r.done ? 1'd1` and the guard already adds an edge.
+            (_, ir::PortParent::Group(_)) => {
+                dst.name == "done" && !src.is_constant(1, 1)
+            }
+
+            // If we encounter anything else, no need to add it to the graph.
+            _ => false,
+        }
+    }
+
+    /// Return a Vec of edges (`a`, `b`), where `a` is a "go" port and `b`
+    /// is a "done" port, and `a` and `b` have the same parent cell.
+    fn find_go_done_edges(
+        &self,
+        group: &ir::Group,
+    ) -> Vec<(RRC<ir::Port>, RRC<ir::Port>)> {
+        let rw_set = ReadWriteSet::uses(group.assignments.iter());
+        let mut go_done_edges: Vec<(RRC<ir::Port>, RRC<ir::Port>)> = Vec::new();
+        for cell_ref in rw_set {
+            let cell = cell_ref.borrow();
+            if let ir::CellType::Primitive {
+                name: cell_type, ..
+            } = &cell.prototype
+            {
+                if let Some((go, done, _)) = self.latency_data.get(cell_type) {
+                    let go_port =
+                        &cell.ports.iter().find(|p| p.borrow().name == *go);
+                    let done_port =
+                        &cell.ports.iter().find(|p| p.borrow().name == *done);
+
+                    if let (Some(g), Some(d)) = (go_port, done_port) {
+                        go_done_edges.push((Rc::clone(g), Rc::clone(d)));
+                    }
+                }
+            }
+        }
+        go_done_edges
+    }
+
+    /// Returns true if `port` is a "done" port, and we know the latency data
+    /// about `port`, or is a constant.
+    fn is_done_port_or_const(&self, port: &ir::Port) -> bool {
+        if let ir::PortParent::Cell(cell) = &port.parent {
+            if let ir::CellType::Primitive {
+                name: cell_type, ..
+            } = &cell.upgrade().borrow().prototype
+            {
+                if let Some((_, done, _)) = self.latency_data.get(cell_type) {
+                    if port.name == *done {
+                        return true;
+                    }
+                }
+            }
+
+            if let ir::CellType::Constant { val, .. } =
+                &cell.upgrade().borrow().prototype
+            {
+                if *val != ir::UBig::from(0u8) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns true if `graph` contains writes to "done" ports
+    /// that could have dynamic latencies, false otherwise.
+    fn contains_dyn_writes(&self, graph: GraphAnalysis) -> bool {
+        for port in &graph.ports() {
+            match &port.borrow().parent {
+                ir::PortParent::Cell(cell) => {
+                    if let ir::CellType::Primitive {
+                        name: cell_type, ..
+                    } = &cell.upgrade().borrow().prototype
+                    {
+                        if let Some((go, _, _)) =
+                            self.latency_data.get(cell_type)
+                        {
+                            if port.borrow().name == *go {
+                                for write_port in
+                                    graph.writes_to(&*port.borrow())
+                                {
+                                    if !self.is_done_port_or_const(
+                                        &*write_port.borrow(),
+                                    ) {
+                                        return true;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                ir::PortParent::Group(_) => {
+                    if port.borrow().name == "done" {
+                        for write_port in graph.writes_to(&*port.borrow()) {
+                            if !self
+                                .is_done_port_or_const(&*write_port.borrow())
+                            {
+                                return true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns true if `graph` contains any nodes other than the `done` hole
+    /// of a group with degree > 1.
+    fn contains_node_deg_gt_one(graph: &GraphAnalysis) -> bool {
+        for port in graph.ports() {
+            if port.borrow().is_hole() {
+                continue;
+            }
+            if graph.writes_to(&*port.borrow()).count() > 1 {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Attempts to infer the number of cycles starting when
+    /// `group[go]` is high, and port is high. If inference is
+    /// not possible, returns None.
+    pub fn infer(&self, group: &ir::Group) -> Option<u64> {
+        // Creates a write dependency graph, which contains an edge (`a`, `b`) if:
+        //   - `a` is a "done" port, and writes to `b`, which is a "go" port
+        //   - `a` is a "done" port, and writes to `b`, which is the "done" port of this group
+        //   - `a` is an "out" port, and is a constant, and writes to `b`, a "go" port
+        //   - `a` is a "go" port, and `b` is a "done" port, and `a` and `b` share a parent cell
+        // Nodes that are not part of any edges that meet these criteria are excluded.
+        //
+        // For example, this group:
+        // ```
+        // group g1 {
+        //   a.in = 32'd1;
+        //   a.write_en = 1'd1;
+        //   g1[done] = a.done;
+        // }
+        // ```
+        // corresponds to this graph:
+        // ```
+        // constant(1) -> a.write_en
+        // a.write_en -> a.done
+        // a.done -> g1[done]
+        // ```
+        let graph_unprocessed = GraphAnalysis::from(group);
+        if self.contains_dyn_writes(graph_unprocessed.clone()) {
+            return None;
+        }
+
+        let go_done_edges = self.find_go_done_edges(group);
+        let graph = graph_unprocessed
+            .edge_induced_subgraph(|src, dst| self.mem_wrt_dep_graph(src, dst))
+            .add_edges(&go_done_edges)
+            .remove_isolated_vertices();
+
+        // Give up if the graph has cycles or a port other than the group's
+        // `done` hole has multiple writes to it.
+        if graph.has_cycles() || Self::contains_node_deg_gt_one(&graph) {
+            return None;
+        }
+
+        let finish = graph.toposort().last()?;
+
+        // Sum the latencies of each primitive along every path to the `done`
+        // hole. A group whose `done` waits on several cells is only static
+        // when all the paths take the same number of cycles.
+        let mut latencies = graph
+            .sources()
+            .flat_map(|start| graph.paths(&*start.borrow(), &*finish.borrow()))
+            .map(|path| self.path_latency(&path));
+        let latency = latencies.next()?;
+        if latencies.all(|l| l == latency) {
+            Some(latency)
+        } else {
+            None
+        }
+    }
+
+    /// Sum of the latencies of the primitives whose `go` port is on `path`.
+    fn path_latency(&self, path: &[RRC<ir::Port>]) -> u64 {
+        let mut latency_sum = 0;
+        for port in path {
+            if let ir::PortParent::Cell(cell) = &port.borrow().parent {
+                if let ir::CellType::Primitive { name, .. } =
+                    &cell.upgrade().borrow().prototype
+                {
+                    if let Some((go, _, latency)) = self.latency_data.get(name)
+                    {
+                        if port.borrow().name == go {
+                            latency_sum += latency;
+                        }
+                    }
+                }
+            }
+        }
+        latency_sum
+    }
+}
@@ -1,11 +1,12 @@
 //! Defines the default passes available to [PassManager].
 use crate::passes::{
     Canonicalize, ClkInsertion, CollapseControl, CombProp, CompileEmpty,
-    CompileInvoke, ComponentInliner, ComponentInterface, DeadCellRemoval,
-    DeadGroupRemoval, Externalize, GoInsertion, GroupToInvoke, HoleInliner,
-    InferStaticTiming, LowerGuards, MergeAssign, MinimizeRegs, Papercut,
-    ParToSeq, RegisterUnsharing, RemoveCombGroups, ResetInsertion,
-    ResourceSharing, SimplifyGuards, SynthesisPapercut, TopDownCompileControl,
+    CompileInvoke, CompileRef, CompileStatic, ComponentInliner,
+    ComponentInterface, DeadCellRemoval, DeadGroupRemoval, Externalize,
+    GoInsertion, GroupToInvoke, HoleInliner, InferStaticTiming, LowerGuards,
+    LowerSelects, MergeAssign, MinimizeRegs, Papercut, ParToSeq,
+    RegisterUnsharing, RemoveCombGroups, ResetInsertion, ResourceSharing,
+    SimplifyGuards, SynthesisPapercut, TopDownCompileControl,
     TopDownStaticTiming, UnrollBounded, WellFormed, WireInliner,
 };
 use crate::{
@@ -35,6 +36,8 @@ impl PassManager {
         pm.register_pass::<InferStaticTiming>()?;
 
         // Compilation passes
+        pm.register_pass::<CompileRef>()?;
+        pm.register_pass::<CompileStatic>()?;
         pm.register_pass::<CompileInvoke>()?;
         pm.register_pass::<RemoveCombGroups>()?;
         pm.register_pass::<TopDownStaticTiming>()?;
@@ -59,6 +62,7 @@ impl PassManager {
         pm.register_pass::<GroupToInvoke>()?;
         pm.register_pass::<ParToSeq>()?;
         pm.register_pass::<LowerGuards>()?;
+        pm.register_pass::<LowerSelects>()?;
         pm.register_pass::<HoleInliner>()?;
 
         register_alias!(pm, "validate", [WellFormed, Papercut, Canonicalize]);
@@ -66,6 +70,7 @@ impl PassManager {
             pm,
             "pre-opt",
             [
+                CompileRef, // Must run before other passes see `ref` cells.
                 ComponentInliner,
                 CombProp,
                 RemoveCombGroups, // Must run before `infer-static-timing`.
@@ -75,11 +80,23 @@ impl PassManager {
                 MinimizeRegs,
             ]
         );
-        register_alias!(pm, "compile", [CompileInvoke, TopDownCompileControl]);
+        register_alias!(
+            pm,
+            "compile",
+            [
+                CompileRef, // Needed when `pre-opt` is disabled.
+                CompileStatic,
+                CompileInvoke,
+                TopDownCompileControl
+            ]
+        );
+        // Removing dead groups and propagating wires expose dead cells and
+        // groups for each other.
         register_alias!(
             pm,
             "post-opt",
-            [DeadGroupRemoval, CombProp, DeadCellRemoval]
+            [DeadGroupRemoval, CombProp, DeadCellRemoval],
+            max_iterations = 4
         );
         register_alias!(
             pm,
@@ -4,8 +4,9 @@ use itertools::Itertools;
 
 use crate::frontend::parser;
 use crate::ir;
+use serde::Serialize;
 use std::cmp;
-use std::rc::Rc;
+use std::sync::Arc;
 
 /// Convience wrapper to represent success or meaningul compiler error.
 pub type CalyxResult<T> = std::result::Result<T, Error>;
@@ -15,9 +16,9 @@ pub type CalyxResult<T> = std::result::Result<T, Error>;
 #[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Span {
     /// Reference to input program source.
-    input: Rc<str>,
+    input: Arc<str>,
     /// Name of the input file
-    file: Rc<str>,
+    file: Arc<str>,
     /// The start of the span.
     start: usize,
     /// The end of the span.
@@ -27,7 +28,7 @@ pub struct Span {
 impl Span {
     /// Create a new `Error::Span` from a `pest::Span` and
     /// the input string.
-    pub fn new(span: pest::Span, file: Rc<str>, input: Rc<str>) -> Span {
+    pub fn new(span: pest::Span, file: Arc<str>, input: Arc<str>) -> Span {
         Span {
             input,
             file,
@@ -87,6 +88,22 @@ impl Span {
         buf
     }
 
+    /// Name of the file this span is from.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The start and end byte offsets of this span.
+    pub fn range(&self) -> (usize, usize) {
+        (self.start, self.end)
+    }
+
+    /// The line and column (both starting at 1) of the start of this span.
+    pub fn line_col(&self) -> (usize, usize) {
+        let (_, pos, linum) = self.get_lines();
+        (linum, self.start - pos + 1)
+    }
+
     /// Visualizes the span without any message or mkaring
     pub fn show(&self) -> String {
         let (lines, _, linum) = self.get_lines();
@@ -102,6 +119,12 @@ pub trait WithPos {
     fn copy_span(&self) -> Option<Span>;
 }
 
+impl WithPos for Span {
+    fn copy_span(&self) -> Option<Span> {
+        Some(self.clone())
+    }
+}
+
 pub struct Error {
     kind: ErrorKind,
     pos: Option<Span>,
@@ -110,9 +133,21 @@ pub struct Error {
 
 impl std::fmt::Debug for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self.pos {
-            None => write!(f, "{}", self.kind)?,
-            Some(pos) => write!(f, "{}", pos.format(&self.kind.to_string()))?,
+        if let ErrorKind::Multiple(errors) = &self.kind {
+            // The caller prints the prefix for the first error.
+            for (idx, err) in errors.iter().enumerate() {
+                if idx > 0 {
+                    write!(f, "\nError: ")?;
+                }
+                write!(f, "{:?}", err)?;
+            }
+        } else {
+            match &self.pos {
+                None => write!(f, "{}", self.kind)?,
+                Some(pos) => {
+                    write!(f, "{}", pos.format(&self.kind.to_string()))?
+                }
+            }
         }
         if let Some(post) = &self.post_msg {
             write!(f, "\n{}", post)?;
@@ -121,7 +156,102 @@ impl std::fmt::Debug for Error {
     }
 }
 
+/// A machine-readable description of an error.
+#[derive(Serialize)]
+pub struct Diagnostic {
+    /// Name of the input file.
+    pub file: Option<String>,
+    /// Byte offset of the start of the error location.
+    pub start: Option<usize>,
+    /// Byte offset of the end of the error location.
+    pub end: Option<usize>,
+    /// Line of the start of the error location.
+    pub line: Option<usize>,
+    /// Column of the start of the error location.
+    pub column: Option<usize>,
+    /// Severity of the diagnostic.
+    pub severity: &'static str,
+    /// Kind of the error.
+    pub kind: &'static str,
+    /// Error message without the source location.
+    pub message: String,
+}
+
 impl Error {
+    /// Describe all the errors contained in this error using [Diagnostic].
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        if let ErrorKind::Multiple(errors) = &self.kind {
+            return errors.iter().flat_map(Error::diagnostics).collect();
+        }
+        let mut message = self.kind.to_string();
+        if let Some(post) = &self.post_msg {
+            message = format!("{}\n{}", message, post);
+        }
+        let mut diag = Diagnostic {
+            file: None,
+            start: None,
+            end: None,
+            line: None,
+            column: None,
+            severity: "error",
+            kind: self.kind.name(),
+            message,
+        };
+        if let Some(pos) = &self.pos {
+            let (line, column) = pos.line_col();
+            diag.file = Some(pos.file.to_string());
+            diag.start = Some(pos.start);
+            diag.end = Some(pos.end);
+            diag.line = Some(line);
+            diag.column = Some(column);
+        } else if let ErrorKind::ParseError(err) = &self.kind {
+            // Parse errors track their own location.
+            use pest::error::{InputLocation, LineColLocation};
+            let (start, end) = match err.location {
+                InputLocation::Pos(p) => (p, p),
+                InputLocation::Span(span) => span,
+            };
+            let (line, column) = match err.line_col {
+                LineColLocation::Pos(lc) | LineColLocation::Span(lc, _) => lc,
+            };
+            diag.start = Some(start);
+            diag.end = Some(end);
+            diag.line = Some(line);
+            diag.column = Some(column);
+        }
+        vec![diag]
+    }
+
+    /// Returns all the errors contained in this error.
+    pub fn into_errors(self) -> Vec<Error> {
+        match self.kind {
+            ErrorKind::Multiple(errors) => errors,
+            _ => vec![self],
+        }
+    }
+
+    /// Report at most `limit` of the errors contained in this error. A limit
+    /// of zero reports all the errors.
+    pub fn limit(self, limit: usize) -> Self {
+        match self.kind {
+            ErrorKind::Multiple(mut errors)
+                if limit > 0 && errors.len() > limit =>
+            {
+                let hidden = errors.len() - limit;
+                errors.truncate(limit);
+                Self {
+                    kind: ErrorKind::Multiple(errors),
+                    pos: None,
+                    post_msg: Some(format!(
+                        "{} more error(s) not shown",
+                        hidden
+                    )),
+                }
+            }
+            kind => Self { kind, ..self },
+        }
+    }
+
     pub fn with_pos<T: WithPos>(mut self, pos: &T) -> Self {
         self.pos = pos.copy_span();
         self
@@ -220,6 +350,74 @@ impl Error {
     }
 }
 
+/// Accumulates errors so that validation can report every problem in a
+/// program instead of stopping at the first one.
+#[derive(Default)]
+pub struct Diagnostics {
+    errors: Vec<Error>,
+}
+
+impl Diagnostics {
+    /// Record an error.
+    pub fn err(&mut self, err: Error) {
+        self.errors.extend(err.into_errors());
+    }
+
+    /// Record the error if `res` failed and return the value otherwise.
+    pub fn check<T>(&mut self, res: CalyxResult<T>) -> Option<T> {
+        match res {
+            Ok(v) => Some(v),
+            Err(err) => {
+                self.err(err);
+                None
+            }
+        }
+    }
+
+    /// Returns true if no errors have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Number of errors recorded so far.
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Take all the recorded errors and leave the accumulator empty.
+    pub fn take(&mut self) -> Self {
+        std::mem::take(self)
+    }
+
+    /// Returns `Ok` if there are no errors. Otherwise, returns an error that
+    /// contains all the recorded errors sorted by their location. Errors
+    /// without a location are reported last.
+    pub fn into_result(mut self) -> CalyxResult<()> {
+        match self.errors.len() {
+            0 => Ok(()),
+            1 => Err(self.errors.pop().unwrap()),
+            _ => {
+                self.errors.sort_by(|e1, e2| {
+                    let key = |e: &Error| {
+                        e.pos.as_ref().map(|sp| (sp.file.clone(), sp.start))
+                    };
+                    match (key(e1), key(e2)) {
+                        (Some(k1), Some(k2)) => k1.cmp(&k2),
+                        (Some(_), None) => cmp::Ordering::Less,
+                        (None, Some(_)) => cmp::Ordering::Greater,
+                        (None, None) => cmp::Ordering::Equal,
+                    }
+                });
+                Err(Error {
+                    kind: ErrorKind::Multiple(self.errors),
+                    pos: None,
+                    post_msg: None,
+                })
+            }
+        }
+    }
+}
+
 /// Standard error type for Calyx errors.
 pub enum ErrorKind {
     /// Error while parsing a Calyx program.
@@ -256,6 +454,32 @@ pub enum ErrorKind {
     InvalidFile(String),
     /// Failed to write the output
     WriteError(String),
+
+    /// Several errors reported together. Constructed using [Diagnostics].
+    Multiple(Vec<Error>),
+}
+
+impl ErrorKind {
+    /// Name of this kind of error.
+    pub fn name(&self) -> &'static str {
+        use ErrorKind::*;
+        match self {
+            ParseError(..) => "ParseError",
+            ReservedName(..) => "ReservedName",
+            MalformedControl(..) => "MalformedControl",
+            MalformedStructure(..) => "MalformedStructure",
+            PassAssumption(..) => "PassAssumption",
+            Undefined(..) => "Undefined",
+            AlreadyBound(..) => "AlreadyBound",
+            Unused(..) => "Unused",
+            Papercut(..) => "Papercut",
+            ImpossibleLatencyAnnotation(..) => "ImpossibleLatencyAnnotation",
+            Misc(..) => "Misc",
+            InvalidFile(..) => "InvalidFile",
+            WriteError(..) => "WriteError",
+            Multiple(..) => "Multiple",
+        }
+    }
 }
 
 impl std::fmt::Display for ErrorKind {
@@ -299,6 +523,9 @@ impl std::fmt::Display for ErrorKind {
             InvalidFile(msg) | WriteError(msg) | Misc(msg) => {
                 write!(f, "{msg}")
             }
+            Multiple(errors) => {
+                write!(f, "{} errors found", errors.len())
+            }
         }
     }
 }
@@ -37,10 +37,13 @@ impl NamespaceDef {
 }
 
 /// AST statement for defining components.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ComponentDef {
     /// Name of the component.
     pub name: ir::Id,
+    /// Names of the parameters of this component. Parameterized components
+    /// are monomorphized for each distinct binding when building the IR.
+    pub params: Vec<ir::Id>,
     /// Defines input and output ports along with their attributes.
     pub signature: Vec<ir::PortDef>,
     /// List of instantiated sub-components
@@ -53,11 +56,13 @@ pub struct ComponentDef {
     pub control: Control,
     /// Attributes attached to this component
     pub attributes: ir::Attributes,
+    /// True iff this is a combinational component
+    pub is_comb: bool,
 }
 
 /// Statement that refers to a port on a subcomponent.
 /// This is distinct from a `Portdef` which defines a port.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Port {
     /// Refers to the port named `port` on the subcomponent
     /// `component`.
@@ -76,7 +81,7 @@ pub enum Port {
 // AST for wire guard expressions
 // ===================================
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum NumType {
     Decimal,
     Binary,
@@ -85,26 +90,28 @@ pub enum NumType {
 }
 
 /// Custom bitwidth numbers
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BitNum {
     pub width: u64,
     pub num_type: NumType,
-    pub val: u64,
+    pub val: ir::UBig,
     pub span: Option<Span>,
 }
 
 /// Atomic operations used in guard conditions and RHS of the
 /// guarded assignments.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Atom {
     /// Accessing a particular port on a component.
     Port(Port),
+    /// `port[msb:lsb]` or `port[bit]`: reading a range of bits from a port.
+    Select { port: Port, msb: u64, lsb: u64 },
     /// A constant.
     Num(BitNum),
 }
 
 /// The AST for GuardExprs
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum GuardExpr {
     // Logical operations
     And(Box<GuardExpr>, Box<GuardExpr>),
@@ -115,7 +122,7 @@ pub enum GuardExpr {
 }
 
 /// Possible comparison operators for guards.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum GuardComp {
     Eq,
     Neq,
@@ -126,7 +133,7 @@ pub enum GuardComp {
 }
 
 /// Guards `expr` using the optional guard condition `guard`.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Guard {
     pub guard: Option<GuardExpr>,
     pub expr: Atom,
@@ -137,16 +144,17 @@ pub struct Guard {
 // ===================================
 
 /// Prototype of the cell definition
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Proto {
     /// Name of the primitive.
     pub name: ir::Id,
-    /// Parameter binding for primitives
-    pub params: Vec<u64>,
+    /// Parameter binding for primitives and parameterized components. The
+    /// bindings may refer to the parameters of the enclosing component.
+    pub params: Vec<ir::Width>,
 }
 
 /// The Cell AST nodes.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Cell {
     /// Name of the cell.
     pub name: ir::Id,
@@ -154,6 +162,8 @@ pub struct Cell {
     pub prototype: Proto,
     /// Attributes attached to this cell definition
     pub attributes: ir::Attributes,
+    /// Whether this cell is a reference to a cell passed in by the caller.
+    pub reference: bool,
 }
 
 /// Methods for constructing the structure AST nodes.
@@ -162,8 +172,9 @@ impl Cell {
     pub fn from(
         name: ir::Id,
         proto: ir::Id,
-        params: Vec<u64>,
+        params: Vec<ir::Width>,
         attributes: ir::Attributes,
+        reference: bool,
     ) -> Cell {
         Cell {
             name,
@@ -172,11 +183,12 @@ impl Cell {
                 params,
             },
             attributes,
+            reference,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Group {
     pub name: ir::Id,
     pub wires: Vec<Wire>,
@@ -185,7 +197,7 @@ pub struct Group {
 }
 
 /// Data for the `->` structure statement.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Wire {
     /// Source of the wire.
     pub src: Guard,
@@ -198,7 +210,7 @@ pub struct Wire {
 }
 
 /// Control AST nodes.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[allow(clippy::large_enum_variant)]
 pub enum Control {
     /// Represents sequential composition of control statements.
@@ -246,6 +258,17 @@ pub enum Control {
         /// Attributes
         attributes: ir::Attributes,
     },
+    /// Runs the body a fixed number of times.
+    Repeat {
+        /// Number of times the body is executed.
+        num_repeats: u64,
+
+        /// Control for the loop body.
+        body: Box<Control>,
+
+        /// Attributes
+        attributes: ir::Attributes,
+    },
     /// Runs the control for a list of subcomponents.
     Enable {
         /// Group to be enabled
@@ -257,6 +280,9 @@ pub enum Control {
     Invoke {
         /// Name of the component to be invoked.
         comp: ir::Id,
+        /// Bindings from `ref` cells of the invoked component to cells in
+        /// the current component.
+        ref_cells: Vec<(ir::Id, ir::Id)>,
         /// Input assignments
         inputs: Vec<(ir::Id, Atom)>,
         /// Output assignments
@@ -268,4 +294,32 @@ pub enum Control {
     },
     /// Control statement that does nothing.
     Empty {},
+    /// Sequential composition of statically timed control statements.
+    StaticSeq {
+        /// List of `Control` statements to run in sequence.
+        stmts: Vec<Control>,
+        /// Attributes
+        attributes: ir::Attributes,
+    },
+    /// Parallel composition of statically timed control statements.
+    StaticPar {
+        /// List of `Control` statements to run in parallel.
+        stmts: Vec<Control>,
+        /// Attributes
+        attributes: ir::Attributes,
+    },
+    /// If statement with statically timed branches.
+    StaticIf {
+        /// Port that connects the conditional check.
+        port: Port,
+
+        /// Control for the true branch.
+        tbranch: Box<Control>,
+
+        /// Control for the false branch.
+        fbranch: Box<Control>,
+
+        /// Attributes
+        attributes: ir::Attributes,
+    },
 }
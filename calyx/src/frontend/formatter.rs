@@ -0,0 +1,780 @@
+//! Source formatter for Calyx programs.
+//!
+//! Unlike [crate::ir::Printer], the formatter works on the concrete syntax
+//! tree produced by [CalyxParser] so that it can retain comments and the
+//! order of definitions in the original program. It normalizes indentation,
+//! spacing, the syntax of attributes, and sorts imports.
+use super::parser::{CalyxParser, Rule};
+use crate::errors::CalyxResult;
+use pest::iterators::Pair;
+use std::path::Path;
+
+type P<'i> = Pair<'i, Rule>;
+
+/// Number of spaces used for each level of indentation.
+const INDENT: usize = 2;
+/// Invocations longer than this are split across multiple lines.
+const MAX_WIDTH: usize = 80;
+
+/// Format the Calyx program `source` read from `path`.
+pub fn format_source(source: &str, path: &Path) -> CalyxResult<String> {
+    // Report errors the same way the compiler does before formatting.
+    CalyxParser::parse_source(source, path)?;
+    let file = <CalyxParser as pest::Parser<Rule>>::parse(Rule::file, source)
+        .map_err(|e| e.with_path(&path.to_string_lossy()))?
+        .next()
+        .unwrap();
+
+    let mut fmt = Formatter::new(source);
+    fmt.file(file);
+    Ok(fmt.out)
+}
+
+/// Returns the spans of all the comments in `src`.
+fn comments(src: &str) -> Vec<(usize, usize)> {
+    let bytes = src.as_bytes();
+    let mut comments = vec![];
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let rest = &src[idx..];
+        if bytes[idx] == b'"' {
+            // Skip string literals. They do not contain escaped quotes.
+            idx += rest[1..].find('"').map(|end| end + 2).unwrap_or(rest.len());
+        } else if rest.starts_with("//") {
+            let end = idx + rest.find('\n').unwrap_or(rest.len());
+            comments.push((idx, end));
+            idx = end;
+        } else if rest.starts_with("/*") {
+            let end =
+                idx + rest.find("*/").map(|e| e + 2).unwrap_or(rest.len());
+            comments.push((idx, end));
+            idx = end;
+        } else {
+            idx += 1;
+        }
+    }
+    comments
+}
+
+struct Formatter<'a> {
+    src: &'a str,
+    /// Spans of comments in `src`.
+    comments: Vec<(usize, usize)>,
+    /// Index of the next comment that needs to be emitted.
+    next_comment: usize,
+    /// Current indentation level.
+    indent: usize,
+    /// Offset in `src` where the last emitted item ended.
+    last: usize,
+    /// Separate the next item from the previous one by a blank line.
+    force_blank: bool,
+    out: String,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            comments: comments(src),
+            next_comment: 0,
+            indent: 0,
+            last: 0,
+            force_blank: false,
+            out: String::new(),
+        }
+    }
+
+    // ============== Layout ==============
+
+    fn push_line(&mut self, text: &str) {
+        let indent = " ".repeat(self.indent * INDENT);
+        for line in text.lines() {
+            if !line.is_empty() {
+                self.out.push_str(&indent);
+            }
+            self.out.push_str(line);
+            self.out.push('\n');
+        }
+    }
+
+    /// Emit a blank line if the next item starting at `start` was separated
+    /// from the previous one by a blank line in the source.
+    fn separate(&mut self, start: usize) {
+        let blank =
+            self.force_blank || self.gap(start).matches('\n').count() > 1;
+        self.force_blank = false;
+        if blank && !self.out.is_empty() && !self.out.ends_with("{\n") {
+            self.out.push('\n');
+        }
+    }
+
+    /// Source text between the last emitted item and `start`. Comments nested
+    /// inside an item start before the item ends and have an empty gap.
+    fn gap(&self, start: usize) -> &'a str {
+        if start >= self.last {
+            &self.src[self.last..start]
+        } else {
+            ""
+        }
+    }
+
+    /// Emit all comments that start before `upto`. Comments on the same
+    /// line as the previously emitted item are attached to that line.
+    fn emit_comments(&mut self, upto: usize) {
+        while let Some(&(start, end)) = self.comments.get(self.next_comment) {
+            if start >= upto {
+                break;
+            }
+            let src = self.src;
+            let text = src[start..end].trim_end();
+            if !self.out.is_empty() && !self.gap(start).contains('\n') {
+                self.out.pop();
+                self.out.push(' ');
+                self.out.push_str(text);
+                self.out.push('\n');
+            } else {
+                self.separate(start);
+                // Multi-line comments are emitted verbatim.
+                let indent = " ".repeat(self.indent * INDENT);
+                self.out.push_str(&indent);
+                self.out.push_str(text);
+                self.out.push('\n');
+            }
+            self.last = self.last.max(end);
+            self.next_comment += 1;
+        }
+    }
+
+    /// Emit an item that spans `start..end` in the source.
+    fn line(&mut self, start: usize, end: usize, text: &str) {
+        self.emit_comments(start);
+        self.separate(start);
+        self.push_line(text);
+        self.last = end;
+    }
+
+    /// Emit the header of a block ending with `{` at `brace`.
+    fn open(&mut self, start: usize, brace: usize, text: &str) {
+        self.line(start, brace + 1, text);
+        self.indent += 1;
+    }
+
+    /// Close the block whose `}` is at `brace`. `text` starts with the `}`
+    /// and may reopen another block, like `} else {`.
+    fn close(&mut self, brace: usize, end: usize, text: &str) {
+        self.emit_comments(brace);
+        self.indent -= 1;
+        self.push_line(text);
+        self.last = end;
+        if text.ends_with('{') {
+            self.indent += 1;
+        }
+    }
+
+    /// Emit a block `text {` ... `}` whose items are emitted by `body`.
+    /// Blocks without any items or comments are emitted on one line.
+    fn block<F>(&mut self, pair: &P, text: &str, body: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        let (start, end) = (pair.as_span().start(), pair.as_span().end());
+        let brace = self.find_after(start, '{');
+        let has_comments = self
+            .comments
+            .get(self.next_comment)
+            .map(|&(s, _)| s < end)
+            .unwrap_or(false);
+        let empty = self.src[brace + 1..end - 1].trim().is_empty();
+        if empty && !has_comments {
+            self.line(start, end, &format!("{} {{}}", text));
+        } else {
+            self.open(start, brace, &format!("{} {{", text));
+            body(self);
+            self.close(end - 1, end, "}");
+        }
+    }
+
+    /// Offset of the first `ch` at or after `start` that is not in a comment.
+    fn find_after(&self, start: usize, ch: char) -> usize {
+        let mut idx = start;
+        loop {
+            let off = idx + self.src[idx..].find(ch).unwrap();
+            match self.comments.iter().find(|&&(s, e)| s <= off && off < e) {
+                Some(&(_, e)) => idx = e,
+                None => return off,
+            }
+        }
+    }
+
+    // ============== Top level ==============
+
+    fn file(&mut self, file: P) {
+        for pair in file.into_inner() {
+            match pair.as_rule() {
+                Rule::imports => self.imports(pair),
+                Rule::extern_or_component => {
+                    let item = pair.into_inner().next().unwrap();
+                    if !self.out.is_empty() {
+                        self.force_blank = true;
+                    }
+                    match item.as_rule() {
+                        Rule::ext => self.ext(item),
+                        _ => self.component(item),
+                    }
+                }
+                Rule::EOI => (),
+                _ => unreachable!("unexpected rule: {:?}", pair.as_rule()),
+            }
+        }
+        self.emit_comments(self.src.len());
+    }
+
+    /// Imports are sorted and deduplicated. Comments directly above an import
+    /// or on the same line as it move with it.
+    fn imports(&mut self, imports: P) {
+        let mut entries = vec![];
+        for path in imports.into_inner() {
+            let span = path.as_span();
+            let start = self.src[..span.start()].rfind("import").unwrap();
+            let mut end = self.find_after(span.end(), ';') + 1;
+            if entries.is_empty() {
+                // Comments before the first import stay at the top.
+                self.emit_comments(start);
+            }
+            let mut text = String::new();
+            while let Some(&(s, e)) = self.comments.get(self.next_comment) {
+                if s >= start {
+                    break;
+                }
+                text.push_str(self.src[s..e].trim_end());
+                text.push('\n');
+                self.next_comment += 1;
+            }
+            text.push_str(&format!("import {};", path.as_str()));
+            if let Some(&(s, e)) = self.comments.get(self.next_comment) {
+                if s >= end && !self.src[end..s].contains('\n') {
+                    text.push(' ');
+                    text.push_str(self.src[s..e].trim_end());
+                    self.next_comment += 1;
+                    end = e;
+                }
+            }
+            self.last = end;
+            entries.push((path.as_str().to_string(), text));
+        }
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        entries.dedup_by(|(a, _), (b, _)| a == b);
+        for (_, text) in entries {
+            self.push_line(&text);
+        }
+    }
+
+    fn ext(&mut self, ext: P) {
+        let mut inner = ext.clone().into_inner();
+        let path = inner.next().unwrap();
+        self.block(&ext, &format!("extern {}", path.as_str()), |fmt| {
+            for prim in inner {
+                let span = prim.as_span();
+                let text = fmt.primitive(prim);
+                fmt.line(span.start(), span.end(), &text);
+            }
+        });
+    }
+
+    fn primitive(&self, prim: P) -> String {
+        let mut out = String::new();
+        for pair in prim.into_inner() {
+            match pair.as_rule() {
+                Rule::comb => out.push_str("comb "),
+                Rule::name_with_attribute => {
+                    out.push_str("primitive ");
+                    out.push_str(&self.name_with_attribute(pair));
+                }
+                Rule::sig_with_params => {
+                    out.push_str(&self.sig_with_params(pair))
+                }
+                _ => unreachable!(),
+            }
+        }
+        out.push(';');
+        out
+    }
+
+    fn component(&mut self, comp: P) {
+        let mut header = String::new();
+        let mut sections = vec![];
+        for pair in comp.clone().into_inner() {
+            match pair.as_rule() {
+                Rule::comb => header.push_str("comb "),
+                Rule::name_with_attribute => {
+                    header.push_str("component ");
+                    header.push_str(&self.name_with_attribute(pair));
+                }
+                Rule::sig_with_params => {
+                    header.push_str(&self.sig_with_params(pair))
+                }
+                _ => sections.push(pair),
+            }
+        }
+        self.block(&comp, &header, |fmt| {
+            for section in sections {
+                match section.as_rule() {
+                    Rule::cells => fmt.cells(section),
+                    Rule::connections => fmt.connections(section),
+                    Rule::control => fmt.control(section),
+                    _ => unreachable!(),
+                }
+            }
+        });
+    }
+
+    // ============== Structure ==============
+
+    fn cells(&mut self, cells: P) {
+        self.block(&cells, "cells", |fmt| {
+            for cell in cells.clone().into_inner() {
+                let span = cell.as_span();
+                let text = fmt.cell(cell);
+                fmt.line(span.start(), span.end(), &text);
+            }
+        });
+    }
+
+    fn cell(&self, cell: P) -> String {
+        let inner = cell.into_inner().next().unwrap();
+        let mut out = String::new();
+        let mut names = vec![];
+        for pair in inner.into_inner() {
+            match pair.as_rule() {
+                Rule::at_attributes => out.push_str(&self.at_attributes(pair)),
+                Rule::reference => out.push_str("ref "),
+                Rule::identifier => names.push(pair.as_str()),
+                Rule::args => {
+                    let args = pair
+                        .into_inner()
+                        .map(|arg| arg.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    out.push_str(&format!(
+                        "{} = {}({});",
+                        names[0], names[1], args
+                    ));
+                }
+                _ => unreachable!(),
+            }
+        }
+        out
+    }
+
+    fn connections(&mut self, conns: P) {
+        self.block(&conns, "wires", |fmt| {
+            for pair in conns.clone().into_inner() {
+                match pair.as_rule() {
+                    Rule::wire => {
+                        let span = pair.as_span();
+                        let text = fmt.wire(pair);
+                        fmt.line(span.start(), span.end(), &text);
+                    }
+                    Rule::group => fmt.group(pair),
+                    _ => unreachable!(),
+                }
+            }
+        });
+    }
+
+    fn group(&mut self, group: P) {
+        let mut header = String::new();
+        let mut wires = vec![];
+        for pair in group.clone().into_inner() {
+            match pair.as_rule() {
+                Rule::comb => header.push_str("comb "),
+                Rule::static_latency => {
+                    let latency = pair.into_inner().next().unwrap();
+                    header.push_str(&format!("static<{}> ", latency.as_str()))
+                }
+                Rule::name_with_attribute => {
+                    header.push_str("group ");
+                    header.push_str(&self.name_with_attribute(pair));
+                }
+                Rule::wire => wires.push(pair),
+                _ => unreachable!(),
+            }
+        }
+        self.block(&group, &header, |fmt| {
+            for wire in wires {
+                let span = wire.as_span();
+                let text = fmt.wire(wire);
+                fmt.line(span.start(), span.end(), &text);
+            }
+        });
+    }
+
+    fn wire(&self, wire: P) -> String {
+        let mut inner = wire.into_inner();
+        let attrs = self.at_attributes(inner.next().unwrap());
+        let dst = self.expr(inner.next().unwrap());
+        let src = self.expr(inner.next().unwrap());
+        format!("{}{} = {};", attrs, dst, src)
+    }
+
+    /// Format expressions appearing in assignments and guards.
+    fn expr(&self, pair: P) -> String {
+        let rule = pair.as_rule();
+        let children = pair.clone().into_inner().collect::<Vec<_>>();
+        match rule {
+            Rule::LHS | Rule::expr => self.expr(children[0].clone()),
+            Rule::port => children
+                .iter()
+                .map(|c| c.as_str())
+                .collect::<Vec<_>>()
+                .join("."),
+            Rule::hole => {
+                format!("{}[{}]", children[0].as_str(), children[1].as_str())
+            }
+            Rule::select => {
+                let bits = children[1]
+                    .clone()
+                    .into_inner()
+                    .map(|b| b.as_str())
+                    .collect::<Vec<_>>()
+                    .join(":");
+                format!("{}[{}]", self.expr(children[0].clone()), bits)
+            }
+            Rule::num_lit
+            | Rule::identifier
+            | Rule::guard_eq
+            | Rule::guard_neq
+            | Rule::guard_leq
+            | Rule::guard_geq
+            | Rule::guard_lt
+            | Rule::guard_gt => pair.as_str().to_string(),
+            Rule::guard_or => " | ".to_string(),
+            Rule::guard_and => " & ".to_string(),
+            Rule::guard_expr => children
+                .into_iter()
+                .map(|c| self.expr(c))
+                .collect::<String>(),
+            Rule::cmp_expr => format!(
+                "{} {} {}",
+                self.expr(children[0].clone()),
+                self.expr(children[1].clone()),
+                self.expr(children[2].clone())
+            ),
+            Rule::term => {
+                let rules =
+                    children.iter().map(|c| c.as_rule()).collect::<Vec<_>>();
+                match rules.as_slice() {
+                    [Rule::guard_not, Rule::expr] => {
+                        format!("!{}", self.expr(children[1].clone()))
+                    }
+                    [Rule::guard_not, _] => {
+                        format!("!({})", self.expr(children[1].clone()))
+                    }
+                    [Rule::guard_expr] => {
+                        format!("({})", self.expr(children[0].clone()))
+                    }
+                    _ => self.expr(children[0].clone()),
+                }
+            }
+            Rule::switch_stmt => format!(
+                "{} ? {}",
+                self.expr(children[0].clone()),
+                self.expr(children[1].clone())
+            ),
+            _ => unreachable!("unexpected rule: {:?}", rule),
+        }
+    }
+
+    // ============== Signatures and attributes ==============
+
+    fn name_with_attribute(&self, pair: P) -> String {
+        let mut inner = pair.into_inner();
+        let name = inner.next().unwrap().as_str().to_string();
+        match inner.next() {
+            Some(attrs) => name + &self.attributes(attrs),
+            None => name,
+        }
+    }
+
+    /// Attributes of the form `<"static"=1>`.
+    fn attributes(&self, attrs: P) -> String {
+        let attrs = attrs
+            .into_inner()
+            .map(|attr| {
+                let mut kv = attr.into_inner();
+                let key = kv.next().unwrap().as_str();
+                let val = kv.next().unwrap().as_str();
+                format!("{}={}", key, val)
+            })
+            .collect::<Vec<_>>();
+        format!("<{}>", attrs.join(", "))
+    }
+
+    /// Attributes of the form `@static(1)`. Values equal to 1 are elided. The
+    /// result has a trailing space if there are any attributes.
+    fn at_attributes(&self, attrs: P) -> String {
+        attrs
+            .into_inner()
+            .map(|attr| {
+                let mut kv = attr.into_inner();
+                let key = kv.next().unwrap().as_str();
+                let val = kv
+                    .next()
+                    .map(|v| v.into_inner().next().unwrap().as_str())
+                    .unwrap_or("1");
+                if val.trim_start_matches('0') == "1" {
+                    format!("@{} ", key)
+                } else {
+                    format!("@{}({}) ", key, val)
+                }
+            })
+            .collect()
+    }
+
+    fn sig_with_params(&self, pair: P) -> String {
+        let mut out = String::new();
+        for pair in pair.into_inner() {
+            match pair.as_rule() {
+                Rule::params => {
+                    let params = pair
+                        .into_inner()
+                        .map(|p| p.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    out.push_str(&format!("[{}]", params));
+                }
+                Rule::signature => {
+                    let (mut inputs, mut outputs) =
+                        (String::new(), String::new());
+                    for ports in pair.into_inner() {
+                        let formatted = ports
+                            .clone()
+                            .into_inner()
+                            .map(|p| self.io_port(p))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        match ports.as_rule() {
+                            Rule::inputs => inputs = formatted,
+                            _ => outputs = formatted,
+                        }
+                    }
+                    out.push_str(&format!("({}) -> ({})", inputs, outputs));
+                }
+                _ => unreachable!(),
+            }
+        }
+        out
+    }
+
+    fn io_port(&self, port: P) -> String {
+        let mut inner = port.into_inner().peekable();
+        let attrs = match inner.peek().map(|p| p.as_rule()) {
+            Some(Rule::at_attributes) => {
+                self.at_attributes(inner.next().unwrap())
+            }
+            _ => String::new(),
+        };
+        let name = inner.next().unwrap().as_str();
+        let width = inner.next().unwrap().as_str();
+        format!("{}{}: {}", attrs, name, width)
+    }
+
+    // ============== Control ==============
+
+    fn control(&mut self, control: P) {
+        match control.clone().into_inner().next() {
+            None => {
+                let span = control.as_span();
+                self.line(span.start(), span.end(), "control {}");
+            }
+            Some(block) => {
+                self.block(&control, "control", |fmt| fmt.stmts(block))
+            }
+        }
+    }
+
+    /// Emit the statements in a `block`.
+    fn stmts(&mut self, block: P) {
+        for pair in block.into_inner() {
+            match pair.as_rule() {
+                Rule::stmt => self.stmt(pair),
+                Rule::stmts_without_block => self.stmts(pair),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    fn stmt(&mut self, stmt: P) {
+        let stmt = stmt.into_inner().next().unwrap();
+        let span = stmt.as_span();
+        let rule = stmt.as_rule();
+        let mut inner = stmt.clone().into_inner();
+        let attrs = self.at_attributes(inner.next().unwrap());
+        match rule {
+            Rule::enable => {
+                let name = inner.next().unwrap().as_str();
+                self.line(
+                    span.start(),
+                    span.end(),
+                    &format!("{}{};", attrs, name),
+                );
+            }
+            Rule::invoke => {
+                let text = self.invoke(attrs, inner.collect());
+                self.line(span.start(), span.end(), &text);
+            }
+            Rule::seq | Rule::par | Rule::static_seq | Rule::static_par => {
+                let keyword = match rule {
+                    Rule::seq => "seq",
+                    Rule::par => "par",
+                    Rule::static_seq => "static seq",
+                    _ => "static par",
+                };
+                let stmts = inner.collect::<Vec<_>>();
+                self.block(&stmt, &format!("{}{}", attrs, keyword), |fmt| {
+                    for s in stmts {
+                        fmt.stmt(s);
+                    }
+                });
+            }
+            Rule::while_stmt | Rule::repeat_stmt => {
+                let head = inner.next().unwrap();
+                let head = match rule {
+                    Rule::while_stmt => {
+                        format!("while {}", self.port_with(head))
+                    }
+                    _ => format!("repeat {}", head.as_str()),
+                };
+                let body = inner.next().unwrap();
+                let brace = body.as_span().start();
+                self.open(
+                    span.start(),
+                    brace,
+                    &format!("{}{} {{", attrs, head),
+                );
+                let end = body.as_span().end();
+                self.stmts(body);
+                self.close(end - 1, end, "}");
+            }
+            Rule::if_stmt | Rule::static_if_stmt => {
+                self.if_chain(stmt, span.start(), "");
+            }
+            _ => unreachable!("unexpected rule: {:?}", rule),
+        }
+    }
+
+    /// Emit an `if` statement along with any `else if` branches. `lead` is
+    /// the text preceding the statement, i.e., `} else ` for `else if`.
+    fn if_chain(&mut self, stmt: P, start: usize, lead: &str) {
+        let keyword = match stmt.as_rule() {
+            Rule::if_stmt => "if",
+            _ => "static if",
+        };
+        let mut inner = stmt.into_inner();
+        let attrs = self.at_attributes(inner.next().unwrap());
+        let cond = inner.next().unwrap();
+        let cond = match cond.as_rule() {
+            Rule::port_with => self.port_with(cond),
+            _ => self.expr(cond),
+        };
+        let tbranch = inner.next().unwrap();
+        let (brace, end) = (tbranch.as_span().start(), tbranch.as_span().end());
+        let header = format!("{}{}{} {} {{", lead, attrs, keyword, cond);
+        if lead.is_empty() {
+            self.open(start, brace, &header);
+        } else {
+            self.close(start, brace + 1, &header);
+        }
+        self.stmts(tbranch);
+        match inner.next() {
+            None => self.close(end - 1, end, "}"),
+            Some(fbranch) if fbranch.as_rule() == Rule::block => {
+                let (fstart, fend) =
+                    (fbranch.as_span().start(), fbranch.as_span().end());
+                self.close(end - 1, fstart + 1, "} else {");
+                self.stmts(fbranch);
+                self.close(fend - 1, fend, "}");
+            }
+            Some(nested) => self.if_chain(nested, end - 1, "} else "),
+        }
+    }
+
+    fn port_with(&self, pair: P) -> String {
+        let mut inner = pair.into_inner();
+        let port = self.expr(inner.next().unwrap());
+        match inner.next() {
+            Some(group) => format!("{} with {}", port, group.as_str()),
+            None => port,
+        }
+    }
+
+    /// Format an invoke. It is split across lines if it is too long.
+    fn invoke(&self, attrs: String, parts: Vec<P>) -> String {
+        let comp = parts[0].as_str();
+        let refs = parts[1]
+            .clone()
+            .into_inner()
+            .map(|arg| {
+                let mut kv = arg.into_inner();
+                let k = kv.next().unwrap().as_str();
+                let v = kv.next().unwrap().as_str();
+                format!("{}={}", k, v)
+            })
+            .collect::<Vec<_>>();
+        let refs = if refs.is_empty() {
+            String::new()
+        } else {
+            format!("[{}]", refs.join(", "))
+        };
+        let args = |pair: &P| {
+            pair.clone()
+                .into_inner()
+                .map(|arg| {
+                    let mut kv = arg.into_inner();
+                    let k = kv.next().unwrap().as_str();
+                    let v = self.expr(kv.next().unwrap());
+                    format!("{} = {}", k, v)
+                })
+                .collect::<Vec<_>>()
+        };
+        let (inputs, outputs) = (args(&parts[2]), args(&parts[3]));
+        let with = parts
+            .get(4)
+            .map(|g| format!(" with {}", g.as_str()))
+            .unwrap_or_default();
+
+        let line = format!(
+            "{}invoke {}{}({})({}){};",
+            attrs,
+            comp,
+            refs,
+            inputs.join(", "),
+            outputs.join(", "),
+            with
+        );
+        if line.len() + self.indent * INDENT <= MAX_WIDTH {
+            return line;
+        }
+        let indent = " ".repeat(INDENT);
+        let split = |args: Vec<String>| {
+            if args.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "\n{}{}\n",
+                    indent,
+                    args.join(&format!(",\n{}", indent))
+                )
+            }
+        };
+        format!(
+            "{}invoke {}{}({})({}){};",
+            attrs,
+            comp,
+            refs,
+            split(inputs),
+            split(outputs),
+            with
+        )
+    }
+}
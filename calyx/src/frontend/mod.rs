@@ -5,8 +5,10 @@
 //! in the `ir` module.
 
 pub mod ast;
+mod formatter;
 pub mod parser;
 mod workspace;
 
 pub use ast::NamespaceDef;
+pub use formatter::format_source;
 pub use workspace::Workspace;
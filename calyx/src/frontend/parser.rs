@@ -3,14 +3,14 @@
 //! Parser for Calyx programs.
 use super::ast::{self, BitNum, GuardComp as GC, GuardExpr, NumType};
 use crate::errors::{self, CalyxResult, Span};
-use crate::ir;
+use crate::ir::{self, UBig};
 use pest::prec_climber::{Assoc, Operator, PrecClimber};
 use pest_consume::{match_nodes, Error, Parser};
 use std::convert::TryInto;
 use std::fs;
 use std::io::Read;
 use std::path::Path;
-use std::rc::Rc;
+use std::sync::Arc;
 
 type ParseResult<T> = Result<T, Error<Rule>>;
 
@@ -18,9 +18,9 @@ type ParseResult<T> = Result<T, Error<Rule>>;
 #[derive(Clone)]
 struct UserData {
     /// Input to the parser
-    pub input: Rc<str>,
+    pub input: Arc<str>,
     /// Path of the file
-    pub file: Rc<str>,
+    pub file: Arc<str>,
 }
 
 // user data is the input program so that we can create ir::Id's
@@ -57,16 +57,22 @@ impl CalyxParser {
             ))
         })?;
         let string_content = std::str::from_utf8(content)?;
+        Self::parse_source(string_content, path)
+    }
+
+    /// Parse the in-memory contents of the file at `path`. Positions in the
+    /// resulting AST refer to `path`.
+    pub fn parse_source(
+        source: &str,
+        path: &Path,
+    ) -> CalyxResult<ast::NamespaceDef> {
         let user_data = UserData {
-            input: Rc::from(string_content),
-            file: Rc::from(path.to_string_lossy()),
+            input: Arc::from(source),
+            file: Arc::from(path.to_string_lossy()),
         };
-        let inputs = CalyxParser::parse_with_userdata(
-            Rule::file,
-            string_content,
-            user_data,
-        )
-        .map_err(|e| e.with_path(&path.to_string_lossy()))?;
+        let inputs =
+            CalyxParser::parse_with_userdata(Rule::file, source, user_data)
+                .map_err(|e| e.with_path(&path.to_string_lossy()))?;
         let input = inputs.single()?;
         Ok(CalyxParser::file(input)?)
     }
@@ -79,8 +85,8 @@ impl CalyxParser {
             ))
         })?;
         let user_data = UserData {
-            input: Rc::from(buf.as_ref()),
-            file: Rc::from("<stdin>"),
+            input: Arc::from(buf.as_ref()),
+            file: Arc::from("<stdin>"),
         };
         let inputs =
             CalyxParser::parse_with_userdata(Rule::file, &buf, user_data)?;
@@ -90,7 +96,7 @@ impl CalyxParser {
 
     fn get_span(node: &Node) -> Span {
         let ud = node.user_data();
-        Span::new(node.as_span(), Rc::clone(&ud.file), Rc::clone(&ud.input))
+        Span::new(node.as_span(), Arc::clone(&ud.file), Arc::clone(&ud.input))
     }
 }
 
@@ -114,6 +120,10 @@ impl CalyxParser {
         Ok(())
     }
 
+    fn reference(_input: Node) -> ParseResult<()> {
+        Ok(())
+    }
+
     // ================ Literals =====================
     fn identifier(input: Node) -> ParseResult<ir::Id> {
         let span = Self::get_span(&input);
@@ -131,28 +141,27 @@ impl CalyxParser {
         Err(input.error("Expected number with bitwidth (like 32'd10)."))
     }
 
-    fn hex(input: Node) -> ParseResult<u64> {
-        u64::from_str_radix(input.as_str(), 16)
+    fn hex(input: Node) -> ParseResult<UBig> {
+        UBig::from_str_radix(input.as_str(), 16)
             .map_err(|_| input.error("Expected hexadecimal number"))
     }
-    fn decimal(input: Node) -> ParseResult<u64> {
-        #[allow(clippy::from_str_radix_10)]
-        u64::from_str_radix(input.as_str(), 10)
+    fn decimal(input: Node) -> ParseResult<UBig> {
+        UBig::from_str_radix(input.as_str(), 10)
             .map_err(|_| input.error("Expected decimal number"))
     }
-    fn octal(input: Node) -> ParseResult<u64> {
-        u64::from_str_radix(input.as_str(), 8)
+    fn octal(input: Node) -> ParseResult<UBig> {
+        UBig::from_str_radix(input.as_str(), 8)
             .map_err(|_| input.error("Expected octal number"))
     }
-    fn binary(input: Node) -> ParseResult<u64> {
-        u64::from_str_radix(input.as_str(), 2)
+    fn binary(input: Node) -> ParseResult<UBig> {
+        UBig::from_str_radix(input.as_str(), 2)
             .map_err(|_| input.error("Expected binary number"))
     }
 
     fn num_lit(input: Node) -> ParseResult<BitNum> {
         let ud = input.user_data();
-        let input_ref = Rc::clone(&ud.input);
-        let file_ref = Rc::clone(&ud.file);
+        let input_ref = Arc::clone(&ud.input);
+        let file_ref = Arc::clone(&ud.file);
         let num = match_nodes!(
             input.clone().into_children();
             [bitwidth(width), decimal(val)] => BitNum {
@@ -198,11 +207,7 @@ impl CalyxParser {
 
         );
 
-        // the below cast is safe since the width must be less than 64 for
-        // the given literal to be unrepresentable
-        if num.width == 0
-            || (num.width < 64 && u64::pow(2, num.width as u32) <= num.val)
-        {
+        if num.width == 0 || num.val.bit_len() as u64 > num.width {
             let lit_str = match num.num_type {
                 NumType::Binary => format!("{:b}", num.val),
                 NumType::Decimal => format!("{}", num.val),
@@ -231,11 +236,19 @@ impl CalyxParser {
     }
 
     // ================ Attributes =====================
-    fn attribute(input: Node) -> ParseResult<(String, u64)> {
-        Ok(match_nodes!(
-            input.into_children();
-            [string_lit(key), bitwidth(num)] => (key, num)
-        ))
+    fn attr_num(input: Node) -> ParseResult<UBig> {
+        UBig::from_str_radix(input.as_str(), 10)
+            .map_err(|_| input.error("Expected attribute value"))
+    }
+
+    fn attribute(input: Node) -> ParseResult<(String, UBig)> {
+        let (key, num) = match_nodes!(
+            input.clone().into_children();
+            [string_lit(key), attr_num(num)] => (key, num)
+        );
+        ir::Attributes::check_value(&key, &num)
+            .map_err(|e| input.error(format!("{:?}", e)))?;
+        Ok((key, num))
     }
     fn attributes(input: Node) -> ParseResult<ir::Attributes> {
         match_nodes!(
@@ -253,19 +266,22 @@ impl CalyxParser {
         ))
     }
 
-    fn attr_val(input: Node) -> ParseResult<u64> {
+    fn attr_val(input: Node) -> ParseResult<UBig> {
         Ok(match_nodes!(
             input.into_children();
-            [bitwidth(num)] => num
+            [attr_num(num)] => num
         ))
     }
 
-    fn at_attribute(input: Node) -> ParseResult<(String, u64)> {
-        Ok(match_nodes!(
-            input.into_children();
+    fn at_attribute(input: Node) -> ParseResult<(String, UBig)> {
+        let (key, num) = match_nodes!(
+            input.clone().into_children();
             [identifier(key), attr_val(num)] => (key.id, num),
-            [identifier(key)] => (key.id, 1)
-        ))
+            [identifier(key)] => (key.id, UBig::from(1u8))
+        );
+        ir::Attributes::check_value(&key, &num)
+            .map_err(|e| input.error(format!("{:?}", e)))?;
+        Ok((key, num))
     }
 
     fn at_attributes(input: Node) -> ParseResult<ir::Attributes> {
@@ -283,10 +299,18 @@ impl CalyxParser {
         ))
     }
 
-    fn args(input: Node) -> ParseResult<Vec<u64>> {
+    fn arg(input: Node) -> ParseResult<ir::Width> {
+        Ok(match_nodes!(
+            input.into_children();
+            [bitwidth(value)] => ir::Width::Const { value },
+            [identifier(value)] => ir::Width::Param { value }
+        ))
+    }
+
+    fn args(input: Node) -> ParseResult<Vec<ir::Width>> {
         Ok(match_nodes!(
             input.into_children();
-            [bitwidth(bw)..] => bw.collect(),
+            [arg(args)..] => args.collect(),
             [] => vec![]
         ))
     }
@@ -376,7 +400,9 @@ impl CalyxParser {
         Ok(match_nodes!(
             input.into_children();
             [at_attributes(attrs), identifier(id), identifier(prim), args(args)] =>
-            ast::Cell::from(id, prim, args, attrs.add_span(span))
+                ast::Cell::from(id, prim, args, attrs.add_span(span), false),
+            [at_attributes(attrs), reference(_), identifier(id), identifier(prim), args(args)] =>
+                ast::Cell::from(id, prim, args, attrs.add_span(span), true),
         ))
     }
 
@@ -413,6 +439,28 @@ impl CalyxParser {
         ))
     }
 
+    fn bit_select(input: Node) -> ParseResult<(u64, u64)> {
+        Ok(match_nodes!(
+            input.into_children();
+            [bitwidth(msb), bitwidth(lsb)] => (msb, lsb),
+            [bitwidth(bit)] => (bit, bit)
+        ))
+    }
+
+    fn select(input: Node) -> ParseResult<ast::Atom> {
+        let (port, msb, lsb) = match_nodes!(
+            input.clone().into_children();
+            [port(port), bit_select(sel)] => (port, sel.0, sel.1)
+        );
+        if lsb > msb {
+            return Err(input.error(format!(
+                "Invalid bit-select `{}`: lower index is larger than the upper index",
+                input.as_str()
+            )));
+        }
+        Ok(ast::Atom::Select { port, msb, lsb })
+    }
+
     #[allow(clippy::upper_case_acronyms)]
     fn LHS(input: Node) -> ParseResult<ast::Port> {
         Ok(match_nodes!(
@@ -425,6 +473,7 @@ impl CalyxParser {
     fn expr(input: Node) -> ParseResult<ast::Atom> {
         match_nodes!(
             input.into_children();
+            [select(sel)] => Ok(sel),
             [LHS(port)] => Ok(ast::Atom::Port(port)),
             [num_lit(num)] => Ok(ast::Atom::Num(num)),
             [bad_num(_)] => unreachable!("bad_num returned non-error result"),
@@ -525,23 +574,48 @@ impl CalyxParser {
         ))
     }
 
-    fn group(input: Node) -> ParseResult<ast::Group> {
-        let span = Self::get_span(&input);
+    fn static_latency(input: Node) -> ParseResult<u64> {
         Ok(match_nodes!(
             input.into_children();
-            [name_with_attribute((name, attrs)), wire(wire)..] => ast::Group {
+            [bitwidth(latency)] => latency,
+        ))
+    }
+
+    fn group(input: Node) -> ParseResult<ast::Group> {
+        let span = Self::get_span(&input);
+        match_nodes!(
+            input.clone().into_children();
+            [name_with_attribute((name, attrs)), wire(wire)..] => Ok(ast::Group {
                 name,
                 attributes: attrs.add_span(span),
                 wires: wire.collect(),
                 is_comb: false,
-            },
-            [comb(_), name_with_attribute((name, attrs)), wire(wire)..] => ast::Group {
+            }),
+            [comb(_), name_with_attribute((name, attrs)), wire(wire)..] => Ok(ast::Group {
                 name,
                 attributes: attrs.add_span(span),
                 wires: wire.collect(),
                 is_comb: true,
+            }),
+            [static_latency(latency), name_with_attribute((name, attrs)), wire(wire)..] => {
+                match attrs.get("static") {
+                    Some(annotated) if annotated != latency => Err(input.error(format!(
+                        "Group has latency {} but is annotated with \"static\"={}",
+                        latency, annotated
+                    ))),
+                    _ => {
+                        let mut attributes = attrs.add_span(span);
+                        attributes.insert("static", latency);
+                        Ok(ast::Group {
+                            name,
+                            attributes,
+                            wires: wire.collect(),
+                            is_comb: false,
+                        })
+                    }
+                }
             }
-        ))
+        )
     }
 
     fn connections(
@@ -563,6 +637,7 @@ impl CalyxParser {
     fn invoke_arg(input: Node) -> ParseResult<(ir::Id, ast::Atom)> {
         Ok(match_nodes!(
             input.into_children();
+            [identifier(name), select(sel)] => (name, sel),
             [identifier(name), port(p)] => (name, ast::Atom::Port(p)),
             [identifier(name), num_lit(bn)] => (name, ast::Atom::Num(bn))
 
@@ -576,21 +651,37 @@ impl CalyxParser {
         ))
     }
 
+    fn invoke_ref_arg(input: Node) -> ParseResult<(ir::Id, ir::Id)> {
+        Ok(match_nodes!(
+            input.into_children();
+            [identifier(outcell), identifier(incell)] => (outcell, incell)
+        ))
+    }
+
+    fn invoke_ref_args(input: Node) -> ParseResult<Vec<(ir::Id, ir::Id)>> {
+        Ok(match_nodes!(
+            input.into_children();
+            [invoke_ref_arg(args)..] => args.collect()
+        ))
+    }
+
     fn invoke(input: Node) -> ParseResult<ast::Control> {
         let span = Self::get_span(&input);
         Ok(match_nodes!(
             input.into_children();
-            [at_attributes(attrs), identifier(comp), invoke_args(inputs), invoke_args(outputs)] =>
+            [at_attributes(attrs), identifier(comp), invoke_ref_args(ref_cells), invoke_args(inputs), invoke_args(outputs)] =>
                 ast::Control::Invoke {
                     comp,
+                    ref_cells,
                     inputs,
                     outputs,
                     attributes: attrs.add_span(span),
                     comb_group: None
                 },
-            [at_attributes(attrs), identifier(comp), invoke_args(inputs), invoke_args(outputs), identifier(group)] =>
+            [at_attributes(attrs), identifier(comp), invoke_ref_args(ref_cells), invoke_args(inputs), invoke_args(outputs), identifier(group)] =>
                 ast::Control::Invoke {
                     comp,
+                    ref_cells,
                     inputs,
                     outputs,
                     attributes: attrs.add_span(span),
@@ -684,6 +775,67 @@ impl CalyxParser {
         ))
     }
 
+    fn repeat_stmt(input: Node) -> ParseResult<ast::Control> {
+        let span = Self::get_span(&input);
+        Ok(match_nodes!(
+            input.into_children();
+            [at_attributes(attrs), bitwidth(num_repeats), block(stmt)] => ast::Control::Repeat {
+                num_repeats,
+                body: Box::new(stmt),
+                attributes: attrs.add_span(span),
+            }
+        ))
+    }
+
+    fn static_seq(input: Node) -> ParseResult<ast::Control> {
+        let span = Self::get_span(&input);
+        Ok(match_nodes!(
+            input.into_children();
+            [at_attributes(attrs), stmt(stmt)..] => ast::Control::StaticSeq {
+                stmts: stmt.collect(),
+                attributes: attrs.add_span(span),
+            }
+        ))
+    }
+
+    fn static_par(input: Node) -> ParseResult<ast::Control> {
+        let span = Self::get_span(&input);
+        Ok(match_nodes!(
+            input.into_children();
+            [at_attributes(attrs), stmt(stmt)..] => ast::Control::StaticPar {
+                stmts: stmt.collect(),
+                attributes: attrs.add_span(span),
+            }
+        ))
+    }
+
+    fn static_if_stmt(input: Node) -> ParseResult<ast::Control> {
+        let span = Self::get_span(&input);
+        Ok(match_nodes!(
+            input.into_children();
+            [at_attributes(attrs), port(port), block(stmt)] => ast::Control::StaticIf {
+                port,
+                tbranch: Box::new(stmt),
+                fbranch: Box::new(ast::Control::Empty{}),
+                attributes: attrs.add_span(span),
+            },
+            [at_attributes(attrs), port(port), block(tbranch), block(fbranch)] =>
+                ast::Control::StaticIf {
+                    port,
+                    tbranch: Box::new(tbranch),
+                    fbranch: Box::new(fbranch),
+                    attributes: attrs.add_span(span),
+                },
+            [at_attributes(attrs), port(port), block(tbranch), static_if_stmt(fbranch)] =>
+                ast::Control::StaticIf {
+                    port,
+                    tbranch: Box::new(tbranch),
+                    fbranch: Box::new(fbranch),
+                    attributes: attrs.add_span(span),
+                },
+        ))
+    }
+
     fn stmt(input: Node) -> ParseResult<ast::Control> {
         Ok(match_nodes!(
             input.into_children();
@@ -693,6 +845,10 @@ impl CalyxParser {
             [par(data)] => data,
             [if_stmt(data)] => data,
             [while_stmt(data)] => data,
+            [repeat_stmt(data)] => data,
+            [static_seq(data)] => data,
+            [static_par(data)] => data,
+            [static_if_stmt(data)] => data,
         ))
     }
 
@@ -726,7 +882,7 @@ impl CalyxParser {
             input.into_children();
             [
                 name_with_attribute((name, attributes)),
-                signature(sig),
+                sig_with_params((params, sig)),
                 cells(cells),
                 connections(connections),
                 control(control)
@@ -734,12 +890,40 @@ impl CalyxParser {
                 let (continuous_assignments, groups) = connections;
                 ast::ComponentDef {
                     name,
+                    params,
                     signature: sig,
                     cells,
                     groups,
                     continuous_assignments,
                     control,
                     attributes: attributes.add_span(span),
+                    is_comb: false,
+                }
+        }))
+    }
+
+    fn comb_component(input: Node) -> ParseResult<ast::ComponentDef> {
+        let span = Self::get_span(&input);
+        Ok(match_nodes!(
+            input.into_children();
+            [
+                comb(_),
+                name_with_attribute((name, attributes)),
+                sig_with_params((params, sig)),
+                cells(cells),
+                connections(connections)
+            ] => {
+                let (continuous_assignments, groups) = connections;
+                ast::ComponentDef {
+                    name,
+                    params,
+                    signature: sig,
+                    cells,
+                    groups,
+                    continuous_assignments,
+                    control: ast::Control::Empty {},
+                    attributes: attributes.add_span(span),
+                    is_comb: true,
                 }
         }))
     }
@@ -762,6 +946,7 @@ impl CalyxParser {
         Ok(match_nodes!(
             input.into_children();
             [component(comp)] => ExtOrComp::Comp(comp),
+            [comb_component(comp)] => ExtOrComp::Comp(comp),
             [ext(ext)] => ExtOrComp::Ext(ext)
         ))
     }
@@ -35,7 +35,7 @@ use crate::{
 /// relative path to this file. It *does not* look for `core.sv` on the library path.
 ///
 /// Finally, since `core.futil` does not `import` any file, the parsing process is completed.
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct Workspace {
     /// List of component definitions that need to be compiled.
     pub components: Vec<ComponentDef>,
@@ -104,7 +104,12 @@ impl Workspace {
         file: &Option<PathBuf>,
         lib_path: &Path,
     ) -> CalyxResult<Self> {
-        Self::construct_with_all_deps(file, lib_path, false)
+        Self::construct_with_all_deps(
+            NamespaceDef::construct(file)?,
+            file,
+            lib_path,
+            false,
+        )
     }
 
     /// Construct the Workspace using the given [NamespaceDef] and ignore all
@@ -113,7 +118,27 @@ impl Workspace {
         file: &Option<PathBuf>,
         lib_path: &Path,
     ) -> CalyxResult<Self> {
-        Self::construct_with_all_deps(file, lib_path, true)
+        Self::construct_with_all_deps(
+            NamespaceDef::construct(file)?,
+            file,
+            lib_path,
+            true,
+        )
+    }
+
+    /// Construct a new workspace from the in-memory contents of the file at
+    /// `file`. Imports are resolved relative to `file` and read from disk.
+    pub fn construct_from_source(
+        source: &str,
+        file: &Path,
+        lib_path: &Path,
+    ) -> CalyxResult<Self> {
+        Self::construct_with_all_deps(
+            parser::CalyxParser::parse_source(source, file)?,
+            &Some(file.to_path_buf()),
+            lib_path,
+            false,
+        )
     }
 
     fn get_parent(p: &Path) -> PathBuf {
@@ -133,13 +158,12 @@ impl Workspace {
     /// Construct the Workspace by transitively parsing all `import`ed Calyx
     /// files.
     fn construct_with_all_deps(
+        namespace: NamespaceDef,
         file: &Option<PathBuf>,
         lib_path: &Path,
         // Parse imported components as declarations
         shallow: bool,
     ) -> CalyxResult<Self> {
-        // Construct initial namespace.
-        let namespace = NamespaceDef::construct(file)?;
         let parent_path = file
             .as_ref()
             .map(|p| Self::get_parent(p))
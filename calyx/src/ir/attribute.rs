@@ -1,13 +1,36 @@
 use linked_hash_map::LinkedHashMap;
 use std::{convert::TryFrom, ops::Index};
 
+use super::UBig;
 use crate::errors::{CalyxResult, Span, WithPos};
 
+/// Attributes whose values are interpreted as numbers by the compiler. Their
+/// values must fit in 64 bits; other attributes can have arbitrarily wide
+/// values.
+pub const NUMERIC_ATTRIBUTES: &[&str] = &[
+    "bound",
+    "clk",
+    "done",
+    "external",
+    "go",
+    "inline",
+    "pos",
+    "read_together",
+    "reset",
+    "share",
+    "stable",
+    "state_share",
+    "static",
+    "toplevel",
+    "write_together",
+];
+
 /// Attributes associated with a specific IR structure.
 #[derive(Debug, Clone)]
 pub struct Attributes {
-    /// Mapping from the name of the attribute to its value.
-    pub(super) attrs: LinkedHashMap<String, u64>,
+    /// Mapping from the name of the attribute to its value. Values may be
+    /// wider than 64 bits.
+    pub(super) attrs: LinkedHashMap<String, UBig>,
     /// Source location information for the item
     span: Option<Span>,
 }
@@ -22,10 +45,10 @@ impl Default for Attributes {
     }
 }
 
-impl TryFrom<Vec<(String, u64)>> for Attributes {
+impl<V: Into<UBig>> TryFrom<Vec<(String, V)>> for Attributes {
     type Error = crate::errors::Error;
 
-    fn try_from(v: Vec<(String, u64)>) -> CalyxResult<Self> {
+    fn try_from(v: Vec<(String, V)>) -> CalyxResult<Self> {
         let mut attrs = LinkedHashMap::with_capacity(v.len());
         for (k, v) in v {
             if attrs.contains_key(&k) {
@@ -33,6 +56,8 @@ impl TryFrom<Vec<(String, u64)>> for Attributes {
                     "Multiple entries for attribute: {k}"
                 )));
             }
+            let v = v.into();
+            Attributes::check_value(&k, &v)?;
             attrs.insert(k, v);
         }
         Ok(Attributes { attrs, span: None })
@@ -60,11 +85,39 @@ impl Attributes {
     where
         S: ToString + std::hash::Hash,
     {
-        self.attrs.insert(key.to_string(), val);
+        self.attrs.insert(key.to_string(), val.into());
+    }
+
+    /// Returns an error if `key` is one of the [NUMERIC_ATTRIBUTES] and `val`
+    /// does not fit in 64 bits.
+    pub fn check_value(key: &str, val: &UBig) -> CalyxResult<()> {
+        if NUMERIC_ATTRIBUTES.contains(&key) && u64::try_from(val).is_err() {
+            return Err(crate::errors::Error::malformed_structure(format!(
+                "Value {val} of attribute `{key}' does not fit in 64 bits"
+            )));
+        }
+        Ok(())
     }
 
-    /// Get the value associated with an attribute key
-    pub fn get<S>(&self, key: S) -> Option<&u64>
+    /// Get the value associated with an attribute key. The values of
+    /// [NUMERIC_ATTRIBUTES] always fit in 64 bits; use [Attributes::get_big]
+    /// for attributes with wider values.
+    ///
+    /// # Panics
+    /// Panics if the value does not fit in 64 bits.
+    pub fn get<S>(&self, key: S) -> Option<u64>
+    where
+        S: std::fmt::Display + AsRef<str>,
+    {
+        self.get_big(&key).map(|v| {
+            u64::try_from(v).unwrap_or_else(|_| {
+                panic!("Value of attribute `{}' does not fit in 64 bits", key)
+            })
+        })
+    }
+
+    /// Get the value associated with an attribute key without truncating it.
+    pub fn get_big<S>(&self, key: S) -> Option<&UBig>
     where
         S: std::fmt::Display + AsRef<str>,
     {
@@ -85,7 +138,7 @@ impl Attributes {
     }
 
     /// Remove attribute with the name `key`
-    pub fn remove<S>(&mut self, key: S) -> Option<u64>
+    pub fn remove<S>(&mut self, key: S) -> Option<UBig>
     where
         S: ToString,
     {
@@ -93,7 +146,7 @@ impl Attributes {
     }
 
     /// Iterate over all attributes
-    pub fn iter(&self) -> impl Iterator<Item = (&String, &u64)> {
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &UBig)> {
         self.attrs.iter()
     }
 
@@ -102,6 +155,12 @@ impl Attributes {
         self.span = Some(span);
         self
     }
+
+    /// Replace the span information. Used by passes to mark the source
+    /// location that generated structure is derived from.
+    pub fn set_span(&mut self, span: Option<Span>) {
+        self.span = span;
+    }
 }
 
 impl<T: GetAttributes> WithPos for T {
@@ -114,10 +173,10 @@ impl<S> Index<&S> for Attributes
 where
     S: AsRef<str> + std::fmt::Display,
 {
-    type Output = u64;
+    type Output = UBig;
 
-    fn index(&self, key: &S) -> &u64 {
-        self.get(&key)
+    fn index(&self, key: &S) -> &UBig {
+        self.get_big(key)
             .unwrap_or_else(|| panic!("No key `{}` in attribute map", key))
     }
 }
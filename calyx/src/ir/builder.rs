@@ -1,5 +1,6 @@
 //! IR Builder. Provides convience methods to build various parts of the internal
 //! representation.
+use crate::errors::Span;
 use crate::ir::{self, LibrarySignatures, RRC, WRC};
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -13,6 +14,10 @@ use super::CellType;
 /// them with the `@generated` attribute.
 ///
 /// In order to disable this behavior, call [[ir::Builder::not_generated()]].
+///
+/// Passes can call [[ir::Builder::set_span()]] to attach the location of the
+/// source construct being compiled to the groups, cells, and assignments
+/// built afterwards.
 pub struct Builder<'a> {
     /// Component for which this builder is constructing.
     pub component: &'a mut ir::Component,
@@ -23,6 +28,8 @@ pub struct Builder<'a> {
     validate: bool,
     /// Cells added are generated during a compiler pass.
     generated: bool,
+    /// Source location attached to the constructs being built.
+    span: Option<Span>,
 }
 
 /// Signature of [Port]s for a [Cell].
@@ -40,6 +47,7 @@ impl<'a> Builder<'a> {
             validate: false,
             // By default, assume that builder is called from a pass
             generated: true,
+            span: None,
         }
     }
 
@@ -55,6 +63,12 @@ impl<'a> Builder<'a> {
         self
     }
 
+    /// Attach `span` to the groups, cells, and assignments built from now on.
+    /// Constants are shared between uses and never get a span.
+    pub fn set_span(&mut self, span: Option<Span>) {
+        self.span = span;
+    }
+
     /// Construct a new group and add it to the Component.
     /// The group is guaranteed to start with `prefix`.
     /// Returns a reference to the group.
@@ -79,9 +93,11 @@ impl<'a> Builder<'a> {
                 direction: ir::Direction::Inout,
                 parent: ir::PortParent::Group(WRC::from(&group)),
                 attributes: ir::Attributes::default(),
+                select: None,
             }));
             group.borrow_mut().holes.push(hole);
         }
+        group.borrow_mut().attributes.set_span(self.span.clone());
 
         // Add the group to the component.
         self.component.groups.add(Rc::clone(&group));
@@ -96,10 +112,13 @@ impl<'a> Builder<'a> {
     {
         let name = self.component.generate_name(prefix);
 
+        let mut attributes = ir::Attributes::default();
+        attributes.set_span(self.span.clone());
+
         // Check if there is a group with the same name.
         let group = Rc::new(RefCell::new(ir::CombGroup {
             name,
-            attributes: ir::Attributes::default(),
+            attributes,
             assignments: vec![],
         }));
 
@@ -113,7 +132,17 @@ impl<'a> Builder<'a> {
     /// pair, building and adding it to the component if needed..
     /// If the constant does not exist, it is added to the Context.
     pub fn add_constant(&mut self, val: u64, width: u64) -> RRC<ir::Cell> {
-        let name = ir::Cell::constant_name(val, width);
+        self.add_wide_constant(val.into(), width)
+    }
+
+    /// Same as [Builder::add_constant] but supports values that do not fit
+    /// in 64 bits.
+    pub fn add_wide_constant(
+        &mut self,
+        val: ir::UBig,
+        width: u64,
+    ) -> RRC<ir::Cell> {
+        let name = ir::Cell::constant_name(&val, width);
         // If this constant has already been instantiated, return the relevant
         // cell.
         if let Some(cell) = self.component.cells.find(&name) {
@@ -177,17 +206,19 @@ impl<'a> Builder<'a> {
         if self.generated {
             cell.borrow_mut().add_attribute("generated", 1);
         }
+        cell.borrow_mut().attributes.set_span(self.span.clone());
         self.component.cells.add(Rc::clone(&cell));
         cell
     }
 
     /// Add a component instance to this component using its name and port
-    /// signature.
+    /// signature. `is_comb` marks instances of combinational components.
     pub fn add_component<Pre>(
         &mut self,
         prefix: Pre,
         component: Pre,
         sig: CellPortSig,
+        is_comb: bool,
     ) -> RRC<ir::Cell>
     where
         Pre: Into<ir::Id> + ToString + Clone,
@@ -197,12 +228,14 @@ impl<'a> Builder<'a> {
             name,
             CellType::Component {
                 name: component.into(),
+                is_comb,
             },
             sig,
         );
         if self.generated {
             cell.borrow_mut().add_attribute("generated", 1);
         }
+        cell.borrow_mut().attributes.set_span(self.span.clone());
         self.component.cells.add(Rc::clone(&cell));
         cell
     }
@@ -248,11 +281,13 @@ impl<'a> Builder<'a> {
             dst.borrow().name
         );
 
+        let mut attributes = ir::Attributes::default();
+        attributes.set_span(self.span.clone());
         ir::Assignment {
             dst,
             src,
             guard: Box::new(guard),
-            attributes: ir::Attributes::default(),
+            attributes,
         }
     }
 
@@ -294,6 +329,7 @@ impl<'a> Builder<'a> {
                     direction,
                     parent: ir::PortParent::Cell(WRC::from(&cell)),
                     attributes,
+                    select: None,
                 }));
                 cell.borrow_mut().ports.push(port);
             });
@@ -34,6 +34,10 @@ pub struct Component {
     pub control: RRC<Control>,
     /// Attributes for this component
     pub attributes: Attributes,
+    /// True iff this is a combinational component.
+    /// Combinational components have no control program, no groups, and no
+    /// interface ports.
+    pub is_comb: bool,
 
     ///// Internal structures
     /// Namegenerator that contains the names currently defined in this
@@ -82,6 +86,7 @@ impl Component {
             control: Rc::new(RefCell::new(Control::empty())),
             namegen: utils::NameGenerator::with_prev_defined_names(prev_names),
             attributes: Attributes::default(),
+            is_comb: false,
         }
     }
 
@@ -89,6 +94,15 @@ impl Component {
         self.namegen.add_names(names)
     }
 
+    /// Drop the component and return its name generator.
+    pub(super) fn into_namegen(self) -> utils::NameGenerator {
+        self.namegen
+    }
+
+    pub(super) fn set_namegen(&mut self, namegen: utils::NameGenerator) {
+        self.namegen = namegen;
+    }
+
     /// Return a reference to the group with `name` if present.
     pub fn find_group<S>(&self, name: &S) -> Option<RRC<Group>>
     where
@@ -12,7 +12,8 @@ use std::path::PathBuf;
 #[derive(Debug, Default)]
 pub struct LibrarySignatures {
     /// Direct mapping from name to primitives
-    primitive_definitions: Vec<(PathBuf, LinkedHashMap<Id, Primitive>)>,
+    pub(super) primitive_definitions:
+        Vec<(PathBuf, LinkedHashMap<Id, Primitive>)>,
 }
 
 impl LibrarySignatures {
@@ -76,17 +77,6 @@ impl From<Vec<(PathBuf, Vec<Primitive>)>> for LibrarySignatures {
     }
 }
 
-/// Configuration information for the backends.
-#[derive(Default)]
-pub struct BackendConf {
-    /// Enables synthesis mode.
-    pub synthesis_mode: bool,
-    /// Enables verification checks.
-    pub enable_verification: bool,
-    /// Generate initial assignments for input ports
-    pub initialize_inputs: bool,
-}
-
 /// The IR Context
 pub struct Context {
     /// The components for this program.
@@ -95,9 +85,7 @@ pub struct Context {
     pub lib: LibrarySignatures,
     // Entrypoint for the program
     pub entrypoint: Id,
-    /// Configuration flags for backends.
-    pub bc: BackendConf,
     /// Extra options provided to the command line.
-    /// Interperted by individual passes
+    /// Interperted by individual passes and backends
     pub extra_opts: Vec<String>,
 }
@@ -54,6 +54,54 @@ pub struct While {
     pub attributes: Attributes,
 }
 
+/// Data for the `repeat` control statement.
+#[derive(Debug)]
+pub struct Repeat {
+    /// Number of times the body is executed.
+    pub num_repeats: u64,
+
+    /// Control for the loop body.
+    pub body: Box<Control>,
+    /// Attributes attached to this control statement.
+    pub attributes: Attributes,
+}
+
+/// Data for the `static seq` control statement.
+#[derive(Debug)]
+pub struct StaticSeq {
+    /// List of statically timed `Control` statements to run in sequence.
+    pub stmts: Vec<Control>,
+    /// Attributes attached to this control statement.
+    pub attributes: Attributes,
+}
+
+/// Data for the `static par` control statement.
+#[derive(Debug)]
+pub struct StaticPar {
+    /// List of statically timed `Control` statements to run in parallel.
+    pub stmts: Vec<Control>,
+    /// Attributes attached to this control statement.
+    pub attributes: Attributes,
+}
+
+/// Data for the `static if` control statement.
+/// The value of `port` is saved in the first cycle and both branches are
+/// padded to the latency of the longer one.
+#[derive(Debug)]
+pub struct StaticIf {
+    /// Port that connects the conditional check.
+    pub port: RRC<Port>,
+
+    /// Statically timed control for the true branch.
+    pub tbranch: Box<Control>,
+
+    /// Statically timed control for the false branch.
+    pub fbranch: Box<Control>,
+
+    /// Attributes attached to this control statement.
+    pub attributes: Attributes,
+}
+
 /// Data for the `enable` control statement.
 #[derive(Debug)]
 pub struct Enable {
@@ -64,12 +112,15 @@ pub struct Enable {
 }
 
 type PortMap = Vec<(Id, RRC<Port>)>;
+type CellMap = Vec<(Id, RRC<Cell>)>;
 
 /// Data for an `invoke` control statement.
 #[derive(Debug)]
 pub struct Invoke {
     /// Cell that is being invoked.
     pub comp: RRC<Cell>,
+    /// Mapping from name of `ref` cells in `comp` to the cells bound to them.
+    pub ref_cells: CellMap,
     /// Mapping from name of input ports in `comp` to the port connected to it.
     pub inputs: PortMap,
     /// Mapping from name of output ports in `comp` to the port connected to it.
@@ -95,12 +146,20 @@ pub enum Control {
     If(If),
     /// Standard imperative while statement
     While(While),
+    /// Runs the body a fixed number of times
+    Repeat(Repeat),
     /// Invoke a sub-component with the given port assignments
     Invoke(Invoke),
     /// Runs the control for a list of subcomponents.
     Enable(Enable),
     /// Control statement that does nothing.
     Empty(Empty),
+    /// Sequential composition of statically timed control statements.
+    StaticSeq(StaticSeq),
+    /// Parallel composition of statically timed control statements.
+    StaticPar(StaticPar),
+    /// If statement with statically timed branches.
+    StaticIf(StaticIf),
 }
 
 impl GetAttributes for Control {
@@ -110,8 +169,12 @@ impl GetAttributes for Control {
             | Self::Par(Par { attributes, .. })
             | Self::If(If { attributes, .. })
             | Self::While(While { attributes, .. })
+            | Self::Repeat(Repeat { attributes, .. })
             | Self::Invoke(Invoke { attributes, .. })
-            | Self::Enable(Enable { attributes, .. }) => Some(attributes),
+            | Self::Enable(Enable { attributes, .. })
+            | Self::StaticSeq(StaticSeq { attributes, .. })
+            | Self::StaticPar(StaticPar { attributes, .. })
+            | Self::StaticIf(StaticIf { attributes, .. }) => Some(attributes),
             Self::Empty(..) => None,
         }
     }
@@ -122,8 +185,12 @@ impl GetAttributes for Control {
             | Self::Par(Par { attributes, .. })
             | Self::If(If { attributes, .. })
             | Self::While(While { attributes, .. })
+            | Self::Repeat(Repeat { attributes, .. })
             | Self::Invoke(Invoke { attributes, .. })
-            | Self::Enable(Enable { attributes, .. }) => Some(attributes),
+            | Self::Enable(Enable { attributes, .. })
+            | Self::StaticSeq(StaticSeq { attributes, .. })
+            | Self::StaticPar(StaticPar { attributes, .. })
+            | Self::StaticIf(StaticIf { attributes, .. }) => Some(attributes),
             Self::Empty(..) => None,
         }
     }
@@ -164,6 +231,7 @@ impl Control {
     pub fn invoke(comp: RRC<Cell>, inputs: PortMap, outputs: PortMap) -> Self {
         Control::Invoke(Invoke {
             comp,
+            ref_cells: vec![],
             inputs,
             outputs,
             attributes: Attributes::default(),
@@ -200,6 +268,15 @@ impl Control {
             attributes: Attributes::default(),
         })
     }
+
+    /// Convenience constructor for repeat
+    pub fn repeat(num_repeats: u64, body: Box<Control>) -> Self {
+        Control::Repeat(Repeat {
+            num_repeats,
+            body,
+            attributes: Attributes::default(),
+        })
+    }
 }
 
 impl Control {
@@ -241,14 +318,28 @@ impl Control {
                 body: Box::new(Control::clone(body)),
                 attributes: attributes.clone(),
             }),
+            Control::Repeat(Repeat {
+                num_repeats,
+                body,
+                attributes,
+            }) => Control::Repeat(Repeat {
+                num_repeats: *num_repeats,
+                body: Box::new(Control::clone(body)),
+                attributes: attributes.clone(),
+            }),
             Control::Invoke(Invoke {
                 comp,
+                ref_cells,
                 inputs,
                 outputs,
                 attributes,
                 comb_group,
             }) => Control::Invoke(Invoke {
                 comp: Rc::clone(comp),
+                ref_cells: ref_cells
+                    .iter()
+                    .map(|(name, cell)| (name.clone(), Rc::clone(cell)))
+                    .collect(),
                 inputs: inputs
                     .iter()
                     .map(|(name, port)| (name.clone(), Rc::clone(port)))
@@ -267,6 +358,87 @@ impl Control {
                 })
             }
             Control::Empty(_) => Control::empty(),
+            Control::StaticSeq(StaticSeq { stmts, attributes }) => {
+                Control::StaticSeq(StaticSeq {
+                    stmts: stmts.iter().map(Control::clone).collect(),
+                    attributes: attributes.clone(),
+                })
+            }
+            Control::StaticPar(StaticPar { stmts, attributes }) => {
+                Control::StaticPar(StaticPar {
+                    stmts: stmts.iter().map(Control::clone).collect(),
+                    attributes: attributes.clone(),
+                })
+            }
+            Control::StaticIf(StaticIf {
+                port,
+                tbranch,
+                fbranch,
+                attributes,
+            }) => Control::StaticIf(StaticIf {
+                port: Rc::clone(port),
+                tbranch: Box::new(Control::clone(tbranch)),
+                fbranch: Box::new(Control::clone(fbranch)),
+                attributes: attributes.clone(),
+            }),
+        }
+    }
+
+    /// Returns the number of cycles taken by a statically timed control
+    /// program and `None` if the program is not statically timed.
+    ///
+    /// Statically timed programs are built from enables of groups with a
+    /// `"static"` attribute, `static` control statements, `repeat` with a
+    /// statically timed body, and `empty`.
+    pub fn static_latency(&self) -> Option<u64> {
+        match self {
+            Control::Enable(Enable { group, .. }) => {
+                group.borrow().attributes.get("static")
+            }
+            Control::StaticSeq(s) => s.latency(),
+            Control::StaticPar(p) => p.latency(),
+            Control::StaticIf(i) => i.latency(),
+            Control::Repeat(r) => r.latency(),
+            Control::Empty(_) => Some(0),
+            Control::Seq(_)
+            | Control::Par(_)
+            | Control::If(_)
+            | Control::While(_)
+            | Control::Invoke(_) => None,
         }
     }
 }
+
+impl Repeat {
+    /// Latency of the body times the number of repeats.
+    pub fn latency(&self) -> Option<u64> {
+        Some(self.num_repeats * self.body.static_latency()?)
+    }
+}
+
+impl StaticSeq {
+    /// Sum of the latencies of the statements.
+    pub fn latency(&self) -> Option<u64> {
+        self.stmts
+            .iter()
+            .try_fold(0, |acc, stmt| Some(acc + stmt.static_latency()?))
+    }
+}
+
+impl StaticPar {
+    /// Maximum of the latencies of the statements.
+    pub fn latency(&self) -> Option<u64> {
+        self.stmts.iter().try_fold(0, |acc, stmt| {
+            Some(std::cmp::max(acc, stmt.static_latency()?))
+        })
+    }
+}
+
+impl StaticIf {
+    /// One cycle to save the condition followed by the longer branch.
+    pub fn latency(&self) -> Option<u64> {
+        let tlat = self.tbranch.static_latency()?;
+        let flat = self.fbranch.static_latency()?;
+        Some(1 + std::cmp::max(tlat, flat))
+    }
+}
@@ -0,0 +1,666 @@
+//! High-level API to construct Calyx programs from Rust.
+//!
+//! Unlike the [Builder](crate::ir::Builder), which is used by passes to add
+//! structure to an existing component, the API here builds whole programs
+//! from scratch and reports mistakes such as unknown primitives, wrong
+//! parameters, mismatched widths, and undefined ports as errors instead of
+//! panicking.
+//!
+//! ```ignore
+//! use calyx::ir::dsl::{self, en, input, output, ProgramBuilder};
+//! use calyx::{par, seq};
+//!
+//! let mut prog = ProgramBuilder::new("./calyx", &["primitives/core.futil"])?;
+//! let mut main = prog.component("main", vec![input("in", 32), output("out", 32)])?;
+//! let r = main.cell("r", "std_reg", &[32])?;
+//! let upd = main.group("upd")?;
+//! main.assign(&upd, main.port(&r, "in")?, main.this("in")?)?;
+//! let one = main.constant(1, 1)?;
+//! main.assign(&upd, main.port(&r, "write_en")?, one)?;
+//! main.assign(&upd, main.hole(&upd, "done")?, main.port(&r, "done")?)?;
+//! main.assign(dsl::Wires::Continuous, main.this("out")?, main.port(&r, "out")?)?;
+//! main.set_control(seq![en(&upd), par![en(&upd)]]);
+//! prog.add_component(main.build()?)?;
+//! let ctx = prog.finish()?;
+//! ```
+use super::{
+    from_ast, verifier, Assignment, Attributes, Builder, Cell, CellType,
+    CombGroup, Component, Context, Control, Direction, Group, Guard, Id,
+    LibrarySignatures, Port, PortDef, Width, RESERVED_NAMES, RRC,
+};
+use crate::errors::{CalyxResult, Error};
+use crate::frontend;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Definition of an input port of a component signature.
+pub fn input<S: AsRef<str>>(name: S, width: u64) -> PortDef {
+    PortDef::from((Id::from(name.as_ref()), width, Direction::Input))
+}
+
+/// Definition of an output port of a component signature.
+pub fn output<S: AsRef<str>>(name: S, width: u64) -> PortDef {
+    PortDef::from((Id::from(name.as_ref()), width, Direction::Output))
+}
+
+/// Enable `group`.
+pub fn en(group: &RRC<Group>) -> Control {
+    Control::enable(Rc::clone(group))
+}
+
+/// Run `tbranch` if `port` is high and `fbranch` otherwise. The value of
+/// `port` is computed by the combinational group `cond` if provided.
+pub fn if_(
+    port: RRC<Port>,
+    cond: Option<&RRC<CombGroup>>,
+    tbranch: Control,
+    fbranch: Control,
+) -> Control {
+    Control::if_(
+        port,
+        cond.map(Rc::clone),
+        Box::new(tbranch),
+        Box::new(fbranch),
+    )
+}
+
+/// Run `body` while `port` is high. The value of `port` is computed by the
+/// combinational group `cond` if provided.
+pub fn while_(
+    port: RRC<Port>,
+    cond: Option<&RRC<CombGroup>>,
+    body: Control,
+) -> Control {
+    Control::while_(port, cond.map(Rc::clone), Box::new(body))
+}
+
+/// Run `body` `num_repeats` times.
+pub fn repeat(num_repeats: u64, body: Control) -> Control {
+    Control::repeat(num_repeats, Box::new(body))
+}
+
+/// Build a `seq` control statement.
+/// ```ignore
+/// seq![en(&g1), par![en(&g2), en(&g3)]]
+/// ```
+#[macro_export]
+macro_rules! seq {
+    ($($c:expr),* $(,)?) => {
+        $crate::ir::Control::seq(vec![$($c),*])
+    };
+}
+
+/// Build a `par` control statement.
+/// ```ignore
+/// par![en(&g1), seq![en(&g2), en(&g3)]]
+/// ```
+#[macro_export]
+macro_rules! par {
+    ($($c:expr),* $(,)?) => {
+        $crate::ir::Control::par(vec![$($c),*])
+    };
+}
+
+/// Where an assignment is added.
+pub enum Wires<'a> {
+    /// Assignments of a group.
+    Group(&'a RRC<Group>),
+    /// Assignments of a combinational group.
+    Comb(&'a RRC<CombGroup>),
+    /// Continuous assignments of the component.
+    Continuous,
+}
+
+impl<'a> From<&'a RRC<Group>> for Wires<'a> {
+    fn from(group: &'a RRC<Group>) -> Self {
+        Wires::Group(group)
+    }
+}
+
+impl<'a> From<&'a RRC<CombGroup>> for Wires<'a> {
+    fn from(group: &'a RRC<CombGroup>) -> Self {
+        Wires::Comb(group)
+    }
+}
+
+/// Builds a Calyx program.
+/// Components are built using a [ComponentBuilder] and added to the program
+/// using [ProgramBuilder::add_component].
+pub struct ProgramBuilder {
+    lib: LibrarySignatures,
+    components: Vec<Component>,
+}
+
+impl ProgramBuilder {
+    /// Construct a program that can use the primitives and components defined
+    /// by `imports`. Imports are resolved relative to `lib_path`, for example,
+    /// `primitives/core.futil`.
+    pub fn new<P: AsRef<Path>>(
+        lib_path: P,
+        imports: &[&str],
+    ) -> CalyxResult<Self> {
+        let lib_path = lib_path.as_ref();
+        let source = imports
+            .iter()
+            .map(|imp| format!("import \"{}\";\n", imp))
+            .collect::<String>();
+        let ws = frontend::Workspace::construct_from_source(
+            &source,
+            &lib_path.join("program.futil"),
+            lib_path,
+        )?;
+        let (components, lib) = from_ast::ast_to_components(ws)?;
+        Ok(Self { lib, components })
+    }
+
+    /// The primitives available to the program.
+    pub fn lib(&self) -> &LibrarySignatures {
+        &self.lib
+    }
+
+    /// Start building a component named `name` with the given signature.
+    /// The `go`, `done`, `clk`, and `reset` interface ports are added unless
+    /// the signature already defines ports with the corresponding attribute.
+    pub fn component<S: AsRef<str>>(
+        &self,
+        name: S,
+        signature: Vec<PortDef>,
+    ) -> CalyxResult<ComponentBuilder<'_>> {
+        self.new_component(name.as_ref(), signature, false)
+    }
+
+    /// Start building a combinational component named `name` with the given
+    /// signature. Combinational components do not have interface ports or a
+    /// control program.
+    pub fn comb_component<S: AsRef<str>>(
+        &self,
+        name: S,
+        signature: Vec<PortDef>,
+    ) -> CalyxResult<ComponentBuilder<'_>> {
+        self.new_component(name.as_ref(), signature, true)
+    }
+
+    fn new_component(
+        &self,
+        name: &str,
+        mut signature: Vec<PortDef>,
+        is_comb: bool,
+    ) -> CalyxResult<ComponentBuilder<'_>> {
+        let name = Id::from(name);
+        self.check_undefined(&name)?;
+
+        let mut names = HashSet::new();
+        for pd in &signature {
+            if !names.insert(pd.name.clone()) {
+                return Err(Error::already_bound(
+                    pd.name.clone(),
+                    format!("signature of component `{}'", name),
+                ));
+            }
+        }
+        if !is_comb {
+            from_ast::extend_signature(&mut signature);
+        }
+        let ports = signature
+            .into_iter()
+            .map(|pd| match (pd.width, pd.direction) {
+                (Width::Const { value }, dir @ Direction::Input)
+                | (Width::Const { value }, dir @ Direction::Output) => {
+                    Ok((pd.name, value, dir, pd.attributes))
+                }
+                (Width::Param { .. }, _) => {
+                    Err(Error::malformed_structure(format!(
+                        "Port `{}' of component `{}' must have a constant width",
+                        pd.name, name
+                    )))
+                }
+                (_, Direction::Inout) => {
+                    Err(Error::malformed_structure(format!(
+                        "Port `{}' of component `{}' must be an input or an output",
+                        pd.name, name
+                    )))
+                }
+            })
+            .collect::<CalyxResult<Vec<_>>>()?;
+
+        let mut comp = Component::new(&name, ports);
+        comp.is_comb = is_comb;
+        Ok(ComponentBuilder { prog: self, comp })
+    }
+
+    /// Add a component built using [ComponentBuilder::build] to the program.
+    pub fn add_component(&mut self, comp: Component) -> CalyxResult<()> {
+        self.check_undefined(&comp.name)?;
+        self.components.push(comp);
+        Ok(())
+    }
+
+    /// Finish building the program. The entrypoint is the component marked
+    /// with the "toplevel" attribute or the component named `main`.
+    pub fn finish(self) -> CalyxResult<Context> {
+        let entrypoint = from_ast::find_entrypoint(&self.components)?;
+        Ok(Context {
+            components: self.components,
+            lib: self.lib,
+            entrypoint,
+            extra_opts: vec![],
+        })
+    }
+
+    fn check_undefined(&self, name: &Id) -> CalyxResult<()> {
+        if self.lib.find_primitive(name).is_some()
+            || self.find_component(name).is_some()
+        {
+            return Err(Error::already_bound(
+                name.clone(),
+                "component or primitive".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn find_component(&self, name: &Id) -> Option<&Component> {
+        self.components.iter().find(|c| c.name == name)
+    }
+}
+
+/// Builds a component of a [ProgramBuilder].
+/// Cells, groups, and ports are returned as `RRC` handles that can be used
+/// to write assignments and control programs.
+pub struct ComponentBuilder<'a> {
+    prog: &'a ProgramBuilder,
+    comp: Component,
+}
+
+impl ComponentBuilder<'_> {
+    /// Reserve `name` in the component. Returns an error if it is already
+    /// defined.
+    fn define(&mut self, name: &Id) -> CalyxResult<()> {
+        if self.comp.generate_name(name.clone()) != *name {
+            return Err(Error::already_bound(
+                name.clone(),
+                format!("component `{}'", self.comp.name),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Add a cell named `name` that instantiates the primitive or component
+    /// `prototype`. Primitives are checked against the number of parameters
+    /// they require and components do not take any parameters.
+    pub fn cell<S, P>(
+        &mut self,
+        name: S,
+        prototype: P,
+        params: &[u64],
+    ) -> CalyxResult<RRC<Cell>>
+    where
+        S: AsRef<str>,
+        P: AsRef<str>,
+    {
+        let name = Id::from(name.as_ref());
+        let proto = Id::from(prototype.as_ref());
+        let (typ, ports) = if let Some(prim) =
+            self.prog.lib.find_primitive(&proto)
+        {
+            let (param_binding, ports) = prim.resolve(params)?;
+            let typ = CellType::Primitive {
+                name: proto,
+                param_binding: Box::new(param_binding),
+                is_comb: prim.is_comb,
+            };
+            (typ, ports)
+        } else if let Some(comp) = self.prog.find_component(&proto) {
+            if !params.is_empty() {
+                return Err(Error::malformed_structure(format!(
+                    "Component `{}' does not take any parameters but cell `{}' provides {}",
+                    proto,
+                    name,
+                    params.len()
+                )));
+            }
+            // The signature cell stores the ports in reversed direction.
+            let ports = comp
+                .signature
+                .borrow()
+                .get_signature()
+                .into_iter()
+                .map(|(n, w, d, attrs)| (n, w, d.reverse(), attrs))
+                .collect();
+            let typ = CellType::Component {
+                name: proto,
+                is_comb: comp.is_comb,
+            };
+            (typ, ports)
+        } else {
+            return Err(Error::undefined(
+                proto,
+                "primitive or component".to_string(),
+            ));
+        };
+
+        self.define(&name)?;
+        let cell = Builder::cell_from_signature(name, typ, ports);
+        self.comp.cells.add(Rc::clone(&cell));
+        Ok(cell)
+    }
+
+    /// Add a group named `name` with `go` and `done` holes.
+    pub fn group<S: AsRef<str>>(&mut self, name: S) -> CalyxResult<RRC<Group>> {
+        let name = Id::from(name.as_ref());
+        self.define(&name)?;
+        let group = Rc::new(RefCell::new(Group::new(name)));
+        for hole in ["go", "done"] {
+            let port = Rc::new(RefCell::new(Port {
+                name: hole.into(),
+                width: 1,
+                direction: Direction::Inout,
+                parent: super::PortParent::Group(super::WRC::from(&group)),
+                attributes: Attributes::default(),
+                select: None,
+            }));
+            group.borrow_mut().holes.push(port);
+        }
+        self.comp.groups.add(Rc::clone(&group));
+        Ok(group)
+    }
+
+    /// Add a combinational group named `name`.
+    pub fn comb_group<S: AsRef<str>>(
+        &mut self,
+        name: S,
+    ) -> CalyxResult<RRC<CombGroup>> {
+        let name = Id::from(name.as_ref());
+        self.define(&name)?;
+        let group = Rc::new(RefCell::new(CombGroup {
+            name,
+            assignments: vec![],
+            attributes: Attributes::default(),
+        }));
+        self.comp.comb_groups.add(Rc::clone(&group));
+        Ok(group)
+    }
+
+    /// The port `port` in the signature of the component.
+    pub fn this<S: AsRef<str>>(&self, port: S) -> CalyxResult<RRC<Port>> {
+        self.comp
+            .signature
+            .borrow()
+            .find(port.as_ref())
+            .ok_or_else(|| {
+                Error::undefined(
+                    port.as_ref().into(),
+                    format!("port of component `{}'", self.comp.name),
+                )
+            })
+    }
+
+    /// The port `port` of `cell`.
+    pub fn port<S: AsRef<str>>(
+        &self,
+        cell: &RRC<Cell>,
+        port: S,
+    ) -> CalyxResult<RRC<Port>> {
+        let cell = cell.borrow();
+        cell.find(port.as_ref()).ok_or_else(|| {
+            Error::undefined(
+                port.as_ref().into(),
+                format!("port of cell `{}'", cell.name()),
+            )
+        })
+    }
+
+    /// The bits `msb` down to `lsb` of `port`.
+    pub fn select(
+        &self,
+        port: &RRC<Port>,
+        msb: u64,
+        lsb: u64,
+    ) -> CalyxResult<RRC<Port>> {
+        let width = port.borrow().width;
+        if lsb > msb || msb >= width {
+            return Err(Error::malformed_structure(format!(
+                "Invalid bit-select [{}:{}] of `{}.{}' with width {}",
+                msb,
+                lsb,
+                port.borrow().get_parent_name(),
+                port.borrow().name,
+                width
+            )));
+        }
+        Ok(Port::select(port, msb, lsb))
+    }
+
+    /// The hole `hole` (`go` or `done`) of `group`.
+    pub fn hole<S: AsRef<str>>(
+        &self,
+        group: &RRC<Group>,
+        hole: S,
+    ) -> CalyxResult<RRC<Port>> {
+        let group = group.borrow();
+        group.find(&hole.as_ref()).ok_or_else(|| {
+            Error::undefined(
+                hole.as_ref().into(),
+                format!("hole of group `{}'", group.name()),
+            )
+        })
+    }
+
+    /// The `width`-bit constant `val`.
+    pub fn constant(&mut self, val: u64, width: u64) -> CalyxResult<RRC<Port>> {
+        if width == 0 || (width < 64 && val >> width != 0) {
+            return Err(Error::malformed_structure(format!(
+                "Constant {} does not fit in {} bits",
+                val, width
+            )));
+        }
+        let cell = Builder::new(&mut self.comp, &self.prog.lib)
+            .not_generated()
+            .add_constant(val, width);
+        let port = cell.borrow().get("out");
+        Ok(port)
+    }
+
+    /// Add the assignment `dst = src` to `wires`.
+    pub fn assign<'w, W: Into<Wires<'w>>>(
+        &mut self,
+        wires: W,
+        dst: RRC<Port>,
+        src: RRC<Port>,
+    ) -> CalyxResult<()> {
+        self.assign_guarded(wires, dst, src, Guard::True)
+    }
+
+    /// Add the assignment `dst = guard ? src` to `wires`.
+    pub fn assign_guarded<'w, W: Into<Wires<'w>>>(
+        &mut self,
+        wires: W,
+        dst: RRC<Port>,
+        src: RRC<Port>,
+        guard: Guard,
+    ) -> CalyxResult<()> {
+        let describe = |port: &RRC<Port>| {
+            let port = port.borrow();
+            format!("{}.{}", port.get_parent_name(), port.name)
+        };
+        let dst_width = dst.borrow().width;
+        let src_width = src.borrow().width;
+        if dst_width != src_width {
+            return Err(Error::malformed_structure(format!(
+                "Invalid assignment in component `{}': `{}' has width {} but `{}' has width {}",
+                self.comp.name,
+                describe(&dst),
+                dst_width,
+                describe(&src),
+                src_width
+            )));
+        }
+        if dst.borrow().direction == Direction::Output {
+            return Err(Error::malformed_structure(format!(
+                "Invalid assignment in component `{}': `{}' cannot be written",
+                self.comp.name,
+                describe(&dst)
+            )));
+        }
+        if src.borrow().direction == Direction::Input {
+            return Err(Error::malformed_structure(format!(
+                "Invalid assignment in component `{}': `{}' cannot be read",
+                self.comp.name,
+                describe(&src)
+            )));
+        }
+
+        let assign = Assignment {
+            dst,
+            src,
+            guard: Box::new(guard),
+            attributes: Attributes::default(),
+        };
+        match wires.into() {
+            Wires::Group(group) => group.borrow_mut().assignments.push(assign),
+            Wires::Comb(group) => group.borrow_mut().assignments.push(assign),
+            Wires::Continuous => self.comp.continuous_assignments.push(assign),
+        }
+        Ok(())
+    }
+
+    /// Invoke `cell` with the given input and output bindings.
+    pub fn invoke<S: AsRef<str>>(
+        &self,
+        cell: &RRC<Cell>,
+        inputs: Vec<(S, RRC<Port>)>,
+        outputs: Vec<(S, RRC<Port>)>,
+    ) -> CalyxResult<Control> {
+        let bind = |bindings: Vec<(S, RRC<Port>)>| {
+            bindings
+                .into_iter()
+                .map(|(name, port)| {
+                    let formal = self.port(cell, name.as_ref())?;
+                    if formal.borrow().width != port.borrow().width {
+                        return Err(Error::malformed_structure(format!(
+                            "Invoke of `{}' binds port `{}' with width {} to a port with width {}",
+                            cell.borrow().name(),
+                            name.as_ref(),
+                            formal.borrow().width,
+                            port.borrow().width
+                        )));
+                    }
+                    Ok((Id::from(name.as_ref()), port))
+                })
+                .collect::<CalyxResult<Vec<_>>>()
+        };
+        Ok(Control::invoke(
+            Rc::clone(cell),
+            bind(inputs)?,
+            bind(outputs)?,
+        ))
+    }
+
+    /// Set the control program of the component.
+    pub fn set_control(&mut self, control: Control) {
+        self.comp.control = Rc::new(RefCell::new(control));
+    }
+
+    /// Finish building the component. Checks that every group writes to its
+    /// `done` hole, that combinational components do not have a control
+    /// program, and that the structure and control only use cells and groups
+    /// defined in this component.
+    pub fn build(self) -> CalyxResult<Component> {
+        let mut comp = self.comp;
+        for group in comp.groups.iter() {
+            let group = group.borrow();
+            let has_done = group.assignments.iter().any(|a| {
+                let dst = a.dst.borrow();
+                dst.is_hole() && dst.name == "done"
+            });
+            if !has_done {
+                return Err(Error::malformed_structure(format!(
+                    "Group `{}' in component `{}' does not write to its done hole",
+                    group.name(),
+                    comp.name
+                )));
+            }
+        }
+        if comp.is_comb && !matches!(&*comp.control.borrow(), Control::Empty(_))
+        {
+            return Err(Error::malformed_structure(format!(
+                "Combinational component `{}' cannot have a control program",
+                comp.name
+            )));
+        }
+        verifier::verify_component(&comp)?;
+        comp.add_names(RESERVED_NAMES.iter().map(|s| s.to_string()).collect());
+        Ok(comp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{en, input, output, ProgramBuilder, Wires};
+    use crate::ir::Printer;
+
+    fn program() -> ProgramBuilder {
+        ProgramBuilder::new("..", &["primitives/core.futil"])
+            .expect("Failed to load the core library")
+    }
+
+    #[test]
+    fn build_program() {
+        let mut prog = program();
+        let mut main = prog
+            .component("main", vec![input("in", 32), output("out", 32)])
+            .unwrap();
+        let r = main.cell("r", "std_reg", &[32]).unwrap();
+        let upd = main.group("upd").unwrap();
+        let (r_in, r_we, r_done, r_out) = (
+            main.port(&r, "in").unwrap(),
+            main.port(&r, "write_en").unwrap(),
+            main.port(&r, "done").unwrap(),
+            main.port(&r, "out").unwrap(),
+        );
+        let this_in = main.this("in").unwrap();
+        let one = main.constant(1, 1).unwrap();
+        let done = main.hole(&upd, "done").unwrap();
+        main.assign(&upd, r_in, this_in).unwrap();
+        main.assign(&upd, r_we, one).unwrap();
+        main.assign(&upd, done, r_done).unwrap();
+        let this_out = main.this("out").unwrap();
+        main.assign(Wires::Continuous, this_out, r_out).unwrap();
+        main.set_control(seq![en(&upd), par![en(&upd)]]);
+        prog.add_component(main.build().unwrap()).unwrap();
+        let ctx = prog.finish().unwrap();
+
+        let mut out = vec![];
+        Printer::write_component(&ctx.components[0], &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("r = std_reg(32);"));
+        assert!(out.contains("upd[done] = r.done;"));
+        assert!(out.contains("out = r.out;"));
+        assert_eq!(ctx.entrypoint, "main");
+    }
+
+    #[test]
+    fn wrong_parameters() {
+        let prog = program();
+        let mut main = prog.component("main", vec![]).unwrap();
+        assert!(main.cell("r", "std_reg", &[32, 1]).is_err());
+        assert!(main.cell("r", "std_foo", &[32]).is_err());
+        main.cell("r", "std_reg", &[32]).unwrap();
+        assert!(main.cell("r", "std_reg", &[32]).is_err());
+    }
+
+    #[test]
+    fn mismatched_widths() {
+        let prog = program();
+        let mut main = prog.component("main", vec![]).unwrap();
+        let r = main.cell("r", "std_reg", &[32]).unwrap();
+        let upd = main.group("upd").unwrap();
+        let r_in = main.port(&r, "in").unwrap();
+        let c = main.constant(1, 8).unwrap();
+        assert!(main.assign(&upd, r_in, c).is_err());
+        assert!(main.constant(256, 8).is_err());
+        assert!(main.build().is_err());
+    }
+}
@@ -1,10 +1,10 @@
 use super::{
-    Assignment, Attributes, BackendConf, Builder, Canonical, CellType,
-    Component, Context, Control, Direction, GetAttributes, Guard, Id, Invoke,
-    LibrarySignatures, Port, PortDef, Width, RESERVED_NAMES, RRC,
+    Assignment, Attributes, Builder, Canonical, CellType, Component, Context,
+    Control, Direction, GetAttributes, Guard, Id, Invoke, LibrarySignatures,
+    Port, PortDef, StaticIf, StaticPar, StaticSeq, Width, RESERVED_NAMES, RRC,
 };
 use crate::{
-    errors::{CalyxResult, Error, WithPos},
+    errors::{CalyxResult, Diagnostics, Error, WithPos},
     frontend::{self, ast},
     ir::PortComp,
     utils::NameGenerator,
@@ -23,6 +23,9 @@ struct SigCtx {
 
     /// Mapping from library functions to signatures
     lib: LibrarySignatures,
+
+    /// Names of the combinational components
+    comb_comps: HashSet<Id>,
 }
 
 /// Validates a component signature to make sure there are not duplicate ports.
@@ -69,7 +72,7 @@ const INTERFACE_PORTS: [(&str, u64, Direction); 4] = [
 ];
 
 /// Extend the signature with magical ports.
-fn extend_signature(sig: &mut Vec<PortDef>) {
+pub fn extend_signature(sig: &mut Vec<PortDef>) {
     let port_names: HashSet<_> =
         sig.iter().map(|pd| pd.name.to_string()).collect();
     let mut namegen = NameGenerator::with_prev_defined_names(port_names);
@@ -89,8 +92,212 @@ fn extend_signature(sig: &mut Vec<PortDef>) {
     }
 }
 
+/// Resolve `width` using the parameter `binding` of the enclosing component.
+fn resolve_width(
+    width: &Width,
+    binding: &LinkedHashMap<Id, u64>,
+) -> CalyxResult<Width> {
+    match width {
+        Width::Const { .. } => Ok(width.clone()),
+        Width::Param { value } => binding
+            .get(value)
+            .map(|v| Width::Const { value: *v })
+            .ok_or_else(|| {
+                Error::undefined(value.clone(), "parameter".to_string())
+            }),
+    }
+}
+
+/// Value of a width that is required to be a constant.
+fn const_width(width: &Width) -> CalyxResult<u64> {
+    match width {
+        Width::Const { value } => Ok(*value),
+        Width::Param { value } => {
+            Err(Error::undefined(value.clone(), "parameter".to_string()))
+        }
+    }
+}
+
+/// Name of the component generated by binding the parameters of `comp` to
+/// `args`. For example, binding `W` to `32` in `adder[W]` generates
+/// `adder_W32`.
+fn mangle_name(comp: &Id, params: &[Id], args: &[u64]) -> Id {
+    let binding = params
+        .iter()
+        .zip(args)
+        .map(|(param, val)| format!("{}{}", param, val))
+        .collect::<Vec<_>>()
+        .join("_");
+    Id::from(format!("{}_{}", comp, binding))
+}
+
+/// Instantiate the parameterized component `template` using `binding`.
+fn instantiate(
+    template: &ast::ComponentDef,
+    name: Id,
+    binding: &LinkedHashMap<Id, u64>,
+) -> CalyxResult<ast::ComponentDef> {
+    let mut comp = template.clone();
+    comp.name = name;
+    comp.params = vec![];
+    for pd in &mut comp.signature {
+        pd.width = resolve_width(&pd.width, binding)?;
+    }
+    for cell in &mut comp.cells {
+        for arg in &mut cell.prototype.params {
+            *arg = resolve_width(arg, binding)?;
+        }
+    }
+    Ok(comp)
+}
+
+/// Monomorphize parameterized components.
+/// Each distinct binding used to instantiate a parameterized component
+/// generates a new component with a mangled name (see [mangle_name]) and the
+/// cells are rewritten to use the generated component. Parameterized
+/// definitions that are never instantiated are removed, which is why the
+/// entrypoint cannot be parameterized.
+fn monomorphize(workspace: &mut frontend::Workspace) -> CalyxResult<()> {
+    let (templates, mut components): (Vec<_>, Vec<_>) = workspace
+        .components
+        .drain(..)
+        .partition(|comp| !comp.params.is_empty());
+
+    // Mirrors the rules used by [find_entrypoint].
+    let has_toplevel = components
+        .iter()
+        .chain(&templates)
+        .any(|comp| comp.attributes.get("toplevel").is_some());
+    if let Some(entry) = templates.iter().find(|comp| {
+        if has_toplevel {
+            comp.attributes.get("toplevel").is_some()
+        } else {
+            comp.name == "main"
+        }
+    }) {
+        return Err(Error::malformed_structure(format!(
+            "Entrypoint component `{}` cannot be parameterized",
+            entry.name
+        ))
+        .with_pos(&entry.attributes));
+    }
+    let (decl_templates, mut declarations): (Vec<_>, Vec<_>) = workspace
+        .declarations
+        .drain(..)
+        .partition(|comp| !comp.params.is_empty());
+
+    // Names that cannot be used by generated components.
+    let mut defined: HashSet<Id> = workspace
+        .externs
+        .iter()
+        .flat_map(|(_, prims)| prims.iter().map(|prim| prim.name.clone()))
+        .chain(components.iter().map(|comp| comp.name.clone()))
+        .chain(declarations.iter().map(|comp| comp.name.clone()))
+        .collect();
+
+    // Mapping from names of parameterized components to their definition and
+    // whether they are declarations.
+    let templates: HashMap<Id, (ast::ComponentDef, bool)> = templates
+        .into_iter()
+        .map(|comp| (comp, false))
+        .chain(decl_templates.into_iter().map(|comp| (comp, true)))
+        .map(|(comp, is_decl)| (comp.name.clone(), (comp, is_decl)))
+        .collect();
+    defined.extend(templates.keys().cloned());
+
+    // Generated components are added to the end of the list and processed in
+    // turn since they might instantiate other parameterized components.
+    let mut instances: HashSet<Id> = HashSet::new();
+    let mut idx = 0;
+    while idx < components.len() {
+        let mut cells = std::mem::take(&mut components[idx].cells);
+        for cell in &mut cells {
+            let (template, is_decl) = match templates.get(&cell.prototype.name)
+            {
+                Some(t) => t,
+                None => continue,
+            };
+            let args = cell
+                .prototype
+                .params
+                .iter()
+                .map(const_width)
+                .collect::<CalyxResult<Vec<_>>>()?;
+            if args.len() != template.params.len() {
+                return Err(Error::malformed_structure(format!(
+                    "Invalid parameter binding for component `{}`. Requires {} parameters but provided with {}.",
+                    template.name,
+                    template.params.len(),
+                    args.len()
+                ))
+                .with_pos(&cell.attributes));
+            }
+
+            let name = mangle_name(&template.name, &template.params, &args);
+            if !instances.contains(&name) {
+                if defined.contains(&name) {
+                    return Err(Error::already_bound(
+                        name,
+                        "component or primitive".to_string(),
+                    )
+                    .with_pos(&cell.attributes)
+                    .with_post_msg(Some(format!(
+                        "Name is required for the instantiation of parameterized component `{}`",
+                        template.name
+                    ))));
+                }
+                let binding =
+                    template.params.iter().cloned().zip(args).collect();
+                let inst = instantiate(template, name.clone(), &binding)?;
+                instances.insert(name.clone());
+                if *is_decl {
+                    declarations.push(inst);
+                } else {
+                    components.push(inst);
+                }
+            }
+            cell.prototype = ast::Proto {
+                name,
+                params: vec![],
+            };
+        }
+        components[idx].cells = cells;
+        idx += 1;
+    }
+
+    workspace.components = components;
+    workspace.declarations = declarations;
+    Ok(())
+}
+
 /// Construct an IR representation using a parsed AST and command line options.
-pub fn ast_to_ir(mut workspace: frontend::Workspace) -> CalyxResult<Context> {
+pub fn ast_to_ir(workspace: frontend::Workspace) -> CalyxResult<Context> {
+    let (comps, lib) = ast_to_components(workspace)?;
+    let entrypoint = find_entrypoint(&comps)?;
+
+    Ok(Context {
+        components: comps,
+        lib,
+        entrypoint,
+        extra_opts: vec![],
+    })
+}
+
+/// Find the entrypoint of a program: the component marked with the
+/// "toplevel" attribute or the component named `main`.
+pub(super) fn find_entrypoint(comps: &[Component]) -> CalyxResult<Id> {
+    comps
+        .iter()
+        .find(|c| c.attributes.get("toplevel").is_some())
+        .or_else(|| comps.iter().find(|c| c.name == "main"))
+        .map(|c| c.name.clone())
+        .ok_or_else(|| Error::misc("No entry point for the program. Program needs to be either mark a component with the \"toplevel\" attribute or define a component named `main`".to_string()))
+}
+
+/// Build the components and the primitive library of `workspace`.
+pub(super) fn ast_to_components(
+    mut workspace: frontend::Workspace,
+) -> CalyxResult<(Vec<Component>, LibrarySignatures)> {
     let mut all_names: HashSet<&Id> = HashSet::with_capacity(
         workspace.components.len() + workspace.externs.len(),
     );
@@ -111,6 +318,8 @@ pub fn ast_to_ir(mut workspace: frontend::Workspace) -> CalyxResult<Context> {
         all_names.insert(bound);
     }
 
+    monomorphize(&mut workspace)?;
+
     // Build the signature context
     let mut sig_ctx = SigCtx {
         lib: workspace.externs.into(),
@@ -120,8 +329,13 @@ pub fn ast_to_ir(mut workspace: frontend::Workspace) -> CalyxResult<Context> {
     // Add declarations to context
     for comp in &mut workspace.declarations {
         check_signature(&comp.signature)?;
-        // extend the signature
-        extend_signature(&mut comp.signature);
+        // extend the signature. Combinational components do not have
+        // interface ports.
+        if comp.is_comb {
+            sig_ctx.comb_comps.insert(comp.name.clone());
+        } else {
+            extend_signature(&mut comp.signature);
+        }
         sig_ctx
             .comp_sigs
             .insert(comp.name.clone(), comp.signature.clone());
@@ -130,39 +344,34 @@ pub fn ast_to_ir(mut workspace: frontend::Workspace) -> CalyxResult<Context> {
     // Add components to context
     for comp in &mut workspace.components {
         check_signature(&comp.signature)?;
-        // extend the signature
-        extend_signature(&mut comp.signature);
+        // extend the signature. Combinational components do not have
+        // interface ports.
+        if comp.is_comb {
+            sig_ctx.comb_comps.insert(comp.name.clone());
+        } else {
+            extend_signature(&mut comp.signature);
+        }
         sig_ctx
             .comp_sigs
             .insert(comp.name.clone(), comp.signature.clone());
     }
+    // Report the errors from all the components together.
+    let mut diag = Diagnostics::default();
     let comps: Vec<Component> = workspace
         .components
         .into_iter()
-        .map(|comp| build_component(comp, &sig_ctx))
-        .collect::<Result<_, _>>()?;
-
-    // Find the entrypoint for the program.
-    let entrypoint = comps
-        .iter()
-        .find(|c| c.attributes.get("toplevel").is_some())
-        .or_else(|| comps.iter().find(|c| c.name == "main"))
-        .map(|c| c.name.clone())
-        .ok_or_else(|| Error::misc("No entry point for the program. Program needs to be either mark a component with the \"toplevel\" attribute or define a component named `main`".to_string()))?;
+        .filter_map(|comp| diag.check(build_component(comp, &sig_ctx)))
+        .collect();
+    diag.into_result()?;
 
-    Ok(Context {
-        components: comps,
-        lib: sig_ctx.lib,
-        bc: BackendConf::default(),
-        entrypoint,
-        extra_opts: vec![],
-    })
+    Ok((comps, sig_ctx.lib))
 }
 
 fn validate_component(
     comp: &ast::ComponentDef,
     sig_ctx: &SigCtx,
 ) -> CalyxResult<()> {
+    let mut diag = Diagnostics::default();
     let mut cells: HashSet<Id> = HashSet::new();
     let mut groups: HashSet<Id> = HashSet::new();
 
@@ -173,11 +382,10 @@ fn validate_component(
                 .unwrap()
                 .copy_span()
                 .map(|s| s.format("Previous definition"));
-            return Err(Error::already_bound(
-                cell.name.clone(),
-                "cell".to_string(),
-            )
-            .with_post_msg(prev));
+            diag.err(
+                Error::already_bound(cell.name.clone(), "cell".to_string())
+                    .with_post_msg(prev),
+            );
         }
         cells.insert(cell.name.clone());
 
@@ -186,7 +394,7 @@ fn validate_component(
         if sig_ctx.lib.find_primitive(&proto_name).is_none()
             && !sig_ctx.comp_sigs.contains_key(proto_name)
         {
-            return Err(Error::undefined(
+            diag.err(Error::undefined(
                 proto_name.clone(),
                 "primitive or component".to_string(),
             ));
@@ -201,11 +409,10 @@ fn validate_component(
                 .unwrap()
                 .copy_span()
                 .map(|s| s.format("Previous definition"));
-            return Err(Error::already_bound(
-                name.clone(),
-                "group".to_string(),
-            )
-            .with_post_msg(prev));
+            diag.err(
+                Error::already_bound(name.clone(), "group".to_string())
+                    .with_post_msg(prev),
+            );
         }
         if cells.contains(name) {
             let prev = cells
@@ -213,13 +420,15 @@ fn validate_component(
                 .unwrap()
                 .copy_span()
                 .map(|s| s.format("Previous definition"));
-            return Err(Error::already_bound(name.clone(), "cell".to_string())
-                .with_post_msg(prev));
+            diag.err(
+                Error::already_bound(name.clone(), "cell".to_string())
+                    .with_post_msg(prev),
+            );
         }
         groups.insert(name.clone());
     }
 
-    Ok(())
+    diag.into_result()
 }
 
 /// Build an `ir::component::Component` using an `frontend::ast::ComponentDef`.
@@ -242,6 +451,7 @@ fn build_component(
             })
             .collect::<Result<_, _>>()?,
     );
+    ir_component.is_comb = comp.is_comb;
     let mut builder =
         Builder::new(&mut ir_component, &sig_ctx.lib).not_generated();
 
@@ -249,7 +459,7 @@ fn build_component(
     // required information.
     comp.cells
         .into_iter()
-        .for_each(|cell| add_cell(cell, sig_ctx, &mut builder));
+        .try_for_each(|cell| add_cell(cell, sig_ctx, &mut builder))?;
 
     comp.groups
         .into_iter()
@@ -276,11 +486,23 @@ fn build_component(
 
 ///////////////// Cell Construction /////////////////////////
 
-fn add_cell(cell: ast::Cell, sig_ctx: &SigCtx, builder: &mut Builder) {
+fn add_cell(
+    cell: ast::Cell,
+    sig_ctx: &SigCtx,
+    builder: &mut Builder,
+) -> CalyxResult<()> {
     let proto_name = &cell.prototype.name;
 
     let res = if sig_ctx.lib.find_primitive(proto_name).is_some() {
-        builder.add_primitive(cell.name, proto_name, &cell.prototype.params)
+        // Parameterized components have been monomorphized so all the
+        // arguments must be constants.
+        let params = cell
+            .prototype
+            .params
+            .iter()
+            .map(const_width)
+            .collect::<CalyxResult<Vec<_>>>()?;
+        builder.add_primitive(cell.name, proto_name, &params)
     } else {
         // Validator ensures that if the protoype is not a primitive, it
         // is a component.
@@ -288,6 +510,7 @@ fn add_cell(cell: ast::Cell, sig_ctx: &SigCtx, builder: &mut Builder) {
         let sig = &sig_ctx.comp_sigs[proto_name];
         let typ = CellType::Component {
             name: proto_name.clone(),
+            is_comb: sig_ctx.comb_comps.contains(proto_name),
         };
         // Components do not have any bindings for parameters
         let fake_binding = LinkedHashMap::with_capacity(0);
@@ -308,7 +531,10 @@ fn add_cell(cell: ast::Cell, sig_ctx: &SigCtx, builder: &mut Builder) {
     };
 
     // Add attributes to the built cell
-    res.borrow_mut().attributes = cell.attributes;
+    let mut built = res.borrow_mut();
+    built.attributes = cell.attributes;
+    built.reference = cell.reference;
+    Ok(())
 }
 
 ///////////////// Group Construction /////////////////////////
@@ -370,10 +596,25 @@ fn atom_to_port(
 ) -> CalyxResult<RRC<Port>> {
     match atom {
         ast::Atom::Num(n) => {
-            let port = builder.add_constant(n.val, n.width).borrow().get("out");
+            let port = builder
+                .add_wide_constant(n.val, n.width)
+                .borrow()
+                .get("out");
             Ok(Rc::clone(&port))
         }
         ast::Atom::Port(p) => get_port_ref(p, builder.component),
+        ast::Atom::Select { port, msb, lsb } => {
+            let port = get_port_ref(port, builder.component)?;
+            let width = port.borrow().width;
+            if msb >= width {
+                let Canonical(c, p) = port.borrow().canonical();
+                return Err(Error::malformed_structure(format!(
+                    "Bit-select `{}.{}[{}:{}]` is out of range for a port of width {}",
+                    c, p, msb, lsb, width
+                )));
+            }
+            Ok(Port::select(&port, msb, lsb))
+        }
     }
 }
 
@@ -498,6 +739,7 @@ fn build_control(
         }
         ast::Control::Invoke {
             comp: component,
+            ref_cells,
             inputs,
             outputs,
             attributes,
@@ -508,6 +750,21 @@ fn build_control(
                     Error::undefined(component.clone(), "cell".to_string())
                 })?,
             );
+            let ref_cells = ref_cells
+                .into_iter()
+                .map(|(id, outcell)| {
+                    builder
+                        .component
+                        .find_cell(&outcell)
+                        .ok_or_else(|| {
+                            Error::undefined(
+                                outcell.clone(),
+                                "cell".to_string(),
+                            )
+                        })
+                        .map(|cell| (id, cell))
+                })
+                .collect::<Result<_, _>>()?;
             let inputs = inputs
                 .into_iter()
                 .map(|(id, port)| {
@@ -519,6 +776,17 @@ fn build_control(
             let outputs = outputs
                 .into_iter()
                 .map(|(id, port)| {
+                    if let ast::Atom::Select { port, .. } = port {
+                        let Canonical(c, p) =
+                            get_port_ref(port, builder.component)?
+                                .borrow()
+                                .canonical();
+                        return Err(Error::malformed_structure(format!(
+                            "Bit-select on `{}.{}` occurs in write position for invoke output `{}`",
+                            c, p, id
+                        ))
+                        .with_pos(&attributes));
+                    }
                     atom_to_port(port, builder)
                         .and_then(|pr| ensure_direction(pr, Direction::Input))
                         .map(|p| (id, p))
@@ -526,6 +794,7 @@ fn build_control(
                 .collect::<Result<_, _>>()?;
             let mut inv = Invoke {
                 comp: cell,
+                ref_cells,
                 inputs,
                 outputs,
                 attributes,
@@ -621,6 +890,50 @@ fn build_control(
             *(con.get_mut_attributes().unwrap()) = attributes;
             con
         }
+        ast::Control::Repeat {
+            num_repeats,
+            body,
+            attributes,
+        } => {
+            let mut con = Control::repeat(
+                num_repeats,
+                Box::new(build_control(*body, builder)?),
+            );
+            *(con.get_mut_attributes().unwrap()) = attributes;
+            con
+        }
         ast::Control::Empty { .. } => Control::empty(),
+        ast::Control::StaticSeq { stmts, attributes } => {
+            Control::StaticSeq(StaticSeq {
+                stmts: stmts
+                    .into_iter()
+                    .map(|c| build_control(c, builder))
+                    .collect::<CalyxResult<Vec<_>>>()?,
+                attributes,
+            })
+        }
+        ast::Control::StaticPar { stmts, attributes } => {
+            Control::StaticPar(StaticPar {
+                stmts: stmts
+                    .into_iter()
+                    .map(|c| build_control(c, builder))
+                    .collect::<CalyxResult<Vec<_>>>()?,
+                attributes,
+            })
+        }
+        ast::Control::StaticIf {
+            port,
+            tbranch,
+            fbranch,
+            attributes,
+        } => Control::StaticIf(StaticIf {
+            port: ensure_direction(
+                get_port_ref(port, builder.component)?,
+                Direction::Output,
+            )?,
+            tbranch: Box::new(build_control(*tbranch, builder)?),
+            fbranch: Box::new(build_control(*fbranch, builder)?),
+            attributes,
+        }),
     })
 }
@@ -0,0 +1,1271 @@
+//! Serialization of an [ir::Context](crate::ir::Context) as JSON.
+//!
+//! The JSON representation is versioned using [JSON_IR_VERSION]. Programs
+//! are represented using names instead of pointers:
+//! ```json
+//! {
+//!   "version": 1,
+//!   "entrypoint": "main",
+//!   "externs": [{ "path": "primitives/core.futil", "primitives": [..] }],
+//!   "components": [{
+//!     "name": "main",
+//!     "signature": [{ "name": "in", "width": 32, "direction": "input" }],
+//!     "cells": [{
+//!       "name": "r",
+//!       "prototype": { "kind": "primitive", "name": "std_reg", "params": { "WIDTH": 32 } },
+//!       "ports": [..]
+//!     }],
+//!     "groups": [{ "name": "write", "assignments": [{
+//!       "dst": { "kind": "hole", "group": "write", "port": "done" },
+//!       "src": { "kind": "cell", "cell": "r", "port": "done" },
+//!       "guard": { "port": { "kind": "this", "port": "in", "select": [0, 0] } }
+//!     }] }],
+//!     "control": { "kind": "enable", "group": "write" }
+//!   }]
+//! }
+//! ```
+//! Constants are not listed as cells and are used inline as
+//! `{ "kind": "constant", "value": "10", "width": 32 }`. Values are decimal
+//! strings so that constants wider than 64 bits are represented exactly.
+//! Attribute values are numbers unless they do not fit in 64 bits, in which
+//! case they are decimal strings as well.
+//!
+//! The source locations of the program are not part of the representation.
+//!
+//! The same representation is used to move components between threads: a
+//! [DetachedComponent] owns all of its data, including the source locations,
+//! and does not contain any reference-counted pointers.
+use super::{
+    Assignment, Attributes, Builder, Cell, CellType, CombGroup, Component,
+    Context, Control, Direction, Enable, Group, Guard, Id, If, Invoke,
+    LibrarySignatures, Par, Port, PortComp, PortDef, PortParent, Primitive,
+    Repeat, Seq, StaticIf, StaticPar, StaticSeq, UBig, While, Width,
+    RESERVED_NAMES, RRC,
+};
+use crate::errors::{CalyxResult, Error, Span, WithPos};
+use crate::utils::NameGenerator;
+use linked_hash_map::LinkedHashMap;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// Version of the JSON representation generated by [Context::to_json].
+/// Incremented every time the schema changes.
+pub const JSON_IR_VERSION: u64 = 1;
+
+/// Attributes of an IR node. The source location is only kept by
+/// [DetachedComponent].
+#[derive(Default, Serialize, Deserialize)]
+#[serde(transparent)]
+struct AttrsJson {
+    values: LinkedHashMap<String, AttrValueJson>,
+    #[serde(skip)]
+    span: Option<Span>,
+}
+
+impl AttrsJson {
+    fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// Value of an attribute.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum AttrValueJson {
+    Num(u64),
+    Wide(String),
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+#[derive(Deserialize)]
+struct VersionJson {
+    version: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ContextJson {
+    version: u64,
+    entrypoint: Id,
+    #[serde(default)]
+    externs: Vec<ExternJson>,
+    components: Vec<ComponentJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExternJson {
+    path: PathBuf,
+    primitives: Vec<PrimitiveJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PrimitiveJson {
+    name: Id,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    params: Vec<Id>,
+    signature: Vec<PortDefJson>,
+    #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+    attributes: AttrsJson,
+    #[serde(default, skip_serializing_if = "is_false")]
+    is_comb: bool,
+}
+
+/// Width of a port of a primitive: either a constant or a parameter.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum WidthJson {
+    Const(u64),
+    Param(Id),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum DirectionJson {
+    Input,
+    Output,
+    Inout,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PortDefJson {
+    name: Id,
+    width: WidthJson,
+    direction: DirectionJson,
+    #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+    attributes: AttrsJson,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ComponentJson {
+    name: Id,
+    #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+    attributes: AttrsJson,
+    #[serde(default, skip_serializing_if = "is_false")]
+    is_comb: bool,
+    /// Ports of the component as seen by its users.
+    signature: Vec<PortJson>,
+    #[serde(default)]
+    cells: Vec<CellJson>,
+    #[serde(default)]
+    groups: Vec<GroupJson>,
+    #[serde(default)]
+    comb_groups: Vec<GroupJson>,
+    #[serde(default)]
+    continuous_assignments: Vec<AssignmentJson>,
+    #[serde(default)]
+    control: ControlJson,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PortJson {
+    name: Id,
+    width: u64,
+    direction: DirectionJson,
+    #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+    attributes: AttrsJson,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CellJson {
+    name: Id,
+    prototype: CellTypeJson,
+    ports: Vec<PortJson>,
+    #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+    attributes: AttrsJson,
+    #[serde(default, skip_serializing_if = "is_false")]
+    reference: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum CellTypeJson {
+    Primitive {
+        name: Id,
+        #[serde(default)]
+        params: LinkedHashMap<Id, u64>,
+        #[serde(default)]
+        is_comb: bool,
+    },
+    Component {
+        name: Id,
+        #[serde(default)]
+        is_comb: bool,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GroupJson {
+    name: Id,
+    #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+    attributes: AttrsJson,
+    assignments: Vec<AssignmentJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AssignmentJson {
+    dst: PortRefJson,
+    src: PortRefJson,
+    #[serde(default, skip_serializing_if = "GuardJson::is_true")]
+    guard: GuardJson,
+    #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+    attributes: AttrsJson,
+}
+
+/// Reference to a port. `select` is the `[msb, lsb]` range of a bit-select.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum PortRefJson {
+    /// Port defined by the signature of the component.
+    This {
+        port: Id,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        select: Option<(u64, u64)>,
+    },
+    /// Port of a cell.
+    Cell {
+        cell: Id,
+        port: Id,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        select: Option<(u64, u64)>,
+    },
+    /// Hole of a group.
+    Hole { group: Id, port: Id },
+    /// Constant value.
+    Constant { value: String, width: u64 },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum CompOpJson {
+    Eq,
+    Neq,
+    Gt,
+    Lt,
+    Geq,
+    Leq,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum GuardJson {
+    True,
+    Port(PortRefJson),
+    Not(Box<GuardJson>),
+    And(Box<GuardJson>, Box<GuardJson>),
+    Or(Box<GuardJson>, Box<GuardJson>),
+    Comp {
+        op: CompOpJson,
+        left: PortRefJson,
+        right: PortRefJson,
+    },
+}
+
+impl Default for GuardJson {
+    fn default() -> Self {
+        GuardJson::True
+    }
+}
+
+impl GuardJson {
+    fn is_true(&self) -> bool {
+        matches!(self, GuardJson::True)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ControlJson {
+    Empty,
+    Enable {
+        group: Id,
+        #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+        attributes: AttrsJson,
+    },
+    Invoke {
+        cell: Id,
+        #[serde(default, skip_serializing_if = "LinkedHashMap::is_empty")]
+        ref_cells: LinkedHashMap<Id, Id>,
+        #[serde(default)]
+        inputs: LinkedHashMap<Id, PortRefJson>,
+        #[serde(default)]
+        outputs: LinkedHashMap<Id, PortRefJson>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        comb_group: Option<Id>,
+        #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+        attributes: AttrsJson,
+    },
+    Seq {
+        stmts: Vec<ControlJson>,
+        #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+        attributes: AttrsJson,
+    },
+    Par {
+        stmts: Vec<ControlJson>,
+        #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+        attributes: AttrsJson,
+    },
+    If {
+        port: PortRefJson,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cond: Option<Id>,
+        tbranch: Box<ControlJson>,
+        #[serde(default)]
+        fbranch: Box<ControlJson>,
+        #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+        attributes: AttrsJson,
+    },
+    While {
+        port: PortRefJson,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cond: Option<Id>,
+        body: Box<ControlJson>,
+        #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+        attributes: AttrsJson,
+    },
+    Repeat {
+        num_repeats: u64,
+        body: Box<ControlJson>,
+        #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+        attributes: AttrsJson,
+    },
+    StaticSeq {
+        stmts: Vec<ControlJson>,
+        #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+        attributes: AttrsJson,
+    },
+    StaticPar {
+        stmts: Vec<ControlJson>,
+        #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+        attributes: AttrsJson,
+    },
+    StaticIf {
+        port: PortRefJson,
+        tbranch: Box<ControlJson>,
+        #[serde(default)]
+        fbranch: Box<ControlJson>,
+        #[serde(default, skip_serializing_if = "AttrsJson::is_empty")]
+        attributes: AttrsJson,
+    },
+}
+
+impl Default for ControlJson {
+    fn default() -> Self {
+        ControlJson::Empty
+    }
+}
+
+// ======================== IR -> JSON ======================== //
+
+fn attrs_to_json(attrs: &Attributes) -> AttrsJson {
+    let values = attrs
+        .iter()
+        .map(|(k, v)| {
+            let v = match u64::try_from(v) {
+                Ok(n) => AttrValueJson::Num(n),
+                Err(_) => AttrValueJson::Wide(v.to_string()),
+            };
+            (k.clone(), v)
+        })
+        .collect();
+    AttrsJson {
+        values,
+        span: attrs.copy_span(),
+    }
+}
+
+fn dir_to_json(dir: &Direction) -> DirectionJson {
+    match dir {
+        Direction::Input => DirectionJson::Input,
+        Direction::Output => DirectionJson::Output,
+        Direction::Inout => DirectionJson::Inout,
+    }
+}
+
+fn port_to_json(port: &Port) -> PortJson {
+    PortJson {
+        name: port.name.clone(),
+        width: port.width,
+        direction: dir_to_json(&port.direction),
+        attributes: attrs_to_json(&port.attributes),
+    }
+}
+
+fn primitive_to_json(prim: &Primitive) -> PrimitiveJson {
+    PrimitiveJson {
+        name: prim.name.clone(),
+        params: prim.params.clone(),
+        signature: prim
+            .signature
+            .iter()
+            .map(|pd| PortDefJson {
+                name: pd.name.clone(),
+                width: match &pd.width {
+                    Width::Const { value } => WidthJson::Const(*value),
+                    Width::Param { value } => WidthJson::Param(value.clone()),
+                },
+                direction: dir_to_json(&pd.direction),
+                attributes: attrs_to_json(&pd.attributes),
+            })
+            .collect(),
+        attributes: attrs_to_json(&prim.attributes),
+        is_comb: prim.is_comb,
+    }
+}
+
+fn port_ref_to_json(port: &RRC<Port>) -> PortRefJson {
+    let port = port.borrow();
+    match &port.parent {
+        PortParent::Cell(cell) => {
+            let cell_ref = cell.upgrade();
+            let cell = cell_ref.borrow();
+            match &cell.prototype {
+                // Bit-selects on constants are represented as the selected
+                // constant.
+                CellType::Constant { val, width } => match port.select {
+                    Some((_, lsb)) => {
+                        let mask = (UBig::from(1u8) << port.width as usize)
+                            - UBig::from(1u8);
+                        PortRefJson::Constant {
+                            value: ((val >> lsb as usize) & mask).to_string(),
+                            width: port.width,
+                        }
+                    }
+                    None => PortRefJson::Constant {
+                        value: val.to_string(),
+                        width: *width,
+                    },
+                },
+                CellType::ThisComponent => PortRefJson::This {
+                    port: port.name.clone(),
+                    select: port.select,
+                },
+                _ => PortRefJson::Cell {
+                    cell: cell.name().clone(),
+                    port: port.name.clone(),
+                    select: port.select,
+                },
+            }
+        }
+        PortParent::Group(group) => PortRefJson::Hole {
+            group: group.upgrade().borrow().name().clone(),
+            port: port.name.clone(),
+        },
+    }
+}
+
+fn guard_to_json(guard: &Guard) -> GuardJson {
+    match guard {
+        Guard::Or(l, r) => GuardJson::Or(
+            Box::new(guard_to_json(l)),
+            Box::new(guard_to_json(r)),
+        ),
+        Guard::And(l, r) => GuardJson::And(
+            Box::new(guard_to_json(l)),
+            Box::new(guard_to_json(r)),
+        ),
+        Guard::Not(g) => GuardJson::Not(Box::new(guard_to_json(g))),
+        Guard::True => GuardJson::True,
+        Guard::CompOp(op, l, r) => GuardJson::Comp {
+            op: match op {
+                PortComp::Eq => CompOpJson::Eq,
+                PortComp::Neq => CompOpJson::Neq,
+                PortComp::Gt => CompOpJson::Gt,
+                PortComp::Lt => CompOpJson::Lt,
+                PortComp::Geq => CompOpJson::Geq,
+                PortComp::Leq => CompOpJson::Leq,
+            },
+            left: port_ref_to_json(l),
+            right: port_ref_to_json(r),
+        },
+        Guard::Port(p) => GuardJson::Port(port_ref_to_json(p)),
+    }
+}
+
+fn assignments_to_json(assigns: &[Assignment]) -> Vec<AssignmentJson> {
+    assigns
+        .iter()
+        .map(|assign| AssignmentJson {
+            dst: port_ref_to_json(&assign.dst),
+            src: port_ref_to_json(&assign.src),
+            guard: guard_to_json(&assign.guard),
+            attributes: attrs_to_json(&assign.attributes),
+        })
+        .collect()
+}
+
+fn control_to_json(con: &Control) -> ControlJson {
+    let stmts = |stmts: &[Control]| -> Vec<ControlJson> {
+        stmts.iter().map(control_to_json).collect()
+    };
+    match con {
+        Control::Empty(_) => ControlJson::Empty,
+        Control::Enable(Enable { group, attributes }) => ControlJson::Enable {
+            group: group.borrow().name().clone(),
+            attributes: attrs_to_json(attributes),
+        },
+        Control::Invoke(Invoke {
+            comp,
+            ref_cells,
+            inputs,
+            outputs,
+            attributes,
+            comb_group,
+        }) => ControlJson::Invoke {
+            cell: comp.borrow().name().clone(),
+            ref_cells: ref_cells
+                .iter()
+                .map(|(name, cell)| {
+                    (name.clone(), cell.borrow().name().clone())
+                })
+                .collect(),
+            inputs: inputs
+                .iter()
+                .map(|(name, port)| (name.clone(), port_ref_to_json(port)))
+                .collect(),
+            outputs: outputs
+                .iter()
+                .map(|(name, port)| (name.clone(), port_ref_to_json(port)))
+                .collect(),
+            comb_group: comb_group.as_ref().map(|g| g.borrow().name().clone()),
+            attributes: attrs_to_json(attributes),
+        },
+        Control::Seq(Seq {
+            stmts: s,
+            attributes,
+        }) => ControlJson::Seq {
+            stmts: stmts(s),
+            attributes: attrs_to_json(attributes),
+        },
+        Control::Par(Par {
+            stmts: s,
+            attributes,
+        }) => ControlJson::Par {
+            stmts: stmts(s),
+            attributes: attrs_to_json(attributes),
+        },
+        Control::If(If {
+            port,
+            cond,
+            tbranch,
+            fbranch,
+            attributes,
+        }) => ControlJson::If {
+            port: port_ref_to_json(port),
+            cond: cond.as_ref().map(|g| g.borrow().name().clone()),
+            tbranch: Box::new(control_to_json(tbranch)),
+            fbranch: Box::new(control_to_json(fbranch)),
+            attributes: attrs_to_json(attributes),
+        },
+        Control::While(While {
+            port,
+            cond,
+            body,
+            attributes,
+        }) => ControlJson::While {
+            port: port_ref_to_json(port),
+            cond: cond.as_ref().map(|g| g.borrow().name().clone()),
+            body: Box::new(control_to_json(body)),
+            attributes: attrs_to_json(attributes),
+        },
+        Control::Repeat(Repeat {
+            num_repeats,
+            body,
+            attributes,
+        }) => ControlJson::Repeat {
+            num_repeats: *num_repeats,
+            body: Box::new(control_to_json(body)),
+            attributes: attrs_to_json(attributes),
+        },
+        Control::StaticSeq(StaticSeq {
+            stmts: s,
+            attributes,
+        }) => ControlJson::StaticSeq {
+            stmts: stmts(s),
+            attributes: attrs_to_json(attributes),
+        },
+        Control::StaticPar(StaticPar {
+            stmts: s,
+            attributes,
+        }) => ControlJson::StaticPar {
+            stmts: stmts(s),
+            attributes: attrs_to_json(attributes),
+        },
+        Control::StaticIf(StaticIf {
+            port,
+            tbranch,
+            fbranch,
+            attributes,
+        }) => ControlJson::StaticIf {
+            port: port_ref_to_json(port),
+            tbranch: Box::new(control_to_json(tbranch)),
+            fbranch: Box::new(control_to_json(fbranch)),
+            attributes: attrs_to_json(attributes),
+        },
+    }
+}
+
+fn cell_to_json(cell: &Cell) -> Option<CellJson> {
+    let prototype = match &cell.prototype {
+        CellType::Primitive {
+            name,
+            param_binding,
+            is_comb,
+        } => CellTypeJson::Primitive {
+            name: name.clone(),
+            params: param_binding.iter().cloned().collect(),
+            is_comb: *is_comb,
+        },
+        CellType::Component { name, is_comb } => CellTypeJson::Component {
+            name: name.clone(),
+            is_comb: *is_comb,
+        },
+        // Constants are used inline.
+        CellType::Constant { .. } | CellType::ThisComponent => return None,
+    };
+    Some(CellJson {
+        name: cell.name().clone(),
+        prototype,
+        ports: cell
+            .ports
+            .iter()
+            .map(|p| port_to_json(&p.borrow()))
+            .collect(),
+        attributes: attrs_to_json(&cell.attributes),
+        reference: cell.reference,
+    })
+}
+
+fn component_to_json(comp: &Component) -> ComponentJson {
+    ComponentJson {
+        name: comp.name.clone(),
+        attributes: attrs_to_json(&comp.attributes),
+        is_comb: comp.is_comb,
+        // The signature cell stores the ports in reversed direction.
+        signature: comp
+            .signature
+            .borrow()
+            .ports
+            .iter()
+            .map(|p| {
+                let mut port = port_to_json(&p.borrow());
+                port.direction = dir_to_json(&p.borrow().direction.reverse());
+                port
+            })
+            .collect(),
+        cells: comp
+            .cells
+            .iter()
+            .filter_map(|cell| cell_to_json(&cell.borrow()))
+            .collect(),
+        groups: comp
+            .groups
+            .iter()
+            .map(|group| {
+                let group = group.borrow();
+                GroupJson {
+                    name: group.name().clone(),
+                    attributes: attrs_to_json(&group.attributes),
+                    assignments: assignments_to_json(&group.assignments),
+                }
+            })
+            .collect(),
+        comb_groups: comp
+            .comb_groups
+            .iter()
+            .map(|group| {
+                let group = group.borrow();
+                GroupJson {
+                    name: group.name().clone(),
+                    attributes: attrs_to_json(&group.attributes),
+                    assignments: assignments_to_json(&group.assignments),
+                }
+            })
+            .collect(),
+        continuous_assignments: assignments_to_json(
+            &comp.continuous_assignments,
+        ),
+        control: control_to_json(&comp.control.borrow()),
+    }
+}
+
+// ======================== JSON -> IR ======================== //
+
+fn attrs_from_json(attrs: AttrsJson) -> CalyxResult<Attributes> {
+    let values = attrs
+        .values
+        .into_iter()
+        .map(|(k, v)| {
+            let v = match v {
+                AttrValueJson::Num(n) => UBig::from(n),
+                AttrValueJson::Wide(s) => s.parse().map_err(|err| {
+                    Error::invalid_file(format!(
+                        "Invalid value `{}' for attribute `{}': {}",
+                        s, k, err
+                    ))
+                })?,
+            };
+            Ok((k, v))
+        })
+        .collect::<CalyxResult<Vec<_>>>()?;
+    let mut built = Attributes::try_from(values)?;
+    built.set_span(attrs.span);
+    Ok(built)
+}
+
+fn dir_from_json(dir: DirectionJson) -> Direction {
+    match dir {
+        DirectionJson::Input => Direction::Input,
+        DirectionJson::Output => Direction::Output,
+        DirectionJson::Inout => Direction::Inout,
+    }
+}
+
+fn ports_from_json(
+    ports: Vec<PortJson>,
+) -> CalyxResult<Vec<(Id, u64, Direction, Attributes)>> {
+    ports
+        .into_iter()
+        .map(|p| {
+            Ok((
+                p.name,
+                p.width,
+                dir_from_json(p.direction),
+                attrs_from_json(p.attributes)?,
+            ))
+        })
+        .collect()
+}
+
+fn primitive_from_json(prim: PrimitiveJson) -> CalyxResult<Primitive> {
+    Ok(Primitive {
+        name: prim.name,
+        params: prim.params,
+        signature: prim
+            .signature
+            .into_iter()
+            .map(|pd| {
+                Ok(PortDef {
+                    name: pd.name,
+                    width: match pd.width {
+                        WidthJson::Const(value) => Width::Const { value },
+                        WidthJson::Param(value) => Width::Param { value },
+                    },
+                    direction: dir_from_json(pd.direction),
+                    attributes: attrs_from_json(pd.attributes)?,
+                })
+            })
+            .collect::<CalyxResult<_>>()?,
+        attributes: attrs_from_json(prim.attributes)?,
+        is_comb: prim.is_comb,
+    })
+}
+
+/// Builds a [Component] from its JSON representation.
+struct ComponentLoader<'a> {
+    comp: Component,
+    lib: &'a LibrarySignatures,
+}
+
+impl ComponentLoader<'_> {
+    /// Reserve `name` in the component. Returns an error if it is already
+    /// defined.
+    fn define(&mut self, name: &Id, typ: &str) -> CalyxResult<()> {
+        if self.comp.generate_name(name.clone()) != *name {
+            return Err(Error::already_bound(name.clone(), typ.to_string()));
+        }
+        Ok(())
+    }
+
+    fn cell(&self, name: &Id) -> CalyxResult<RRC<Cell>> {
+        self.comp
+            .find_cell(name)
+            .ok_or_else(|| Error::undefined(name.clone(), "cell".to_string()))
+    }
+
+    fn group(&self, name: &Id) -> CalyxResult<RRC<Group>> {
+        self.comp
+            .find_group(name)
+            .ok_or_else(|| Error::undefined(name.clone(), "group".to_string()))
+    }
+
+    fn comb_group(&self, name: &Id) -> CalyxResult<RRC<CombGroup>> {
+        self.comp.find_comb_group(name).ok_or_else(|| {
+            Error::undefined(name.clone(), "combinational group".to_string())
+        })
+    }
+
+    fn cell_port(
+        &self,
+        cell: &RRC<Cell>,
+        port: &Id,
+        select: Option<(u64, u64)>,
+    ) -> CalyxResult<RRC<Port>> {
+        let cell = cell.borrow();
+        let base = cell.find(port).ok_or_else(|| {
+            Error::undefined(
+                port.clone(),
+                format!("port on cell `{}'", cell.name()),
+            )
+        })?;
+        match select {
+            None => Ok(base),
+            Some((msb, lsb)) => {
+                if lsb > msb || msb >= base.borrow().width {
+                    return Err(Error::malformed_structure(format!(
+                        "Component `{}': invalid bit-select [{}:{}] on `{}.{}'",
+                        self.comp.name,
+                        msb,
+                        lsb,
+                        cell.name(),
+                        port
+                    )));
+                }
+                Ok(Port::select(&base, msb, lsb))
+            }
+        }
+    }
+
+    fn port(&mut self, port: PortRefJson) -> CalyxResult<RRC<Port>> {
+        match port {
+            PortRefJson::This { port, select } => {
+                let sig = Rc::clone(&self.comp.signature);
+                self.cell_port(&sig, &port, select)
+            }
+            PortRefJson::Cell { cell, port, select } => {
+                let cell = self.cell(&cell)?;
+                self.cell_port(&cell, &port, select)
+            }
+            PortRefJson::Hole { group, port } => {
+                let group_ref = self.group(&group)?;
+                let hole = group_ref.borrow().find(&port);
+                hole.ok_or_else(|| {
+                    Error::undefined(port, format!("hole on group `{}'", group))
+                })
+            }
+            PortRefJson::Constant { value, width } => {
+                let val = value.parse::<UBig>().map_err(|err| {
+                    Error::invalid_file(format!(
+                        "Invalid constant `{}' in component `{}': {}",
+                        value, self.comp.name, err
+                    ))
+                })?;
+                let cell = Builder::new(&mut self.comp, self.lib)
+                    .not_generated()
+                    .add_wide_constant(val, width);
+                let out = cell.borrow().get("out");
+                Ok(out)
+            }
+        }
+    }
+
+    fn guard(&mut self, guard: GuardJson) -> CalyxResult<Guard> {
+        Ok(match guard {
+            GuardJson::True => Guard::True,
+            GuardJson::Port(p) => Guard::Port(self.port(p)?),
+            GuardJson::Not(g) => Guard::Not(Box::new(self.guard(*g)?)),
+            GuardJson::And(l, r) => {
+                Guard::And(Box::new(self.guard(*l)?), Box::new(self.guard(*r)?))
+            }
+            GuardJson::Or(l, r) => {
+                Guard::Or(Box::new(self.guard(*l)?), Box::new(self.guard(*r)?))
+            }
+            GuardJson::Comp { op, left, right } => {
+                let op = match op {
+                    CompOpJson::Eq => PortComp::Eq,
+                    CompOpJson::Neq => PortComp::Neq,
+                    CompOpJson::Gt => PortComp::Gt,
+                    CompOpJson::Lt => PortComp::Lt,
+                    CompOpJson::Geq => PortComp::Geq,
+                    CompOpJson::Leq => PortComp::Leq,
+                };
+                Guard::CompOp(op, self.port(left)?, self.port(right)?)
+            }
+        })
+    }
+
+    fn assignments(
+        &mut self,
+        assigns: Vec<AssignmentJson>,
+    ) -> CalyxResult<Vec<Assignment>> {
+        assigns
+            .into_iter()
+            .map(|assign| {
+                Ok(Assignment {
+                    dst: self.port(assign.dst)?,
+                    src: self.port(assign.src)?,
+                    guard: Box::new(self.guard(assign.guard)?),
+                    attributes: attrs_from_json(assign.attributes)?,
+                })
+            })
+            .collect()
+    }
+
+    fn control(&mut self, con: ControlJson) -> CalyxResult<Control> {
+        Ok(match con {
+            ControlJson::Empty => Control::empty(),
+            ControlJson::Enable { group, attributes } => {
+                Control::Enable(Enable {
+                    group: self.group(&group)?,
+                    attributes: attrs_from_json(attributes)?,
+                })
+            }
+            ControlJson::Invoke {
+                cell,
+                ref_cells,
+                inputs,
+                outputs,
+                comb_group,
+                attributes,
+            } => Control::Invoke(Invoke {
+                comp: self.cell(&cell)?,
+                ref_cells: ref_cells
+                    .into_iter()
+                    .map(|(name, cell)| Ok((name, self.cell(&cell)?)))
+                    .collect::<CalyxResult<_>>()?,
+                inputs: inputs
+                    .into_iter()
+                    .map(|(name, port)| Ok((name, self.port(port)?)))
+                    .collect::<CalyxResult<_>>()?,
+                outputs: outputs
+                    .into_iter()
+                    .map(|(name, port)| Ok((name, self.port(port)?)))
+                    .collect::<CalyxResult<_>>()?,
+                comb_group: comb_group
+                    .map(|g| self.comb_group(&g))
+                    .transpose()?,
+                attributes: attrs_from_json(attributes)?,
+            }),
+            ControlJson::Seq { stmts, attributes } => Control::Seq(Seq {
+                stmts: self.stmts(stmts)?,
+                attributes: attrs_from_json(attributes)?,
+            }),
+            ControlJson::Par { stmts, attributes } => Control::Par(Par {
+                stmts: self.stmts(stmts)?,
+                attributes: attrs_from_json(attributes)?,
+            }),
+            ControlJson::If {
+                port,
+                cond,
+                tbranch,
+                fbranch,
+                attributes,
+            } => Control::If(If {
+                port: self.port(port)?,
+                cond: cond.map(|g| self.comb_group(&g)).transpose()?,
+                tbranch: Box::new(self.control(*tbranch)?),
+                fbranch: Box::new(self.control(*fbranch)?),
+                attributes: attrs_from_json(attributes)?,
+            }),
+            ControlJson::While {
+                port,
+                cond,
+                body,
+                attributes,
+            } => Control::While(While {
+                port: self.port(port)?,
+                cond: cond.map(|g| self.comb_group(&g)).transpose()?,
+                body: Box::new(self.control(*body)?),
+                attributes: attrs_from_json(attributes)?,
+            }),
+            ControlJson::Repeat {
+                num_repeats,
+                body,
+                attributes,
+            } => Control::Repeat(Repeat {
+                num_repeats,
+                body: Box::new(self.control(*body)?),
+                attributes: attrs_from_json(attributes)?,
+            }),
+            ControlJson::StaticSeq { stmts, attributes } => {
+                Control::StaticSeq(StaticSeq {
+                    stmts: self.stmts(stmts)?,
+                    attributes: attrs_from_json(attributes)?,
+                })
+            }
+            ControlJson::StaticPar { stmts, attributes } => {
+                Control::StaticPar(StaticPar {
+                    stmts: self.stmts(stmts)?,
+                    attributes: attrs_from_json(attributes)?,
+                })
+            }
+            ControlJson::StaticIf {
+                port,
+                tbranch,
+                fbranch,
+                attributes,
+            } => Control::StaticIf(StaticIf {
+                port: self.port(port)?,
+                tbranch: Box::new(self.control(*tbranch)?),
+                fbranch: Box::new(self.control(*fbranch)?),
+                attributes: attrs_from_json(attributes)?,
+            }),
+        })
+    }
+
+    fn stmts(&mut self, stmts: Vec<ControlJson>) -> CalyxResult<Vec<Control>> {
+        stmts.into_iter().map(|s| self.control(s)).collect()
+    }
+
+    fn load(
+        json: ComponentJson,
+        lib: &LibrarySignatures,
+    ) -> CalyxResult<Component> {
+        let mut comp =
+            Component::new(&json.name, ports_from_json(json.signature)?);
+        comp.is_comb = json.is_comb;
+        comp.attributes = attrs_from_json(json.attributes)?;
+        let mut loader = ComponentLoader { comp, lib };
+
+        for cell in json.cells {
+            loader.define(&cell.name, "cell")?;
+            let typ = match cell.prototype {
+                CellTypeJson::Primitive {
+                    name,
+                    params,
+                    is_comb,
+                } => CellType::Primitive {
+                    name,
+                    param_binding: Box::new(params.into_iter().collect()),
+                    is_comb,
+                },
+                CellTypeJson::Component { name, is_comb } => {
+                    CellType::Component { name, is_comb }
+                }
+            };
+            let built = Builder::cell_from_signature(
+                cell.name,
+                typ,
+                ports_from_json(cell.ports)?,
+            );
+            built.borrow_mut().attributes = attrs_from_json(cell.attributes)?;
+            built.borrow_mut().reference = cell.reference;
+            loader.comp.cells.add(built);
+        }
+
+        // Define all groups before building assignments because assignments
+        // may use the holes of any group.
+        let mut groups = Vec::with_capacity(json.groups.len());
+        for group in json.groups {
+            // Adds the `go` and `done` holes to the group.
+            let built = Builder::new(&mut loader.comp, lib)
+                .not_generated()
+                .add_group(group.name.clone());
+            if *built.borrow().name() != group.name {
+                return Err(Error::already_bound(
+                    group.name,
+                    "group".to_string(),
+                ));
+            }
+            built.borrow_mut().attributes = attrs_from_json(group.attributes)?;
+            groups.push((built, group.assignments));
+        }
+        let mut comb_groups = Vec::with_capacity(json.comb_groups.len());
+        for group in json.comb_groups {
+            loader.define(&group.name, "combinational group")?;
+            let built = Rc::new(RefCell::new(CombGroup {
+                name: group.name,
+                attributes: attrs_from_json(group.attributes)?,
+                assignments: vec![],
+            }));
+            loader.comp.comb_groups.add(Rc::clone(&built));
+            comb_groups.push((built, group.assignments));
+        }
+        for (group, assigns) in groups {
+            let assigns = loader.assignments(assigns)?;
+            group.borrow_mut().assignments = assigns;
+        }
+        for (group, assigns) in comb_groups {
+            let assigns = loader.assignments(assigns)?;
+            group.borrow_mut().assignments = assigns;
+        }
+        let continuous = loader.assignments(json.continuous_assignments)?;
+        loader.comp.continuous_assignments = continuous;
+
+        let control = loader.control(json.control)?;
+        loader.comp.control = Rc::new(RefCell::new(control));
+
+        let mut comp = loader.comp;
+        comp.add_names(RESERVED_NAMES.iter().map(|s| s.to_string()).collect());
+        Ok(comp)
+    }
+}
+
+impl Context {
+    /// Serialize the program as JSON. The format is described in the
+    /// [module documentation](crate::ir::json).
+    pub fn to_json(&self) -> String {
+        let json = ContextJson {
+            version: JSON_IR_VERSION,
+            entrypoint: self.entrypoint.clone(),
+            externs: self
+                .lib
+                .primitive_definitions
+                .iter()
+                .map(|(path, prims)| ExternJson {
+                    path: path.clone(),
+                    primitives: prims.values().map(primitive_to_json).collect(),
+                })
+                .collect(),
+            components: self.components.iter().map(component_to_json).collect(),
+        };
+        serde_json::to_string_pretty(&json)
+            .expect("Failed to serialize the program as JSON")
+    }
+
+    /// Build a program from the JSON representation generated by
+    /// [Context::to_json]. The backend configuration and extra options of the
+    /// returned context are empty.
+    pub fn from_json(input: &str) -> CalyxResult<Context> {
+        let malformed = |err: serde_json::Error| {
+            Error::invalid_file(format!("Malformed JSON IR: {}", err))
+        };
+        // Check the version before the rest of the schema.
+        let version: VersionJson =
+            serde_json::from_str(input).map_err(malformed)?;
+        if version.version != JSON_IR_VERSION {
+            return Err(Error::invalid_file(format!(
+                "Unsupported JSON IR version {}. This compiler supports version {}.",
+                version.version, JSON_IR_VERSION
+            )));
+        }
+        let json: ContextJson =
+            serde_json::from_str(input).map_err(malformed)?;
+
+        let externs = json
+            .externs
+            .into_iter()
+            .map(|ext| {
+                let prims = ext
+                    .primitives
+                    .into_iter()
+                    .map(primitive_from_json)
+                    .collect::<CalyxResult<Vec<_>>>()?;
+                Ok((ext.path, prims))
+            })
+            .collect::<CalyxResult<Vec<_>>>()?;
+        let lib: LibrarySignatures = externs.into();
+
+        let entrypoint = json.entrypoint;
+        let components = json
+            .components
+            .into_iter()
+            .map(|comp| ComponentLoader::load(comp, &lib))
+            .collect::<CalyxResult<Vec<_>>>()?;
+
+        if !components.iter().any(|c| c.name == entrypoint) {
+            return Err(Error::undefined(
+                entrypoint,
+                "entrypoint component".to_string(),
+            ));
+        }
+
+        Ok(Context {
+            components,
+            lib,
+            entrypoint,
+            extra_opts: vec![],
+        })
+    }
+}
+
+// ======================== Detached components ======================== //
+
+/// A component converted into owned data without reference-counted pointers
+/// so that it can be moved to another thread.
+/// Unlike the JSON text, it keeps the source locations and the name generator
+/// of the component, so attaching it again gives back the same component.
+pub struct DetachedComponent {
+    comp: ComponentJson,
+    namegen: NameGenerator,
+}
+
+// Checked by the compiler instead of promised by an `unsafe impl`.
+const _: fn() = || {
+    fn is_send<T: Send>() {}
+    is_send::<DetachedComponent>();
+};
+
+impl DetachedComponent {
+    /// Detach `comp`. Pointers into `comp` held elsewhere do not observe
+    /// changes made to the component after it is attached again.
+    pub fn new(comp: Component) -> Self {
+        let json = component_to_json(&comp);
+        Self {
+            comp: json,
+            namegen: comp.into_namegen(),
+        }
+    }
+
+    /// Rebuild the component using the primitives defined in `lib`.
+    pub fn attach(self, lib: &LibrarySignatures) -> CalyxResult<Component> {
+        let mut comp = ComponentLoader::load(self.comp, lib)?;
+        comp.set_namegen(self.namegen);
+        Ok(comp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DetachedComponent;
+    use crate::errors::WithPos;
+    use crate::{frontend::Workspace, ir};
+    use std::path::{Path, PathBuf};
+
+    const SRC: &str = r#"import "primitives/core.futil";
+component main() -> () {
+  cells {
+    r = std_reg(32);
+  }
+  wires {
+    group upd {
+      r.in = 32'd1;
+      r.write_en = 1'd1;
+      upd[done] = r.done;
+    }
+  }
+  control { upd; }
+}
+"#;
+
+    fn print(comp: &ir::Component) -> String {
+        let mut out = vec![];
+        ir::Printer::write_component(comp, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn detach_and_attach() {
+        let lib = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        let ws = Workspace::construct_from_source(
+            SRC,
+            Path::new("test.futil"),
+            &lib,
+        )
+        .unwrap();
+        let mut ctx = ir::from_ast::ast_to_ir(ws).unwrap();
+        let mut comp = ctx.components.remove(0);
+        let before = print(&comp);
+        let generated = comp.generate_name("fsm");
+
+        let mut comp = DetachedComponent::new(comp).attach(&ctx.lib).unwrap();
+        assert_eq!(print(&comp), before);
+        // Source locations and generated names are kept.
+        let group = comp.find_group(&"upd").unwrap();
+        assert!(group.borrow().attributes.copy_span().is_some());
+        assert_ne!(comp.generate_name("fsm"), generated);
+    }
+}
@@ -13,22 +13,30 @@ mod common;
 mod component;
 mod context;
 mod control;
+pub mod dsl;
 mod guard;
 mod id;
+pub mod json;
 mod primitives;
 mod printer;
 mod reserved_names;
 pub mod rewriter;
 mod structure;
+mod verifier;
 
 // Re-export types at the module level.
-pub use attribute::{Attributes, GetAttributes};
+pub use attribute::{Attributes, GetAttributes, NUMERIC_ATTRIBUTES};
 pub use builder::Builder;
 pub use common::{RRC, WRC};
 pub use component::{Component, IdList};
-pub use context::{BackendConf, Context, LibrarySignatures};
-pub use control::{Control, Empty, Enable, If, Invoke, Par, Seq, While};
+pub use context::{Context, LibrarySignatures};
+pub use control::{
+    Control, Empty, Enable, If, Invoke, Par, Repeat, Seq, StaticIf, StaticPar,
+    StaticSeq, While,
+};
 pub use guard::{Guard, PortComp};
+/// Arbitrary-precision unsigned integers used to represent constants.
+pub use ibig::UBig;
 pub use id::Id;
 pub use primitives::{PortDef, Primitive, Width};
 pub use printer::Printer;
@@ -38,6 +46,7 @@ pub use structure::{
     Assignment, Binding, Canonical, Cell, CellType, CloneName, CombGroup,
     Direction, GetName, Group, Port, PortIterator, PortParent,
 };
+pub use verifier::verify;
 
 /// Visitor to traverse a control program.
 pub mod traversal;
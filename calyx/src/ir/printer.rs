@@ -19,7 +19,7 @@ impl Printer {
             .attrs
             .iter()
             .map(|(k, v)| {
-                if *v == 1 {
+                if *v == ir::UBig::from(1u8) {
                     format!("@{}", k)
                 } else {
                     format!("@{}({})", k, v)
@@ -160,7 +160,8 @@ impl Printer {
 
         writeln!(
             f,
-            "component {}{}({}) -> ({}) {{",
+            "{}component {}{}({}) -> ({}) {{",
+            if comp.is_comb { "comb " } else { "" },
             comp.name.id,
             Self::format_attributes(&comp.attributes),
             Self::format_ports(&inputs),
@@ -189,6 +190,11 @@ impl Printer {
             Self::write_assignment(assign, 4, f)?;
             writeln!(f)?;
         }
+        // Combinational components do not have a control program
+        if comp.is_comb {
+            writeln!(f, "  }}")?;
+            return write!(f, "}}");
+        }
         writeln!(f, "  }}\n")?;
 
         // Add the control program
@@ -223,6 +229,9 @@ impl Printer {
                         Self::format_at_attributes(&cell.attributes)
                     )?
                 }
+                if cell.is_reference() {
+                    write!(f, "ref ")?;
+                }
                 write!(f, "{} = ", cell.name().id)?;
                 writeln!(
                     f,
@@ -235,7 +244,7 @@ impl Printer {
                         .join(", ")
                 )
             }
-            ir::CellType::Component { name } => {
+            ir::CellType::Component { name, .. } => {
                 write!(f, "{}", " ".repeat(indent_level))?;
                 if !cell.attributes.is_empty() {
                     write!(
@@ -244,6 +253,9 @@ impl Printer {
                         Self::format_at_attributes(&cell.attributes)
                     )?
                 }
+                if cell.is_reference() {
+                    write!(f, "ref ")?;
+                }
                 writeln!(f, "{} = {}();", cell.name().id, name)
             }
             ir::CellType::Constant { .. } => Ok(()),
@@ -338,6 +350,7 @@ impl Printer {
             }
             ir::Control::Invoke(ir::Invoke {
                 comp,
+                ref_cells,
                 inputs,
                 outputs,
                 attributes,
@@ -346,7 +359,22 @@ impl Printer {
                 if !attributes.is_empty() {
                     write!(f, "{} ", Self::format_at_attributes(attributes))?
                 }
-                write!(f, "invoke {}(", comp.borrow().name())?;
+                write!(f, "invoke {}", comp.borrow().name())?;
+                if !ref_cells.is_empty() {
+                    write!(
+                        f,
+                        "[{}]",
+                        ref_cells
+                            .iter()
+                            .map(|(name, cell)| format!(
+                                "{}={}",
+                                name,
+                                cell.borrow().name()
+                            ))
+                            .join(", ")
+                    )?;
+                }
+                write!(f, "(")?;
                 for (i, (arg, port)) in inputs.iter().enumerate() {
                     write!(
                         f,
@@ -445,6 +473,62 @@ impl Printer {
                 Self::write_control(body, indent_level + 2, f)?;
                 writeln!(f, "{}}}", " ".repeat(indent_level))
             }
+            ir::Control::Repeat(ir::Repeat {
+                num_repeats,
+                body,
+                attributes,
+            }) => {
+                if !attributes.is_empty() {
+                    write!(f, "{} ", Self::format_at_attributes(attributes))?
+                }
+                writeln!(f, "repeat {} {{", num_repeats)?;
+                Self::write_control(body, indent_level + 2, f)?;
+                writeln!(f, "{}}}", " ".repeat(indent_level))
+            }
+            ir::Control::StaticSeq(ir::StaticSeq { stmts, attributes }) => {
+                if !attributes.is_empty() {
+                    write!(f, "{} ", Self::format_at_attributes(attributes))?
+                }
+                writeln!(f, "static seq {{")?;
+                for stmt in stmts {
+                    Self::write_control(stmt, indent_level + 2, f)?;
+                }
+                writeln!(f, "{}}}", " ".repeat(indent_level))
+            }
+            ir::Control::StaticPar(ir::StaticPar { stmts, attributes }) => {
+                if !attributes.is_empty() {
+                    write!(f, "{} ", Self::format_at_attributes(attributes))?
+                }
+                writeln!(f, "static par {{")?;
+                for stmt in stmts {
+                    Self::write_control(stmt, indent_level + 2, f)?;
+                }
+                writeln!(f, "{}}}", " ".repeat(indent_level))
+            }
+            ir::Control::StaticIf(ir::StaticIf {
+                port,
+                tbranch,
+                fbranch,
+                attributes,
+            }) => {
+                if !attributes.is_empty() {
+                    write!(f, "{} ", Self::format_at_attributes(attributes))?
+                }
+                writeln!(
+                    f,
+                    "static if {} {{",
+                    Self::port_to_str(&port.borrow())
+                )?;
+                Self::write_control(tbranch, indent_level + 2, f)?;
+                write!(f, "{}}}", " ".repeat(indent_level))?;
+                if let ir::Control::Empty(_) = **fbranch {
+                    writeln!(f)
+                } else {
+                    writeln!(f, " else {{")?;
+                    Self::write_control(fbranch, indent_level + 2, f)?;
+                    writeln!(f, "{}}}", " ".repeat(indent_level))
+                }
+            }
             ir::Control::Empty(_) => writeln!(f),
         }
     }
@@ -488,6 +572,11 @@ impl Printer {
 
     /// Get the port access expression.
     pub fn port_to_str(port: &ir::Port) -> String {
+        let select = match port.select {
+            Some((msb, lsb)) if msb == lsb => format!("[{}]", msb),
+            Some((msb, lsb)) => format!("[{}:{}]", msb, lsb),
+            None => String::new(),
+        };
         match &port.parent {
             ir::PortParent::Cell(cell_wref) => {
                 let cell_ref =
@@ -498,12 +587,30 @@ impl Printer {
                         )
                     });
                 let cell = cell_ref.borrow();
-                match cell.prototype {
+                match &cell.prototype {
+                    // Bit-selects on constants are printed as the selected
+                    // constant.
                     ir::CellType::Constant { val, width } => {
-                        format!("{}'d{}", width, val)
+                        match port.select {
+                            Some((_, lsb)) => {
+                                let mask = (ir::UBig::from(1u8)
+                                    << port.width as usize)
+                                    - ir::UBig::from(1u8);
+                                format!(
+                                    "{}'d{}",
+                                    port.width,
+                                    (val >> lsb as usize) & mask
+                                )
+                            }
+                            None => format!("{}'d{}", width, val),
+                        }
+                    }
+                    ir::CellType::ThisComponent => {
+                        format!("{}{}", port.name, select)
+                    }
+                    _ => {
+                        format!("{}.{}{}", cell.name().id, port.name.id, select)
                     }
-                    ir::CellType::ThisComponent => port.name.to_string(),
-                    _ => format!("{}.{}", cell.name().id, port.name.id),
                 }
             }
             ir::PortParent::Group(group_wref) => format!(
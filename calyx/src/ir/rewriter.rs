@@ -85,10 +85,17 @@ impl<'a> Rewriter<'a> {
     }
 
     /// Get any port rewrite defined for the given port.
+    /// Bit-selects are rewritten by applying the same select to the rewrite of
+    /// the port they read from.
     #[inline]
     pub fn get(&self, port_ref: &RRC<ir::Port>) -> Option<RRC<ir::Port>> {
-        self.get_port_rewrite(port_ref)
-            .or_else(|| self.get_cell_port_rewrite(port_ref))
+        let new_port = self
+            .get_port_rewrite(port_ref)
+            .or_else(|| self.get_cell_port_rewrite(port_ref))?;
+        match port_ref.borrow().select {
+            Some((msb, lsb)) => Some(ir::Port::select(&new_port, msb, lsb)),
+            None => Some(new_port),
+        }
     }
 
     // =========== Control Rewriting Methods =============
@@ -104,6 +111,13 @@ impl<'a> Rewriter<'a> {
             inv.comp = Rc::clone(new_cell);
         }
 
+        // Rewrite the cells bound to `ref` cells
+        inv.ref_cells.iter_mut().for_each(|(_, cell)| {
+            if let Some(new_cell) = self.get_cell_rewrite(&cell.clone_name()) {
+                *cell = new_cell;
+            }
+        });
+
         // Rewrite the combinational group
         if let Some(cg_ref) = &inv.comb_group {
             let cg = cg_ref.borrow().clone_name();
@@ -140,7 +154,9 @@ impl<'a> Rewriter<'a> {
                 }
             }
             ir::Control::Seq(ir::Seq { stmts, .. })
-            | ir::Control::Par(ir::Par { stmts, .. }) => {
+            | ir::Control::Par(ir::Par { stmts, .. })
+            | ir::Control::StaticSeq(ir::StaticSeq { stmts, .. })
+            | ir::Control::StaticPar(ir::StaticPar { stmts, .. }) => {
                 stmts.iter_mut().for_each(|c| {
                     self.rewrite_control(c, group_map, comb_group_map)
                 })
@@ -184,6 +200,27 @@ impl<'a> Rewriter<'a> {
                 // rewrite body
                 self.rewrite_control(&mut wh.body, group_map, comb_group_map);
             }
+            ir::Control::Repeat(rep) => {
+                // rewrite body
+                self.rewrite_control(&mut rep.body, group_map, comb_group_map);
+            }
+            ir::Control::StaticIf(sif) => {
+                // Rewrite port use
+                if let Some(new_port) = self.get(&sif.port) {
+                    sif.port = new_port;
+                }
+                // rewrite branches
+                self.rewrite_control(
+                    &mut sif.tbranch,
+                    group_map,
+                    comb_group_map,
+                );
+                self.rewrite_control(
+                    &mut sif.fbranch,
+                    group_map,
+                    comb_group_map,
+                );
+            }
             ir::Control::Invoke(inv) => {
                 self.rewrite_invoke(inv, comb_group_map)
             }
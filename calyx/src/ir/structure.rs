@@ -1,6 +1,7 @@
 //! Representation for structure (wires and cells) in a Calyx program.
-use super::{Attributes, GetAttributes, Guard, Id, RRC, WRC};
+use super::{Attributes, GetAttributes, Guard, Id, UBig, RRC, WRC};
 use smallvec::{smallvec, SmallVec};
+use std::cell::RefCell;
 use std::hash::Hash;
 use std::rc::Rc;
 
@@ -46,6 +47,9 @@ pub struct Port {
     pub parent: PortParent,
     /// Attributes associated with this port.
     pub attributes: Attributes,
+    /// Bit range `(msb, lsb)` read from the port named `name` on `parent`.
+    /// Only ports created using [Port::select] have this set.
+    pub select: Option<(u64, u64)>,
 }
 
 /// Canonical name of a Port
@@ -70,9 +74,9 @@ impl Port {
     /// Checks if this port is a constant of value: `val`.
     pub fn is_constant(&self, val: u64, width: u64) -> bool {
         if let PortParent::Cell(cell) = &self.parent {
-            match cell.upgrade().borrow().prototype {
+            match &cell.upgrade().borrow().prototype {
                 CellType::Constant { val: v, width: w } => {
-                    v == val && width == w
+                    *v == UBig::from(val) && width == *w
                 }
                 _ => false,
             }
@@ -90,15 +94,44 @@ impl Port {
     }
 
     /// Get the canonical representation for this Port.
+    /// A bit-select shares the canonical name of the port it selects from.
     pub fn canonical(&self) -> Canonical {
         Canonical(self.get_parent_name(), self.name.clone())
     }
+
+    /// Construct a port that reads bits `msb` down to `lsb` (inclusive) of
+    /// `port`. Selecting from a bit-select composes the two ranges.
+    pub fn select(port: &RRC<Port>, msb: u64, lsb: u64) -> RRC<Port> {
+        let base = port.borrow();
+        debug_assert!(lsb <= msb && msb < base.width);
+        let offset = base.select.map(|(_, l)| l).unwrap_or(0);
+        Rc::new(RefCell::new(Port {
+            name: base.name.clone(),
+            width: msb - lsb + 1,
+            direction: base.direction.clone(),
+            parent: base.parent.clone(),
+            attributes: Attributes::default(),
+            select: Some((msb + offset, lsb + offset)),
+        }))
+    }
+
+    /// Returns the port this bit-select reads from or the port itself if it
+    /// is not a bit-select.
+    pub fn base_port(&self) -> RRC<Port> {
+        match &self.parent {
+            PortParent::Cell(cell) => cell.upgrade().borrow().get(&self.name),
+            PortParent::Group(group) => {
+                group.upgrade().borrow().get(&self.name)
+            }
+        }
+    }
 }
 
 impl PartialEq for Port {
     fn eq(&self, other: &Self) -> bool {
         self.get_parent_name() == other.get_parent_name()
             && self.name == other.name
+            && self.select == other.select
     }
 }
 
@@ -146,13 +179,15 @@ pub enum CellType {
     Component {
         /// Name of the component used to instantiate this cell.
         name: Id,
+        /// True iff this is a combinational component
+        is_comb: bool,
     },
     /// This cell represents the current component
     ThisComponent,
     /// Cell representing a Constant
     Constant {
         /// Value of this constant
-        val: u64,
+        val: UBig,
         /// Width of this constant
         width: u64,
     },
@@ -169,6 +204,8 @@ pub struct Cell {
     pub prototype: CellType,
     /// Attributes for this group.
     pub attributes: Attributes,
+    /// Whether this cell is a reference to a cell bound by the caller.
+    pub reference: bool,
 }
 
 impl GetAttributes for Cell {
@@ -189,9 +226,16 @@ impl Cell {
             ports: smallvec![],
             prototype,
             attributes: Attributes::default(),
+            reference: false,
         }
     }
 
+    /// Returns true iff this cell is a `ref` cell whose concrete instance is
+    /// provided by the invoking component.
+    pub fn is_reference(&self) -> bool {
+        self.reference
+    }
+
     /// Get a reference to the named port if it exists.
     pub fn find<S>(&self, name: S) -> Option<RRC<Port>>
     where
@@ -230,6 +274,17 @@ impl Cell {
         matches!(&self.prototype, CellType::Component { .. })
     }
 
+    /// Returns true iff this cell is a constant or an instance of a
+    /// combinational primitive or component.
+    pub fn is_comb_cell(&self) -> bool {
+        match &self.prototype {
+            CellType::Primitive { is_comb, .. }
+            | CellType::Component { is_comb, .. } => *is_comb,
+            CellType::Constant { .. } => true,
+            CellType::ThisComponent => false,
+        }
+    }
+
     /// Returns true if this is an instance of a primitive. If the optional name is provided then
     /// only returns true if the primitive has the given name.
     pub fn is_primitive<S>(&self, prim: Option<S>) -> bool
@@ -262,9 +317,8 @@ impl Cell {
     /// Returns the name of the component that is this cells type.
     pub fn type_name(&self) -> Option<&Id> {
         match &self.prototype {
-            CellType::Primitive { name, .. } | CellType::Component { name } => {
-                Some(name)
-            }
+            CellType::Primitive { name, .. }
+            | CellType::Component { name, .. } => Some(name),
             CellType::ThisComponent => Some(&self.name),
             CellType::Constant { .. } => None,
         }
@@ -288,12 +342,12 @@ impl Cell {
 
     /// Return the canonical name for the cell generated to represent this
     /// (val, width) constant.
-    pub fn constant_name(val: u64, width: u64) -> Id {
+    pub fn constant_name(val: &UBig, width: u64) -> Id {
         format!("_{}_{}", val, width).into()
     }
 
     /// Return the value associated with this attribute key.
-    pub fn get_attribute<S>(&self, attr: S) -> Option<&u64>
+    pub fn get_attribute<S>(&self, attr: S) -> Option<u64>
     where
         S: AsRef<str>,
     {
@@ -1,7 +1,12 @@
 mod action;
+mod pass_opts;
 mod post_order;
 mod visitor;
 
 pub use action::{Action, VisResult};
+pub use pass_opts::{ParseVal, PassOpt, PassOpts};
 pub use post_order::PostOrder;
-pub use visitor::{ConstructVisitor, Named, Visitable, Visitor};
+pub use visitor::{
+    traversed_components, ComponentLocal, ConstructVisitor, Named, Visitable,
+    Visitor,
+};
@@ -0,0 +1,185 @@
+//! Options for passes and backends provided on the command line using
+//! `-x <pass>:<key>=<value>`.
+use crate::errors::{CalyxResult, Error};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Value of a pass option.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseVal {
+    Bool(bool),
+    Num(u64),
+    String(String),
+}
+
+impl ParseVal {
+    /// Name of the type of this value used in error messages.
+    fn type_name(&self) -> &'static str {
+        match self {
+            ParseVal::Bool(_) => "bool",
+            ParseVal::Num(_) => "int",
+            ParseVal::String(_) => "string",
+        }
+    }
+
+    /// Parse `val` as a value of the same type as `self`.
+    fn parse_like(&self, val: &str) -> Option<ParseVal> {
+        match self {
+            ParseVal::Bool(_) => val.parse().ok().map(ParseVal::Bool),
+            ParseVal::Num(_) => val.parse().ok().map(ParseVal::Num),
+            ParseVal::String(_) => Some(ParseVal::String(val.to_string())),
+        }
+    }
+
+    /// Returns the boolean value. Panics if this is not a [ParseVal::Bool].
+    pub fn bool(&self) -> bool {
+        match self {
+            ParseVal::Bool(b) => *b,
+            v => panic!("Expected bool option, found {}", v.type_name()),
+        }
+    }
+
+    /// Returns the integer value. Panics if this is not a [ParseVal::Num].
+    pub fn num(&self) -> u64 {
+        match self {
+            ParseVal::Num(n) => *n,
+            v => panic!("Expected int option, found {}", v.type_name()),
+        }
+    }
+
+    /// Returns the string value. Panics if this is not a [ParseVal::String].
+    pub fn string(&self) -> &str {
+        match self {
+            ParseVal::String(s) => s,
+            v => panic!("Expected string option, found {}", v.type_name()),
+        }
+    }
+}
+
+impl Display for ParseVal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseVal::Bool(b) => write!(f, "{}", b),
+            ParseVal::Num(n) => write!(f, "{}", n),
+            ParseVal::String(s) => write!(f, "\"{}\"", s),
+        }
+    }
+}
+
+/// An option accepted by a pass along with its default value. The default
+/// value also determines the type of the option.
+#[derive(Clone, Debug)]
+pub struct PassOpt {
+    name: &'static str,
+    description: &'static str,
+    default: ParseVal,
+}
+
+impl PassOpt {
+    pub const fn new(
+        name: &'static str,
+        description: &'static str,
+        default: ParseVal,
+    ) -> Self {
+        Self {
+            name,
+            description,
+            default,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+
+    pub fn default(&self) -> &ParseVal {
+        &self.default
+    }
+}
+
+/// Values of the options of a pass after applying the command line options.
+#[derive(Debug)]
+pub struct PassOpts {
+    values: HashMap<&'static str, ParseVal>,
+}
+
+impl PassOpts {
+    /// Parse the options for the pass `pass` which accepts the options `opts`
+    /// from the list of extra options in the context. Options are of the form
+    /// `<pass>:<key>=<value>`. Boolean options may be written as `<pass>:<key>`
+    /// to set them to `true`.
+    /// Returns an error if an option is unknown or its value has the wrong
+    /// type.
+    pub fn parse(
+        pass: &str,
+        opts: &[PassOpt],
+        extra_opts: &[String],
+    ) -> CalyxResult<Self> {
+        let mut values: HashMap<_, _> =
+            opts.iter().map(|o| (o.name, o.default.clone())).collect();
+
+        for extra in extra_opts {
+            let (key, val) = match extra.split_once(':') {
+                Some((p, rest)) if p == pass => match rest.split_once('=') {
+                    Some((key, val)) => (key, Some(val)),
+                    None => (rest, None),
+                },
+                _ => continue,
+            };
+            let opt = opts.iter().find(|o| o.name == key).ok_or_else(|| {
+                let known = opts.iter().map(|o| o.name).collect::<Vec<_>>();
+                Error::misc(format!(
+                    "Unknown option `{}' for `{}'. {}",
+                    key,
+                    pass,
+                    if known.is_empty() {
+                        format!("`{}' does not accept any options.", pass)
+                    } else {
+                        format!("Valid options: {}", known.join(", "))
+                    }
+                ))
+            })?;
+            let parsed = match (val, &opt.default) {
+                (None, ParseVal::Bool(_)) => ParseVal::Bool(true),
+                (None, default) => {
+                    return Err(Error::misc(format!(
+                        "Option `{}' for `{}' requires a value of type {}: -x {}:{}=<value>",
+                        key,
+                        pass,
+                        default.type_name(),
+                        pass,
+                        key
+                    )))
+                }
+                (Some(val), default) => {
+                    default.parse_like(val).ok_or_else(|| {
+                        Error::misc(format!(
+                            "Option `{}' for `{}' expects a value of type {}, found `{}'",
+                            key,
+                            pass,
+                            default.type_name(),
+                            val
+                        ))
+                    })?
+                }
+            };
+            values.insert(opt.name, parsed);
+        }
+
+        Ok(Self { values })
+    }
+}
+
+impl std::ops::Index<&str> for PassOpts {
+    type Output = ParseVal;
+
+    fn index(&self, key: &str) -> &ParseVal {
+        self.values
+            .get(key)
+            .unwrap_or_else(|| panic!("Pass does not declare option `{}'", key))
+    }
+}
@@ -51,7 +51,9 @@ impl PostOrder {
         // Construct a graph.
         for comp in &comps {
             for cell in comp.cells.iter() {
-                if let CellType::Component { name } = &cell.borrow().prototype {
+                if let CellType::Component { name, .. } =
+                    &cell.borrow().prototype
+                {
                     graph.add_edge(rev_map[name], rev_map[&comp.name], ());
                 }
             }
@@ -4,11 +4,26 @@
 use itertools::Itertools;
 
 use super::action::{Action, VisResult};
+use super::pass_opts::{PassOpt, PassOpts};
 use super::PostOrder;
 use crate::errors::CalyxResult;
-use crate::ir::{self, Component, Context, Control, LibrarySignatures};
-use std::collections::HashSet;
+use crate::ir::{
+    self, json::DetachedComponent, Component, Context, Control,
+    LibrarySignatures,
+};
+use rayon::prelude::*;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of components traversed using [Visitor::traverse_component].
+static TRAVERSED: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of components traversed by all the visitors that have run in this
+/// process. The difference before and after running a pass is the number of
+/// components it visited.
+pub fn traversed_components() -> usize {
+    TRAVERSED.load(Ordering::Relaxed)
+}
 
 /// Trait that describes named things. Calling [`do_pass`](Visitor::do_pass) and [`do_pass_default`](Visitor::do_pass_default).
 /// require this to be implemented.
@@ -21,6 +36,11 @@ pub trait Named {
 
     /// A short description of the pass.
     fn description() -> &'static str;
+
+    /// Options accepted by the pass using `-x <pass>:<key>=<value>`.
+    fn opts() -> Vec<PassOpt> {
+        vec![]
+    }
 }
 
 /// Trait defining method that can be used to construct a Visitor from an
@@ -31,38 +51,27 @@ pub trait Named {
 /// For passes that don't need to use the context, this trait can be automatically
 /// be derived from [Default].
 pub trait ConstructVisitor {
-    fn get_opts(opts: &[&'static str], ctx: &ir::Context) -> Vec<bool>
+    /// Parse the values of the options declared by [Named::opts] from the
+    /// extra options in the context.
+    fn get_opts(ctx: &ir::Context) -> CalyxResult<PassOpts>
     where
         Self: Named,
     {
-        let n = Self::name();
-        let given_opts: HashSet<_> = ctx
-            .extra_opts
-            .iter()
-            .filter_map(|opt| {
-                let mut splits = opt.split(':');
-                if splits.next() == Some(n) {
-                    splits.next()
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        let values = opts.iter().map(|o| given_opts.contains(o)).collect_vec();
+        let opts =
+            PassOpts::parse(Self::name(), &Self::opts(), &ctx.extra_opts)?;
 
         if log::log_enabled!(log::Level::Debug) {
             log::debug!(
                 "Extra options for {}: {}",
                 Self::name(),
-                opts.iter()
-                    .zip(values.iter())
-                    .map(|(o, v)| format!("{o}->{v}"))
+                Self::opts()
+                    .iter()
+                    .map(|o| format!("{}->{}", o.name(), opts[o.name()]))
                     .join(", ")
             );
         }
 
-        values
+        Ok(opts)
     }
 
     /// Construct the visitor using information from the Context
@@ -118,6 +127,7 @@ pub trait Visitor {
     where
         Self: Sized,
     {
+        TRAVERSED.fetch_add(1, Ordering::Relaxed);
         self.start(comp, signatures, components)?
             .and_then(|| {
                 // Create a clone of the reference to the Control
@@ -173,6 +183,7 @@ pub trait Visitor {
             context.components = comps;
         }
 
+        self.finish_context(context)?;
         Ok(())
     }
 
@@ -210,6 +221,12 @@ pub trait Visitor {
         Ok(Action::Continue)
     }
 
+    /// Executed after all the components in the context have been
+    /// traversed.
+    fn finish_context(&mut self, _ctx: &mut Context) -> VisResult {
+        Ok(Action::Continue)
+    }
+
     /// Executed before visiting the children of a [ir::Seq] node.
     fn start_seq(
         &mut self,
@@ -298,6 +315,94 @@ pub trait Visitor {
         Ok(Action::Continue)
     }
 
+    /// Executed before visiting the children of a [ir::Repeat] node.
+    fn start_repeat(
+        &mut self,
+        _s: &mut ir::Repeat,
+        _comp: &mut Component,
+        _sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        Ok(Action::Continue)
+    }
+
+    /// Executed after visiting the children of a [ir::Repeat] node.
+    fn finish_repeat(
+        &mut self,
+        _s: &mut ir::Repeat,
+        _comp: &mut Component,
+        _sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        Ok(Action::Continue)
+    }
+
+    /// Executed before visiting the children of a [ir::StaticSeq] node.
+    fn start_static_seq(
+        &mut self,
+        _s: &mut ir::StaticSeq,
+        _comp: &mut Component,
+        _sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        Ok(Action::Continue)
+    }
+
+    /// Executed after visiting the children of a [ir::StaticSeq] node.
+    fn finish_static_seq(
+        &mut self,
+        _s: &mut ir::StaticSeq,
+        _comp: &mut Component,
+        _sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        Ok(Action::Continue)
+    }
+
+    /// Executed before visiting the children of a [ir::StaticPar] node.
+    fn start_static_par(
+        &mut self,
+        _s: &mut ir::StaticPar,
+        _comp: &mut Component,
+        _sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        Ok(Action::Continue)
+    }
+
+    /// Executed after visiting the children of a [ir::StaticPar] node.
+    fn finish_static_par(
+        &mut self,
+        _s: &mut ir::StaticPar,
+        _comp: &mut Component,
+        _sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        Ok(Action::Continue)
+    }
+
+    /// Executed before visiting the children of a [ir::StaticIf] node.
+    fn start_static_if(
+        &mut self,
+        _s: &mut ir::StaticIf,
+        _comp: &mut Component,
+        _sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        Ok(Action::Continue)
+    }
+
+    /// Executed after visiting the children of a [ir::StaticIf] node.
+    fn finish_static_if(
+        &mut self,
+        _s: &mut ir::StaticIf,
+        _comp: &mut Component,
+        _sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        Ok(Action::Continue)
+    }
+
     /// Executed at an [ir::Enable] node.
     fn enable(
         &mut self,
@@ -332,6 +437,90 @@ pub trait Visitor {
     }
 }
 
+/// A pass that only reads and modifies the component it is visiting. Such
+/// passes implement [Visitor::do_pass] using
+/// [ComponentLocal::do_pass_parallel] to visit the components in parallel.
+///
+/// Each component is converted into a [DetachedComponent] that owns all of
+/// its data and rebuilt on the thread that visits it, so the threads never
+/// share reference-counted pointers. The visitors are moved between threads
+/// as well and therefore have to be [Send].
+/// The `comps` argument of the visitor methods is always empty.
+pub trait ComponentLocal: Visitor + ConstructVisitor + Send + Sized {
+    /// Returns false if the components must be visited in order, for example,
+    /// because the pass prints something.
+    fn visit_in_parallel(&self) -> bool {
+        true
+    }
+
+    /// Fold the state that `visitor` collected while visiting a component
+    /// into this visitor. Called in component order before
+    /// [Visitor::finish_context] runs on this visitor.
+    fn merge(&mut self, visitor: Self);
+
+    /// Visit the components in parallel. Each component is visited by its
+    /// own visitor constructed using [ConstructVisitor::from]. The components
+    /// keep their order and the first error in component order is reported
+    /// so the result does not depend on the scheduling of the threads.
+    fn do_pass_parallel(&mut self, context: &mut Context) -> CalyxResult<()> {
+        if !self.visit_in_parallel()
+            || cfg!(target_family = "wasm")
+            || context.components.len() <= 1
+            || rayon::current_num_threads() <= 1
+        {
+            for comp in &mut context.components {
+                self.traverse_component(comp, &context.lib, &[])?;
+                self.clear_data();
+            }
+            self.finish_context(context)?;
+            return Ok(());
+        }
+
+        let visitors = context
+            .components
+            .iter()
+            .map(|_| Self::from(&*context))
+            .collect::<CalyxResult<Vec<_>>>()?;
+        let signatures = &context.lib;
+        let work = context
+            .components
+            .drain(..)
+            .map(DetachedComponent::new)
+            .zip(visitors)
+            .collect_vec();
+
+        let results = work
+            .into_par_iter()
+            .map(|(comp, mut visitor)| {
+                let mut comp = comp.attach(signatures)?;
+                visitor.traverse_component(&mut comp, signatures, &[])?;
+                Ok((DetachedComponent::new(comp), visitor))
+            })
+            .collect::<Vec<CalyxResult<_>>>();
+
+        // Attach all components before reporting an error so that the
+        // context stays complete.
+        let mut error = None;
+        for res in results {
+            match res {
+                Ok((comp, visitor)) => {
+                    context.components.push(comp.attach(signatures)?);
+                    self.merge(visitor);
+                }
+                Err(err) => {
+                    error.get_or_insert(err);
+                }
+            }
+        }
+        if let Some(err) = error {
+            return Err(err);
+        }
+
+        self.finish_context(context)?;
+        Ok(())
+    }
+}
+
 /// Describes types that can be visited by things implementing [Visitor].
 /// This performs a recursive walk of the tree.
 ///
@@ -388,6 +577,13 @@ impl Visitable for Control {
                 .and_then(|| {
                     visitor.finish_while(ctrl, component, sigs, comps)
                 })?,
+            Control::Repeat(ctrl) => visitor
+                .start_repeat(ctrl, component, sigs, comps)?
+                .and_then(|| ctrl.body.visit(visitor, component, sigs, comps))?
+                .pop()
+                .and_then(|| {
+                    visitor.finish_repeat(ctrl, component, sigs, comps)
+                })?,
             Control::Enable(ctrl) => {
                 visitor.enable(ctrl, component, sigs, comps)?
             }
@@ -397,6 +593,32 @@ impl Visitable for Control {
             Control::Invoke(data) => {
                 visitor.invoke(data, component, sigs, comps)?
             }
+            Control::StaticSeq(ctrl) => visitor
+                .start_static_seq(ctrl, component, sigs, comps)?
+                .and_then(|| ctrl.stmts.visit(visitor, component, sigs, comps))?
+                .pop()
+                .and_then(|| {
+                    visitor.finish_static_seq(ctrl, component, sigs, comps)
+                })?,
+            Control::StaticPar(ctrl) => visitor
+                .start_static_par(ctrl, component, sigs, comps)?
+                .and_then(|| ctrl.stmts.visit(visitor, component, sigs, comps))?
+                .pop()
+                .and_then(|| {
+                    visitor.finish_static_par(ctrl, component, sigs, comps)
+                })?,
+            Control::StaticIf(ctrl) => visitor
+                .start_static_if(ctrl, component, sigs, comps)?
+                .and_then(|| {
+                    ctrl.tbranch.visit(visitor, component, sigs, comps)
+                })?
+                .and_then(|| {
+                    ctrl.fbranch.visit(visitor, component, sigs, comps)
+                })?
+                .pop()
+                .and_then(|| {
+                    visitor.finish_static_if(ctrl, component, sigs, comps)
+                })?,
         };
         Ok(res.apply_change(self))
     }
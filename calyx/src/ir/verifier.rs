@@ -0,0 +1,338 @@
+//! Structural verifier for the IR.
+//!
+//! Passes transform the IR using shared pointers and assume that the
+//! structure they are given is consistent. The verifier re-checks these
+//! invariants and is run by the [PassManager](crate::pass_manager::PassManager)
+//! after every pass when `--verify-each` is enabled.
+use super::{
+    Assignment, Cell, CombGroup, Component, Context, Control, Group, Guard,
+    Port, PortParent, RRC,
+};
+use crate::errors::{CalyxResult, Error};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+/// Check the structural invariants of every component in `ctx`:
+/// 1. Ports used in assignments, guards, and control belong to cells and
+///    groups defined in the component.
+/// 2. Ports are owned by the cell or group their parent pointer refers to and
+///    the parent pointers are not dangling.
+/// 3. Both sides of assignments, comparisons, and invoke ports have the same
+///    width and guards and conditions are 1-bit.
+/// 4. Control does not reference removed groups or cells.
+pub fn verify(ctx: &Context) -> CalyxResult<()> {
+    ctx.components.iter().try_for_each(verify_component)
+}
+
+/// Check the structural invariants of `comp`. See [verify].
+pub(super) fn verify_component(comp: &Component) -> CalyxResult<()> {
+    Verifier::new(comp).verify()
+}
+
+/// Result of a single check. Errors are described without the component name
+/// which is added by [Verifier::verify].
+type Check = Result<(), String>;
+
+struct Verifier<'a> {
+    comp: &'a Component,
+    /// Cells defined in the component including its signature.
+    cells: HashSet<*const RefCell<Cell>>,
+    /// Groups defined in the component.
+    groups: HashSet<*const RefCell<Group>>,
+    /// Combinational groups defined in the component.
+    comb_groups: HashSet<*const RefCell<CombGroup>>,
+}
+
+impl<'a> Verifier<'a> {
+    fn new(comp: &'a Component) -> Self {
+        Self {
+            comp,
+            cells: comp
+                .cells
+                .iter()
+                .chain(std::iter::once(&comp.signature))
+                .map(Rc::as_ptr)
+                .collect(),
+            groups: comp.groups.iter().map(Rc::as_ptr).collect(),
+            comb_groups: comp.comb_groups.iter().map(Rc::as_ptr).collect(),
+        }
+    }
+
+    fn verify(&self) -> CalyxResult<()> {
+        let err = |msg: String| {
+            Error::malformed_structure(format!(
+                "Component `{}': {}",
+                self.comp.name, msg
+            ))
+        };
+
+        for cell in self
+            .comp
+            .cells
+            .iter()
+            .chain(std::iter::once(&self.comp.signature))
+        {
+            self.owned_ports(cell).map_err(err)?;
+        }
+        for group in self.comp.groups.iter() {
+            let group_ref = group.borrow();
+            for hole in &group_ref.holes {
+                let owned = match &hole.borrow().parent {
+                    PortParent::Group(g) => g
+                        .internal
+                        .upgrade()
+                        .map(|g| Rc::ptr_eq(&g, group))
+                        .unwrap_or(false),
+                    PortParent::Cell(_) => false,
+                };
+                if !owned {
+                    return Err(err(format!(
+                        "hole `{}' of group `{}' does not point to its group",
+                        hole.borrow().name,
+                        group_ref.name()
+                    )));
+                }
+            }
+            for assign in &group_ref.assignments {
+                self.assignment(assign)
+                    .map_err(|msg| err(msg).with_pos(&assign.attributes))?;
+            }
+        }
+        for group in self.comp.comb_groups.iter() {
+            for assign in &group.borrow().assignments {
+                self.assignment(assign)
+                    .map_err(|msg| err(msg).with_pos(&assign.attributes))?;
+            }
+        }
+        for assign in &self.comp.continuous_assignments {
+            self.assignment(assign)
+                .map_err(|msg| err(msg).with_pos(&assign.attributes))?;
+        }
+        self.control(&self.comp.control.borrow()).map_err(err)
+    }
+
+    /// Check that the ports of `cell` point back to it.
+    fn owned_ports(&self, cell: &RRC<Cell>) -> Check {
+        for port in cell.borrow().ports() {
+            let owned = match &port.borrow().parent {
+                PortParent::Cell(c) => c
+                    .internal
+                    .upgrade()
+                    .map(|c| Rc::ptr_eq(&c, cell))
+                    .unwrap_or(false),
+                PortParent::Group(_) => false,
+            };
+            if !owned {
+                return Err(format!(
+                    "port `{}' of cell `{}' does not point to its cell",
+                    port.borrow().name,
+                    cell.borrow().name()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that `port` belongs to a cell or group of the component.
+    fn port(&self, port: &RRC<Port>) -> Check {
+        let port_ref = port.borrow();
+        let (parent, owned) = match &port_ref.parent {
+            PortParent::Cell(wrc) => {
+                let cell = wrc.internal.upgrade().ok_or_else(|| {
+                    format!(
+                        "port `{}' has a dangling parent pointer",
+                        port_ref.name
+                    )
+                })?;
+                let name = cell.borrow().name().clone();
+                if !self.cells.contains(&Rc::as_ptr(&cell)) {
+                    return Err(format!(
+                        "port `{}.{}' belongs to a cell that is not defined in the component",
+                        name, port_ref.name
+                    ));
+                }
+                let owned = cell.borrow().find(&port_ref.name);
+                (name, owned)
+            }
+            PortParent::Group(wrc) => {
+                let group = wrc.internal.upgrade().ok_or_else(|| {
+                    format!(
+                        "hole `{}' has a dangling parent pointer",
+                        port_ref.name
+                    )
+                })?;
+                let name = group.borrow().name().clone();
+                if !self.groups.contains(&Rc::as_ptr(&group)) {
+                    return Err(format!(
+                        "hole `{}[{}]' references removed group `{}'",
+                        name, port_ref.name, name
+                    ));
+                }
+                let owned = group.borrow().find(&port_ref.name);
+                (name, owned)
+            }
+        };
+
+        let owned = owned.ok_or_else(|| {
+            format!("port `{}.{}' does not exist", parent, port_ref.name)
+        })?;
+        match port_ref.select {
+            // Bit-selects are not owned by their parent but must read from
+            // one of its ports.
+            Some((msb, lsb)) if lsb <= msb && msb < owned.borrow().width => {
+                Ok(())
+            }
+            Some((msb, lsb)) => Err(format!(
+                "bit-select `{}.{}[{}:{}]' is out of range",
+                parent, port_ref.name, msb, lsb
+            )),
+            None if Rc::ptr_eq(&owned, port) => Ok(()),
+            None => Err(format!(
+                "port `{}.{}' is not owned by its parent",
+                parent, port_ref.name
+            )),
+        }
+    }
+
+    /// Check that `port` is valid and 1-bit.
+    fn cond_port(&self, port: &RRC<Port>) -> Check {
+        self.port(port)?;
+        let port = port.borrow();
+        if port.width != 1 {
+            return Err(format!(
+                "condition `{}.{}' has width {} instead of 1",
+                port.get_parent_name(),
+                port.name,
+                port.width
+            ));
+        }
+        Ok(())
+    }
+
+    fn same_width(&self, l: &RRC<Port>, r: &RRC<Port>) -> Check {
+        let (l, r) = (l.borrow(), r.borrow());
+        if l.width != r.width {
+            return Err(format!(
+                "`{}.{}' has width {} but `{}.{}' has width {}",
+                l.get_parent_name(),
+                l.name,
+                l.width,
+                r.get_parent_name(),
+                r.name,
+                r.width
+            ));
+        }
+        Ok(())
+    }
+
+    fn assignment(&self, assign: &Assignment) -> Check {
+        self.port(&assign.dst)?;
+        self.port(&assign.src)?;
+        self.same_width(&assign.dst, &assign.src)?;
+        self.guard(&assign.guard)
+    }
+
+    fn guard(&self, guard: &Guard) -> Check {
+        match guard {
+            Guard::Or(l, r) | Guard::And(l, r) => {
+                self.guard(l)?;
+                self.guard(r)
+            }
+            Guard::Not(g) => self.guard(g),
+            Guard::True => Ok(()),
+            Guard::CompOp(_, l, r) => {
+                self.port(l)?;
+                self.port(r)?;
+                self.same_width(l, r)
+            }
+            Guard::Port(p) => self.cond_port(p),
+        }
+    }
+
+    fn group(&self, group: &RRC<Group>) -> Check {
+        if self.groups.contains(&Rc::as_ptr(group)) {
+            Ok(())
+        } else {
+            Err(format!(
+                "control references removed group `{}'",
+                group.borrow().name()
+            ))
+        }
+    }
+
+    fn comb_group(&self, group: &Option<RRC<CombGroup>>) -> Check {
+        match group {
+            Some(g) if !self.comb_groups.contains(&Rc::as_ptr(g)) => {
+                Err(format!(
+                    "control references removed combinational group `{}'",
+                    g.borrow().name()
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn cell(&self, cell: &RRC<Cell>) -> Check {
+        if self.cells.contains(&Rc::as_ptr(cell)) {
+            Ok(())
+        } else {
+            Err(format!(
+                "control references removed cell `{}'",
+                cell.borrow().name()
+            ))
+        }
+    }
+
+    fn control(&self, con: &Control) -> Check {
+        match con {
+            Control::Seq(s) => s.stmts.iter().try_for_each(|c| self.control(c)),
+            Control::Par(p) => p.stmts.iter().try_for_each(|c| self.control(c)),
+            Control::StaticSeq(s) => {
+                s.stmts.iter().try_for_each(|c| self.control(c))
+            }
+            Control::StaticPar(p) => {
+                p.stmts.iter().try_for_each(|c| self.control(c))
+            }
+            Control::If(i) => {
+                self.cond_port(&i.port)?;
+                self.comb_group(&i.cond)?;
+                self.control(&i.tbranch)?;
+                self.control(&i.fbranch)
+            }
+            Control::StaticIf(i) => {
+                self.cond_port(&i.port)?;
+                self.control(&i.tbranch)?;
+                self.control(&i.fbranch)
+            }
+            Control::While(w) => {
+                self.cond_port(&w.port)?;
+                self.comb_group(&w.cond)?;
+                self.control(&w.body)
+            }
+            Control::Repeat(r) => self.control(&r.body),
+            Control::Enable(e) => self.group(&e.group),
+            Control::Invoke(inv) => {
+                self.cell(&inv.comp)?;
+                self.comb_group(&inv.comb_group)?;
+                for (_, cell) in &inv.ref_cells {
+                    self.cell(cell)?;
+                }
+                for (name, port) in inv.inputs.iter().chain(&inv.outputs) {
+                    self.port(port)?;
+                    let comp_port =
+                        inv.comp.borrow().find(name).ok_or_else(|| {
+                            format!(
+                                "invoke of `{}' binds unknown port `{}'",
+                                inv.comp.borrow().name(),
+                                name
+                            )
+                        })?;
+                    self.same_width(&comp_port, port)?;
+                }
+                Ok(())
+            }
+            Control::Empty(_) => Ok(()),
+        }
+    }
+}
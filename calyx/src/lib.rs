@@ -32,5 +32,7 @@ pub mod errors;
 pub mod frontend;
 pub mod ir;
 pub mod pass_manager;
+pub mod pass_profile;
 pub mod passes;
+pub mod pipeline;
 pub mod utils;
@@ -4,21 +4,71 @@ use crate::{
     errors::{CalyxResult, Error},
     ir,
     ir::traversal,
+    pass_profile::{IrSize, PassProfile},
 };
+use itertools::Itertools;
+use similar::TextDiff;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
-use std::time::Instant;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 /// Top-level type for all passes that transform an [ir::Context]
 pub type PassClosure = Box<dyn Fn(&mut ir::Context) -> CalyxResult<()>>;
 
+/// Configuration for dumping the IR before and after passes.
+pub struct IrDump {
+    /// Directory the numbered dumps are written to.
+    pub dir: PathBuf,
+    /// Passes before which the IR is dumped. `all` matches every pass.
+    pub before: Vec<String>,
+    /// Passes after which the IR is dumped. `all` matches every pass.
+    pub after: Vec<String>,
+    /// Print a unified diff of each changed component against the previous
+    /// dump to stderr.
+    pub diff: bool,
+}
+
+/// Output of the last dump. Dumps are numbered across calls to
+/// [PassManager::execute_plan].
+#[derive(Default)]
+struct DumpState {
+    /// Number of dumps written so far.
+    count: usize,
+    /// Name of the last stage.
+    stage: String,
+    /// Printed components from the last stage.
+    components: Vec<(String, String)>,
+}
+
 /// Structure that tracks all registered passes for the compiler.
 #[derive(Default)]
 pub struct PassManager {
     /// All registered passes
     passes: HashMap<String, PassClosure>,
 
+    /// Options accepted by each registered pass.
+    pass_opts: HashMap<String, Vec<traversal::PassOpt>>,
+
     /// Tracks alias for groups of passes that run together.
     aliases: HashMap<String, Vec<String>>,
+
+    /// Aliases that are repeated until the IR stops changing along with the
+    /// maximum number of iterations.
+    fixed_points: HashMap<String, (Vec<String>, usize)>,
+
+    /// Configuration for IR dumps and the state of the last dump.
+    dump: Option<(IrDump, RefCell<DumpState>)>,
+
+    /// Run the structural verifier after every pass.
+    verify_each: bool,
+
+    /// Profiles of the passes executed so far when profiling is enabled.
+    profile: Option<RefCell<Vec<PassProfile>>>,
 }
 
 impl PassManager {
@@ -46,38 +96,189 @@ impl PassManager {
             Pass::do_pass_default(ir)?;
             Ok(())
         });
-        self.passes.insert(name, pass_closure);
+        self.passes.insert(name.clone(), pass_closure);
+        self.pass_opts.insert(name, Pass::opts());
         Ok(())
     }
 
     /// Adds a new alias for groups of passes. An alias is a list of strings
     /// that represent valid pass names OR an alias.
     /// The passes and aliases are executed in the order of specification.
-    pub fn add_alias(
+    /// Returns an error pointing to the position of the offending name if the
+    /// alias is already defined or uses an unknown pass or alias.
+    pub fn add_alias<N, S>(
         &mut self,
-        name: String,
-        passes: Vec<String>,
-    ) -> CalyxResult<()> {
-        if self.aliases.contains_key(&name) {
-            return Err(Error::misc(format!(
-                "Alias with name '{}'  already registered.",
-                name
-            )));
+        name: N,
+        passes: Vec<S>,
+    ) -> CalyxResult<()>
+    where
+        N: Into<ir::Id>,
+        S: Into<ir::Id>,
+    {
+        let name = name.into();
+        if self.is_defined(name.as_ref()) {
+            return Err(Error::already_bound(
+                name,
+                "pass or alias".to_string(),
+            ));
         }
         // Expand any aliases used in defining this alias.
-        let all_passes = passes
-            .into_iter()
-            .flat_map(|pass| {
-                if self.aliases.contains_key(&pass) {
-                    self.aliases[&pass].clone()
-                } else if self.passes.contains_key(&pass) {
-                    vec![pass]
-                } else {
-                    panic!("No pass or alias named: {}", pass)
-                }
+        let mut all_passes = vec![];
+        for pass in passes {
+            let pass = pass.into();
+            if let Some(passes) = self.aliases.get(pass.as_ref()) {
+                all_passes.extend(passes.iter().cloned())
+            } else if self.passes.contains_key(pass.as_ref())
+                || self.fixed_points.contains_key(pass.as_ref())
+            {
+                all_passes.push(pass.to_string())
+            } else {
+                return Err(Error::undefined(
+                    pass,
+                    "pass or alias".to_string(),
+                ));
+            }
+        }
+        self.aliases.insert(name.to_string(), all_passes);
+        Ok(())
+    }
+
+    /// Adds an alias whose passes are repeated until they stop changing the
+    /// program or `max_iterations` iterations have run.
+    /// Uses of the alias in other aliases run the whole loop.
+    pub fn add_fixed_point_alias<N, S>(
+        &mut self,
+        name: N,
+        passes: Vec<S>,
+        max_iterations: usize,
+    ) -> CalyxResult<()>
+    where
+        N: Into<ir::Id>,
+        S: Into<ir::Id>,
+    {
+        let name = name.into();
+        if max_iterations == 0 {
+            return Err(Error::misc(format!(
+                "Fixed-point alias `{name}' must run at least one iteration"
+            ))
+            .with_pos(&name));
+        }
+        let key = name.to_string();
+        self.add_alias(name, passes)?;
+        let passes = self.aliases.remove(&key).unwrap();
+        self.fixed_points.insert(key, (passes, max_iterations));
+        Ok(())
+    }
+
+    /// Returns true if `name` is a registered pass or alias.
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.passes.contains_key(name)
+            || self.aliases.contains_key(name)
+            || self.fixed_points.contains_key(name)
+    }
+
+    /// Dump the IR into `dump.dir` before and after the passes selected by
+    /// `dump`. Returns an error if `dump` mentions an unknown pass.
+    pub fn dump_ir(&mut self, dump: IrDump) -> CalyxResult<()> {
+        for pass in dump.before.iter().chain(&dump.after) {
+            if pass != "all" && !self.passes.contains_key(pass) {
+                return Err(Error::misc(format!(
+                    "Unknown pass `{pass}' for IR dump. Run compiler with --list-passes to view registered passes."
+                )));
+            }
+        }
+        self.dump = Some((dump, RefCell::default()));
+        Ok(())
+    }
+
+    /// Run the structural verifier ([ir::verify]) after every pass and report
+    /// the pass that broke an invariant.
+    pub fn verify_each(&mut self, enable: bool) {
+        self.verify_each = enable;
+    }
+
+    /// Record the wall time and the change in IR size of every pass executed
+    /// from now on. The profiles are returned by [PassManager::profile].
+    pub fn profile_passes(&mut self, enable: bool) {
+        self.profile = enable.then(RefCell::default);
+    }
+
+    /// Profiles of the passes executed since profiling was enabled, in order
+    /// of execution.
+    pub fn profile(&self) -> Vec<PassProfile> {
+        self.profile
+            .as_ref()
+            .map(|p| p.borrow().clone())
+            .unwrap_or_default()
+    }
+
+    /// Write the IR of `ctx` into the next numbered file of the dump
+    /// directory and print the changes since the previous dump if requested.
+    fn dump_stage(
+        dump: &IrDump,
+        state: &RefCell<DumpState>,
+        ctx: &ir::Context,
+        stage: String,
+    ) -> CalyxResult<()> {
+        let components = ctx
+            .components
+            .iter()
+            .map(|comp| {
+                let mut buf = Vec::new();
+                ir::Printer::write_component(comp, &mut buf)?;
+                Ok((
+                    comp.name.to_string(),
+                    String::from_utf8(buf)
+                        .expect("Printer produced invalid UTF-8"),
+                ))
             })
-            .collect();
-        self.aliases.insert(name, all_passes);
+            .collect::<CalyxResult<Vec<_>>>()?;
+
+        let mut state = state.borrow_mut();
+        fs::create_dir_all(&dump.dir)?;
+        let path = dump.dir.join(format!("{:02}-{}.futil", state.count, stage));
+        fs::write(
+            &path,
+            components
+                .iter()
+                .map(|(_, text)| format!("{text}\n"))
+                .collect::<String>(),
+        )?;
+
+        if dump.diff && state.count > 0 {
+            let find = |comps: &[(String, String)], name: &str| {
+                comps
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, text)| text.clone())
+                    .unwrap_or_default()
+            };
+            // Components in the order of the new stage followed by removed
+            // components.
+            let names = components
+                .iter()
+                .chain(&state.components)
+                .map(|(name, _)| name.as_str())
+                .unique();
+            for name in names {
+                let old = find(&state.components, name);
+                let new = find(&components, name);
+                if old != new {
+                    let old_header = format!("{} ({})", name, state.stage);
+                    let new_header = format!("{} ({})", name, stage);
+                    eprint!(
+                        "{}",
+                        TextDiff::from_lines(&old, &new)
+                            .unified_diff()
+                            .header(&old_header, &new_header)
+                    );
+                }
+            }
+        }
+
+        state.count += 1;
+        state.stage = stage;
+        state.components = components;
         Ok(())
     }
 
@@ -93,10 +294,26 @@ impl PassManager {
         pass_names.iter().for_each(|pass| {
             ret.push_str(&format!("- {}", pass));
             ret.push('\n');
+            for opt in &self.pass_opts[*pass] {
+                ret.push_str(&format!(
+                    "  * {}: {} (default: {})\n",
+                    opt.name(),
+                    opt.description(),
+                    opt.default()
+                ));
+            }
         });
 
         // Push all aliases
-        let mut aliases = self.aliases.iter().collect::<Vec<_>>();
+        let mut aliases = self
+            .aliases
+            .iter()
+            .chain(
+                self.fixed_points
+                    .iter()
+                    .map(|(name, (passes, _))| (name, passes)),
+            )
+            .collect::<Vec<_>>();
         aliases.sort_by(|kv1, kv2| kv1.0.cmp(kv2.0));
         ret.push_str("\nAliases:\n");
         aliases.iter().for_each(|(alias, passes)| {
@@ -105,7 +322,13 @@ impl PassManager {
                 .map(|p| p.to_string())
                 .collect::<Vec<String>>()
                 .join(", ");
-            ret.push_str(&format!("- {}: {}", alias, pass_str));
+            match self.fixed_points.get(*alias) {
+                Some((_, max)) => ret.push_str(&format!(
+                    "- {} (until fixed point, at most {} iterations): {}",
+                    alias, max, pass_str
+                )),
+                None => ret.push_str(&format!("- {}: {}", alias, pass_str)),
+            }
             ret.push('\n');
         });
         ret
@@ -140,7 +363,9 @@ impl PassManager {
 
         // Validate that names of passes in incl and excl sets are known
         passes.iter().chain(excl_set.iter()).try_for_each(|pass| {
-            if !self.passes.contains_key(pass) {
+            if !self.passes.contains_key(pass)
+                && !self.fixed_points.contains_key(pass)
+            {
                 Err(Error::misc(format!(
                     "Unknown pass: {pass}. Run compiler with --list-passes to view registered passes."
                 )))
@@ -152,6 +377,32 @@ impl PassManager {
         Ok((passes, excl_set))
     }
 
+    /// Validate the extra options of the form `<pass>:<key>=<value>` before
+    /// running any passes.
+    pub(crate) fn validate_opts(
+        &self,
+        extra_opts: &[String],
+    ) -> CalyxResult<()> {
+        for opt in extra_opts {
+            let pass = opt.split(':').next().unwrap_or_default();
+            match self.pass_opts.get(pass) {
+                Some(opts) => {
+                    traversal::PassOpts::parse(
+                        pass,
+                        opts,
+                        std::slice::from_ref(opt),
+                    )?;
+                }
+                None => {
+                    return Err(Error::misc(format!(
+                        "Unknown pass `{pass}' in option `{opt}'. Options must be of the form <pass>:<key>=<value>."
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Executes a given "plan" constructed using the incl and excl lists.
     pub fn execute_plan(
         &self,
@@ -160,24 +411,114 @@ impl PassManager {
         excl: &[String],
     ) -> CalyxResult<()> {
         let (passes, excl_set) = self.create_plan(incl, excl)?;
+        self.validate_opts(&ctx.extra_opts)?;
+
+        // Problems in the input program should not be blamed on the first
+        // pass.
+        if self.verify_each {
+            ir::verify(ctx).map_err(|err| {
+                err.with_post_msg(Some(
+                    "The input program is malformed.".to_string(),
+                ))
+            })?;
+        }
+
+        // The input program is the baseline for the first dump.
+        if let Some((dump, state)) = &self.dump {
+            if state.borrow().count == 0 {
+                Self::dump_stage(dump, state, ctx, "input".to_string())?;
+            }
+        }
 
         for name in passes {
-            // Pass is known to exist because create_plan validates the
-            // names of passes.
-            let pass = &self.passes[&name];
-
-            // Conditional compilation for WASM target because Instant::now
-            // is not supported.
-            if cfg!(not(target_family = "wasm")) {
-                if !excl_set.contains(&name) {
-                    let start = Instant::now();
-                    pass(ctx)?;
-                    log::info!("{name}: {}ms", start.elapsed().as_millis());
-                } else {
-                    log::info!("{name}: Ignored")
+            self.run(ctx, &name, &excl_set)?;
+        }
+
+        Ok(())
+    }
+
+    /// Run the pass or fixed-point alias `name` unless it is excluded.
+    fn run(
+        &self,
+        ctx: &mut ir::Context,
+        name: &str,
+        excl_set: &HashSet<String>,
+    ) -> CalyxResult<()> {
+        if excl_set.contains(name) {
+            log::info!("{name}: Ignored");
+            return Ok(());
+        }
+
+        if let Some((passes, max_iterations)) = self.fixed_points.get(name) {
+            let mut hash = fingerprint(ctx);
+            for iteration in 1..=*max_iterations {
+                for pass in passes {
+                    self.run(ctx, pass, excl_set)?;
                 }
-            } else if !excl_set.contains(&name) {
-                pass(ctx)?;
+                let new_hash = fingerprint(ctx);
+                if new_hash == hash {
+                    log::info!(
+                        "{name}: Fixed point reached after {iteration} iteration(s)"
+                    );
+                    return Ok(());
+                }
+                hash = new_hash;
+            }
+            log::info!(
+                "{name}: Stopped after {max_iterations} iteration(s) without reaching a fixed point"
+            );
+            return Ok(());
+        }
+
+        // Pass is known to exist because create_plan and add_alias validate
+        // the names of passes.
+        let pass = &self.passes[name];
+        let should_dump =
+            |list: &[String]| list.iter().any(|p| p == "all" || p == name);
+
+        if let Some((dump, state)) = &self.dump {
+            if should_dump(&dump.before) {
+                Self::dump_stage(dump, state, ctx, format!("before-{name}"))?;
+            }
+        }
+
+        let before = self.profile.as_ref().map(|_| IrSize::of(ctx));
+        let traversed = traversal::traversed_components();
+
+        // Conditional compilation for WASM target because Instant::now
+        // is not supported.
+        let elapsed = if cfg!(not(target_family = "wasm")) {
+            let start = Instant::now();
+            pass(ctx)?;
+            let elapsed = start.elapsed();
+            log::info!("{name}: {}ms", elapsed.as_millis());
+            elapsed
+        } else {
+            pass(ctx)?;
+            Duration::ZERO
+        };
+
+        if let (Some(profile), Some(before)) = (&self.profile, before) {
+            profile.borrow_mut().push(PassProfile {
+                pass: name.to_string(),
+                time_ms: elapsed.as_secs_f64() * 1000.0,
+                components: traversal::traversed_components() - traversed,
+                before,
+                after: IrSize::of(ctx),
+            });
+        }
+
+        if self.verify_each {
+            ir::verify(ctx).map_err(|err| {
+                err.with_post_msg(Some(format!(
+                    "The IR is malformed after running pass `{name}'."
+                )))
+            })?;
+        }
+
+        if let Some((dump, state)) = &self.dump {
+            if should_dump(&dump.after) {
+                Self::dump_stage(dump, state, ctx, format!("after-{name}"))?;
             }
         }
 
@@ -185,6 +526,190 @@ impl PassManager {
     }
 }
 
+/// Hash of the structure of `ctx`. Used to detect whether an iteration of a
+/// fixed-point alias changed the program.
+fn fingerprint(ctx: &ir::Context) -> u64 {
+    let mut state = DefaultHasher::new();
+    for comp in &ctx.components {
+        hash_component(comp, &mut state);
+    }
+    state.finish()
+}
+
+fn hash_component(comp: &ir::Component, state: &mut DefaultHasher) {
+    comp.name.hash(state);
+    hash_attributes(&comp.attributes, state);
+    hash_cell(&comp.signature.borrow(), state);
+    comp.cells.len().hash(state);
+    for cell in comp.cells.iter() {
+        hash_cell(&cell.borrow(), state);
+    }
+    comp.groups.len().hash(state);
+    for group in comp.groups.iter() {
+        let group = group.borrow();
+        group.name().hash(state);
+        hash_attributes(&group.attributes, state);
+        hash_assignments(&group.assignments, state);
+    }
+    comp.comb_groups.len().hash(state);
+    for group in comp.comb_groups.iter() {
+        let group = group.borrow();
+        group.name().hash(state);
+        hash_attributes(&group.attributes, state);
+        hash_assignments(&group.assignments, state);
+    }
+    hash_assignments(&comp.continuous_assignments, state);
+    hash_control(&comp.control.borrow(), state);
+}
+
+fn hash_attributes(attrs: &ir::Attributes, state: &mut DefaultHasher) {
+    for (key, val) in attrs.iter() {
+        key.hash(state);
+        val.hash(state);
+    }
+    // Separates the attributes from the next item.
+    0xffu8.hash(state);
+}
+
+fn hash_cell(cell: &ir::Cell, state: &mut DefaultHasher) {
+    cell.name().hash(state);
+    cell.prototype.hash(state);
+    cell.reference.hash(state);
+    hash_attributes(&cell.attributes, state);
+    cell.ports.len().hash(state);
+    for port in &cell.ports {
+        let port = port.borrow();
+        port.name.hash(state);
+        port.width.hash(state);
+        port.direction.hash(state);
+        hash_attributes(&port.attributes, state);
+    }
+}
+
+fn hash_port(port: &ir::Port, state: &mut DefaultHasher) {
+    port.canonical().hash(state);
+    port.select.hash(state);
+}
+
+fn hash_assignments(assigns: &[ir::Assignment], state: &mut DefaultHasher) {
+    assigns.len().hash(state);
+    for assign in assigns {
+        hash_port(&assign.dst.borrow(), state);
+        hash_port(&assign.src.borrow(), state);
+        hash_guard(&assign.guard, state);
+        hash_attributes(&assign.attributes, state);
+    }
+}
+
+fn hash_guard(guard: &ir::Guard, state: &mut DefaultHasher) {
+    mem::discriminant(guard).hash(state);
+    match guard {
+        ir::Guard::Or(l, r) | ir::Guard::And(l, r) => {
+            hash_guard(l, state);
+            hash_guard(r, state);
+        }
+        ir::Guard::Not(inner) => hash_guard(inner, state),
+        ir::Guard::CompOp(op, l, r) => {
+            mem::discriminant(op).hash(state);
+            hash_port(&l.borrow(), state);
+            hash_port(&r.borrow(), state);
+        }
+        ir::Guard::Port(port) => hash_port(&port.borrow(), state),
+        ir::Guard::True => (),
+    }
+}
+
+fn hash_control(con: &ir::Control, state: &mut DefaultHasher) {
+    mem::discriminant(con).hash(state);
+    match con {
+        ir::Control::Seq(ir::Seq { stmts, attributes })
+        | ir::Control::Par(ir::Par { stmts, attributes })
+        | ir::Control::StaticSeq(ir::StaticSeq { stmts, attributes })
+        | ir::Control::StaticPar(ir::StaticPar { stmts, attributes }) => {
+            hash_attributes(attributes, state);
+            stmts.len().hash(state);
+            for stmt in stmts {
+                hash_control(stmt, state);
+            }
+        }
+        ir::Control::If(ir::If {
+            port,
+            cond,
+            tbranch,
+            fbranch,
+            attributes,
+        }) => {
+            hash_attributes(attributes, state);
+            hash_port(&port.borrow(), state);
+            cond.as_ref().map(|c| c.borrow().name().clone()).hash(state);
+            hash_control(tbranch, state);
+            hash_control(fbranch, state);
+        }
+        ir::Control::StaticIf(ir::StaticIf {
+            port,
+            tbranch,
+            fbranch,
+            attributes,
+        }) => {
+            hash_attributes(attributes, state);
+            hash_port(&port.borrow(), state);
+            hash_control(tbranch, state);
+            hash_control(fbranch, state);
+        }
+        ir::Control::While(ir::While {
+            port,
+            cond,
+            body,
+            attributes,
+        }) => {
+            hash_attributes(attributes, state);
+            hash_port(&port.borrow(), state);
+            cond.as_ref().map(|c| c.borrow().name().clone()).hash(state);
+            hash_control(body, state);
+        }
+        ir::Control::Repeat(ir::Repeat {
+            num_repeats,
+            body,
+            attributes,
+        }) => {
+            hash_attributes(attributes, state);
+            num_repeats.hash(state);
+            hash_control(body, state);
+        }
+        ir::Control::Enable(ir::Enable { group, attributes }) => {
+            hash_attributes(attributes, state);
+            group.borrow().name().hash(state);
+        }
+        ir::Control::Invoke(ir::Invoke {
+            comp,
+            ref_cells,
+            inputs,
+            outputs,
+            attributes,
+            comb_group,
+        }) => {
+            hash_attributes(attributes, state);
+            comp.borrow().name().hash(state);
+            ref_cells.len().hash(state);
+            for (name, cell) in ref_cells {
+                name.hash(state);
+                cell.borrow().name().hash(state);
+            }
+            inputs.len().hash(state);
+            outputs.len().hash(state);
+            for (name, port) in inputs.iter().chain(outputs) {
+                name.hash(state);
+                hash_port(&port.borrow(), state);
+            }
+            comb_group
+                .as_ref()
+                .map(|c| c.borrow().name().clone())
+                .hash(state);
+        }
+        ir::Control::Empty(_) => (),
+    }
+}
+
 /// Simple macro to register an alias with a pass manager.
 ///
 /// ## Example
@@ -192,6 +717,14 @@ impl PassManager {
 /// let pm = PassManager::default();
 /// // Register passes WellFormed, Papercut, and Canonicalize.
 /// register_alias!(pm, "validate", [WellFormed, Papercut, Canonicalize]);
+/// // Repeat DeadGroupRemoval and DeadCellRemoval until they stop changing
+/// // the program, at most 4 times.
+/// register_alias!(
+///     pm,
+///     "cleanup",
+///     [DeadGroupRemoval, DeadCellRemoval],
+///     max_iterations = 4
+/// );
 /// ```
 #[macro_export]
 macro_rules! register_alias {
@@ -204,8 +737,14 @@ macro_rules! register_alias {
     };
 
     ($manager:expr, $alias:literal, [ $($pass:tt),* $(,)? ]) => {
-        $manager.add_alias($alias.to_string(), vec![
+        $manager.add_alias($alias.to_string(), Vec::<String>::from([
+            $(register_alias!(@unwrap_name $pass)),*
+        ]))?;
+    };
+
+    ($manager:expr, $alias:literal, [ $($pass:tt),* $(,)? ], max_iterations = $max:expr) => {
+        $manager.add_fixed_point_alias($alias.to_string(), Vec::<String>::from([
             $(register_alias!(@unwrap_name $pass)),*
-        ])?;
+        ]), $max)?;
     };
 }
@@ -0,0 +1,179 @@
+//! Profiles of pass executions collected by the
+//! [PassManager](crate::pass_manager::PassManager) when profiling is enabled.
+use crate::ir;
+use serde::Serialize;
+
+/// Size of the IR of a program.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct IrSize {
+    /// Number of cells in all components.
+    pub cells: usize,
+    /// Number of groups and combinational groups in all components.
+    pub groups: usize,
+    /// Number of assignments in groups, combinational groups, and continuous
+    /// assignments of all components.
+    pub assignments: usize,
+}
+
+impl IrSize {
+    /// Compute the size of the IR of `ctx`.
+    pub fn of(ctx: &ir::Context) -> Self {
+        let mut size = IrSize::default();
+        for comp in &ctx.components {
+            size.cells += comp.cells.len();
+            size.groups += comp.groups.len() + comp.comb_groups.len();
+            size.assignments += comp.continuous_assignments.len()
+                + comp
+                    .groups
+                    .iter()
+                    .map(|g| g.borrow().assignments.len())
+                    .sum::<usize>()
+                + comp
+                    .comb_groups
+                    .iter()
+                    .map(|g| g.borrow().assignments.len())
+                    .sum::<usize>();
+        }
+        size
+    }
+}
+
+/// Profile of a single execution of a pass.
+#[derive(Clone, Debug, Serialize)]
+pub struct PassProfile {
+    /// Name of the pass.
+    pub pass: String,
+    /// Wall time of the pass in milliseconds.
+    pub time_ms: f64,
+    /// Number of components visited by the pass.
+    pub components: usize,
+    /// Size of the IR before the pass.
+    pub before: IrSize,
+    /// Size of the IR after the pass.
+    pub after: IrSize,
+}
+
+impl PassProfile {
+    /// Format `profiles` as a table sorted by decreasing wall time.
+    pub fn table(profiles: &[PassProfile]) -> String {
+        let mut sorted = profiles.iter().collect::<Vec<_>>();
+        sorted.sort_by(|a, b| b.time_ms.total_cmp(&a.time_ms));
+
+        let change = |before: usize, after: usize| {
+            if before == after {
+                before.to_string()
+            } else {
+                format!("{} -> {}", before, after)
+            }
+        };
+        let rows = sorted
+            .iter()
+            .map(|p| {
+                [
+                    p.pass.clone(),
+                    format!("{:.3}", p.time_ms),
+                    p.components.to_string(),
+                    change(p.before.cells, p.after.cells),
+                    change(p.before.groups, p.after.groups),
+                    change(p.before.assignments, p.after.assignments),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let header = [
+            "pass",
+            "time (ms)",
+            "components",
+            "cells",
+            "groups",
+            "assignments",
+        ]
+        .map(String::from);
+
+        let widths = (0..header.len())
+            .map(|col| {
+                rows.iter()
+                    .chain(std::iter::once(&header))
+                    .map(|row| row[col].len())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+
+        let mut out = String::new();
+        for row in std::iter::once(&header).chain(&rows) {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(col, (cell, width))| {
+                    // Left-align the pass names and right-align the numbers.
+                    if col == 0 {
+                        format!("{:<width$}", cell, width = width)
+                    } else {
+                        format!("{:>width$}", cell, width = width)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("  ");
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        // Summing an empty iterator of floats gives -0.0.
+        let total = profiles.iter().fold(0.0, |total, p| total + p.time_ms);
+        out.push_str(&format!("total: {:.3}ms\n", total));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(
+        pass: &str,
+        time_ms: f64,
+        before: usize,
+        after: usize,
+    ) -> PassProfile {
+        let size = |n| IrSize {
+            cells: n,
+            groups: 2,
+            assignments: 10 * n,
+        };
+        PassProfile {
+            pass: pass.to_string(),
+            time_ms,
+            components: 3,
+            before: size(before),
+            after: size(after),
+        }
+    }
+
+    #[test]
+    fn table_sorted_by_time() {
+        let profiles = vec![
+            profile("canonicalize", 1.5, 4, 4),
+            profile("tdcc", 12.25, 4, 7),
+        ];
+        assert_eq!(
+            PassProfile::table(&profiles),
+            "\
+pass          time (ms)  components   cells  groups  assignments
+tdcc             12.250           3  4 -> 7       2     40 -> 70
+canonicalize      1.500           3       4       2           40
+total: 13.750ms
+"
+        );
+    }
+
+    #[test]
+    fn table_without_passes() {
+        assert_eq!(
+            PassProfile::table(&[]),
+            "\
+pass  time (ms)  components  cells  groups  assignments
+total: 0.000ms
+"
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::errors::Error;
+use crate::errors::{Error, WithPos};
 use crate::ir::traversal::{Action, Named, VisResult, Visitor};
 use crate::ir::{self, Attributes, LibrarySignatures};
 use crate::structure;
@@ -27,6 +27,7 @@ impl Visitor for CompileInvoke {
         _comps: &[ir::Component],
     ) -> VisResult {
         let mut builder = ir::Builder::new(comp, ctx);
+        builder.set_span(s.attributes.copy_span());
 
         let invoke_group = builder.add_group("invoke");
 
@@ -72,15 +73,16 @@ impl Visitor for CompileInvoke {
 
         // Copy "static" annotation from the `invoke` statement if present
         if let Some(time) = s.attributes.get("static") {
-            invoke_group.borrow_mut().attributes.insert("static", *time);
+            invoke_group.borrow_mut().attributes.insert("static", time);
         }
 
         let mut en = ir::Enable {
             group: invoke_group,
             attributes: Attributes::default(),
         };
+        en.attributes.set_span(s.attributes.copy_span());
         if let Some(time) = s.attributes.get("static") {
-            en.attributes.insert("static", *time);
+            en.attributes.insert("static", time);
         }
 
         Ok(Action::Change(ir::Control::Enable(en)))
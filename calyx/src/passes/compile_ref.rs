@@ -0,0 +1,239 @@
+use crate::errors::{CalyxResult, Error};
+use crate::ir::traversal::{
+    Action, ConstructVisitor, Named, VisResult, Visitor,
+};
+use crate::ir::{self, CloneName, LibrarySignatures, WRC};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Ports added to the signature of a component for its `ref` cells.
+/// Each entry contains the canonical name of the port on the `ref` cell, the
+/// name of the new signature port, its width, and its direction on the
+/// signature cell.
+type RefPorts = Vec<(ir::Canonical, ir::Id, u64, ir::Direction)>;
+
+/// Compiles away `ref` cells by exposing their ports through the signature of
+/// the component and connecting the cells bound in each `invoke` to these
+/// ports.
+///
+/// For example:
+/// ```calyx
+/// component incr() -> () {
+///   cells { ref r = std_reg(32); add = std_add(32); }
+///   wires {
+///     group upd {
+///       add.left = r.out; add.right = 32'd1;
+///       r.in = add.out; r.write_en = 1'd1; upd[done] = r.done;
+///     }
+///   }
+///   control { upd; }
+/// }
+/// component main() -> () {
+///   cells { x = std_reg(32); f = incr(); }
+///   wires {}
+///   control { invoke f[r=x]()(); }
+/// }
+/// ```
+/// is transformed into:
+/// ```calyx
+/// component incr(r_out: 32, r_done: 1) -> (r_in: 32, r_write_en: 1) {
+///   cells { add = std_add(32); }
+///   wires {
+///     group upd {
+///       add.left = r_out; add.right = 32'd1;
+///       r_in = add.out; r_write_en = 1'd1; upd[done] = r_done;
+///     }
+///   }
+///   control { upd; }
+/// }
+/// component main() -> () {
+///   cells { x = std_reg(32); f = incr(); }
+///   wires {}
+///   control {
+///     invoke f(r_out = x.out, r_done = x.done)(r_in = x.in, r_write_en = x.write_en);
+///   }
+/// }
+/// ```
+///
+/// The `@clk` and `@reset` ports of `ref` cells are not exposed since the
+/// bound cell is already connected to them in the invoking component.
+pub struct CompileRef {
+    /// Ports added to the signature of each component with `ref` cells.
+    ref_ports: HashMap<ir::Id, RefPorts>,
+}
+
+impl ConstructVisitor for CompileRef {
+    fn from(_ctx: &ir::Context) -> CalyxResult<Self>
+    where
+        Self: Sized,
+    {
+        Ok(CompileRef {
+            ref_ports: HashMap::new(),
+        })
+    }
+
+    fn clear_data(&mut self) {
+        // The added ports are shared between components
+    }
+}
+
+impl Named for CompileRef {
+    fn name() -> &'static str {
+        "compile-ref"
+    }
+
+    fn description() -> &'static str {
+        "Compile away ref cells by exposing their ports through the component signature"
+    }
+}
+
+/// Generate a string given the name of the cell and the port.
+fn format_port_name(cell: &ir::Id, port: &ir::Id) -> String {
+    format!("{}_{}", cell.id, port.id)
+}
+
+impl CompileRef {
+    /// Move the ports of all the `ref` cells in `comp` to its signature and
+    /// return the added ports.
+    fn externalize_ref_cells(comp: &mut ir::Component) -> RefPorts {
+        let (ref_cells, cells): (Vec<_>, Vec<_>) = comp
+            .cells
+            .drain()
+            .partition(|cr| cr.borrow().is_reference());
+
+        // Re-add non-ref cells.
+        comp.cells.append(cells.into_iter());
+
+        let mut added = vec![];
+        for cell_ref in ref_cells {
+            let mut cell = cell_ref.borrow_mut();
+            let name = cell.clone_name();
+            for port_ref in cell.ports.drain(..) {
+                {
+                    let mut port = port_ref.borrow_mut();
+                    if port.attributes.has("clk")
+                        || port.attributes.has("reset")
+                    {
+                        continue;
+                    }
+                    let new_name =
+                        comp.generate_name(format_port_name(&name, &port.name));
+                    added.push((
+                        ir::Canonical(name.clone(), port.name.clone()),
+                        new_name.clone(),
+                        port.width,
+                        port.direction.clone(),
+                    ));
+                    // Change the name and the parent of this port. The
+                    // assignments that use the port don't need to change.
+                    port.name = new_name;
+                    port.parent =
+                        ir::PortParent::Cell(WRC::from(&comp.signature));
+                    port.attributes = ir::Attributes::default();
+                }
+                comp.signature.borrow_mut().ports.push(port_ref);
+            }
+        }
+        added
+    }
+}
+
+impl Visitor for CompileRef {
+    fn require_postorder() -> bool {
+        true
+    }
+
+    fn start(
+        &mut self,
+        comp: &mut ir::Component,
+        _sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        let added = Self::externalize_ref_cells(comp);
+        if !added.is_empty() {
+            self.ref_ports.insert(comp.name.clone(), added);
+        }
+
+        // Components are visited in post-order so the signatures of all the
+        // instantiated components have already been extended. Add the new
+        // ports to the instances.
+        for cell_ref in comp.cells.iter() {
+            let ports = match &cell_ref.borrow().prototype {
+                ir::CellType::Component { name, .. } => {
+                    match self.ref_ports.get(name) {
+                        Some(ports) => ports,
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            for (_, name, width, direction) in ports {
+                let port = Rc::new(RefCell::new(ir::Port {
+                    name: name.clone(),
+                    width: *width,
+                    // The signature cell stores ports in the reverse direction
+                    direction: direction.reverse(),
+                    parent: ir::PortParent::Cell(WRC::from(cell_ref)),
+                    attributes: ir::Attributes::default(),
+                    select: None,
+                }));
+                cell_ref.borrow_mut().ports.push(port);
+            }
+        }
+
+        Ok(Action::Continue)
+    }
+
+    fn invoke(
+        &mut self,
+        s: &mut ir::Invoke,
+        _comp: &mut ir::Component,
+        _sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        if s.ref_cells.is_empty() {
+            return Ok(Action::Continue);
+        }
+
+        let comp_name = s.comp.borrow().type_name().cloned().unwrap();
+        let ports = self.ref_ports.get(&comp_name).ok_or_else(|| {
+            Error::malformed_control(format!(
+                "Component `{}` does not define any ref cells",
+                comp_name
+            ))
+            .with_pos(&s.attributes)
+        })?;
+
+        for (ref_name, cell_ref) in std::mem::take(&mut s.ref_cells) {
+            let cell = cell_ref.borrow();
+            for (ir::Canonical(_, port), name, _, direction) in
+                ports.iter().filter(|(canon, ..)| canon.0 == ref_name)
+            {
+                let bound = cell.find(port).ok_or_else(|| {
+                    Error::malformed_control(format!(
+                        "Cell `{}` bound to ref cell `{}` does not have port `{}`",
+                        cell.name(),
+                        ref_name,
+                        port
+                    ))
+                    .with_pos(&s.attributes)
+                })?;
+                match direction {
+                    // Inputs of the ref cell are driven by the component.
+                    ir::Direction::Input => {
+                        s.outputs.push((name.clone(), bound))
+                    }
+                    ir::Direction::Output => {
+                        s.inputs.push((name.clone(), bound))
+                    }
+                    ir::Direction::Inout => {
+                        unreachable!("Cells should not have inout ports")
+                    }
+                }
+            }
+        }
+
+        Ok(Action::Continue)
+    }
+}
@@ -0,0 +1,293 @@
+use super::top_down_static_timing::Schedule;
+use crate::errors::{CalyxResult, Error};
+use crate::ir::traversal::{
+    Action, ConstructVisitor, Named, ParseVal, PassOpt, VisResult, Visitor,
+};
+use crate::ir::{self, LibrarySignatures};
+use crate::{build_assignments, guard, structure};
+use std::cmp;
+use std::ops::Not;
+use std::rc::Rc;
+
+/// Compiles `static` control statements into groups that implement
+/// latency-sensitive FSMs.
+///
+/// Unlike [super::TopDownStaticTiming], which opportunistically compiles
+/// control programs with `@static` annotations, this pass compiles every
+/// `static` control statement and guarantees that the generated group takes
+/// exactly as many cycles as the latency of the statement.
+///
+/// The generated FSM counts up to the latency of the statement and enables
+/// each group for exactly its latency:
+/// - `static seq` schedules its statements one after another.
+/// - `static par` schedules all its statements in the same state.
+/// - `repeat` schedules its body `num_repeats` times in sequence.
+/// - `static if` saves the value of its condition in a register in the first
+///   cycle and runs its branches afterwards. The shorter branch waits for the
+///   longer one to finish.
+pub struct CompileStatic {
+    /// Print out the FSM representation to STDOUT.
+    dump_fsm: bool,
+}
+
+impl ConstructVisitor for CompileStatic {
+    fn from(ctx: &ir::Context) -> CalyxResult<Self>
+    where
+        Self: Sized + Named,
+    {
+        let opts = Self::get_opts(ctx)?;
+
+        Ok(CompileStatic {
+            dump_fsm: opts["dump-fsm"].bool(),
+        })
+    }
+
+    fn clear_data(&mut self) {
+        /* All data can be transferred between components */
+    }
+}
+
+impl Named for CompileStatic {
+    fn name() -> &'static str {
+        "compile-static"
+    }
+
+    fn description() -> &'static str {
+        "Compile static control into latency-sensitive FSMs"
+    }
+
+    fn opts() -> Vec<PassOpt> {
+        vec![PassOpt::new(
+            "dump-fsm",
+            "print the generated FSMs to stdout",
+            ParseVal::Bool(false),
+        )]
+    }
+}
+
+impl Schedule<'_> {
+    /// Schedule the statically timed program `con` starting at `cur_state`
+    /// and return the state after it finishes.
+    fn static_calculate_states(
+        &mut self,
+        con: &ir::Control,
+        cur_state: u64,
+        pre_guard: &ir::Guard,
+    ) -> CalyxResult<u64> {
+        match con {
+            ir::Control::Enable(e) => {
+                self.static_enable_calculate_states(e, cur_state, pre_guard)
+            }
+            ir::Control::StaticSeq(s) => {
+                self.static_seq_calculate_states(s, cur_state, pre_guard)
+            }
+            ir::Control::StaticPar(p) => {
+                self.static_par_calculate_states(p, cur_state, pre_guard)
+            }
+            ir::Control::StaticIf(i) => {
+                self.static_if_calculate_states(i, cur_state, pre_guard)
+            }
+            ir::Control::Repeat(r) => {
+                self.static_repeat_calculate_states(r, cur_state, pre_guard)
+            }
+            ir::Control::Empty(_) => Ok(cur_state),
+            _ => Err(Error::pass_assumption(
+                CompileStatic::name().to_string(),
+                "static control contains a statement that is not statically timed. Run `well-formed` before this pass.".to_string(),
+            )
+            .with_pos(con)),
+        }
+    }
+
+    /// Enable the group when the FSM is in the range
+    /// `[cur_state, cur_state + latency)`.
+    fn static_enable_calculate_states(
+        &mut self,
+        con: &ir::Enable,
+        cur_state: u64,
+        pre_guard: &ir::Guard,
+    ) -> CalyxResult<u64> {
+        let group = &con.group;
+        let time =
+            group.borrow().attributes.get("static").ok_or_else(|| {
+                Error::pass_assumption(
+                    CompileStatic::name().to_string(),
+                    format!(
+                        "group `{}` in static control does not have a latency",
+                        group.borrow().name()
+                    ),
+                )
+                .with_pos(&con.attributes)
+            })?;
+
+        structure!(self.builder;
+            let signal_on = constant(1, 1);
+        );
+        let mut assigns = build_assignments!(self.builder;
+            group["go"] = pre_guard ? signal_on["out"];
+        );
+        self.enables
+            .entry((cur_state, cur_state + time))
+            .or_default()
+            .append(&mut assigns);
+
+        Ok(cur_state + time)
+    }
+
+    fn static_seq_calculate_states(
+        &mut self,
+        con: &ir::StaticSeq,
+        cur_state: u64,
+        pre_guard: &ir::Guard,
+    ) -> CalyxResult<u64> {
+        con.stmts.iter().try_fold(cur_state, |state, stmt| {
+            self.static_calculate_states(stmt, state, pre_guard)
+        })
+    }
+
+    fn static_par_calculate_states(
+        &mut self,
+        con: &ir::StaticPar,
+        cur_state: u64,
+        pre_guard: &ir::Guard,
+    ) -> CalyxResult<u64> {
+        con.stmts.iter().try_fold(cur_state, |end, stmt| {
+            let stmt_end =
+                self.static_calculate_states(stmt, cur_state, pre_guard)?;
+            Ok(cmp::max(end, stmt_end))
+        })
+    }
+
+    fn static_repeat_calculate_states(
+        &mut self,
+        con: &ir::Repeat,
+        cur_state: u64,
+        pre_guard: &ir::Guard,
+    ) -> CalyxResult<u64> {
+        (0..con.num_repeats).try_fold(cur_state, |state, _| {
+            self.static_calculate_states(&con.body, state, pre_guard)
+        })
+    }
+
+    fn static_if_calculate_states(
+        &mut self,
+        con: &ir::StaticIf,
+        cur_state: u64,
+        pre_guard: &ir::Guard,
+    ) -> CalyxResult<u64> {
+        // Save the value of the condition in the first cycle so that the
+        // branches don't observe changes to the port.
+        structure!(self.builder;
+            let signal_on = constant(1, 1);
+            let cond_stored = prim std_reg(1);
+        );
+        let save_cond = vec![
+            self.builder.build_assignment(
+                cond_stored.borrow().get("in"),
+                Rc::clone(&con.port),
+                pre_guard.clone(),
+            ),
+            self.builder.build_assignment(
+                cond_stored.borrow().get("write_en"),
+                signal_on.borrow().get("out"),
+                pre_guard.clone(),
+            ),
+        ];
+        self.enables
+            .entry((cur_state, cur_state + 1))
+            .or_default()
+            .extend(save_cond);
+
+        let stored = guard!(cond_stored["out"]);
+        let tru_end = self.static_calculate_states(
+            &con.tbranch,
+            cur_state + 1,
+            &pre_guard.clone().and(stored.clone()),
+        )?;
+        let fal_end = self.static_calculate_states(
+            &con.fbranch,
+            cur_state + 1,
+            &pre_guard.clone().and(stored.not()),
+        )?;
+
+        Ok(cmp::max(tru_end, fal_end))
+    }
+}
+
+impl CompileStatic {
+    /// Compile a statically timed program into a group that runs for exactly
+    /// the latency of the program. `calculate_states` schedules the program
+    /// starting at the first state and returns its latency.
+    fn compile<F>(
+        &self,
+        comp: &mut ir::Component,
+        sigs: &LibrarySignatures,
+        calculate_states: F,
+    ) -> VisResult
+    where
+        F: FnOnce(&mut Schedule) -> CalyxResult<u64>,
+    {
+        let mut builder = ir::Builder::new(comp, sigs);
+        let mut schedule = Schedule::new(&mut builder);
+        let latency = calculate_states(&mut schedule)?;
+
+        // Programs that take zero cycles don't do anything.
+        if latency == 0 {
+            return Ok(Action::Change(ir::Control::empty()));
+        }
+
+        // Every statement takes exactly its latency so the FSM transitions to
+        // the next state in every cycle.
+        schedule
+            .transitions
+            .extend((0..latency).map(|st| (st, st + 1, ir::Guard::True)));
+
+        // Dump FSM if requested.
+        if self.dump_fsm {
+            schedule.display();
+        }
+
+        let group = schedule.realize_schedule();
+        group.borrow_mut().attributes.insert("static", latency);
+
+        Ok(Action::Change(ir::Control::enable(group)))
+    }
+}
+
+impl Visitor for CompileStatic {
+    fn start_static_seq(
+        &mut self,
+        s: &mut ir::StaticSeq,
+        comp: &mut ir::Component,
+        sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        self.compile(comp, sigs, |schedule| {
+            schedule.static_seq_calculate_states(s, 0, &ir::Guard::True)
+        })
+    }
+
+    fn start_static_par(
+        &mut self,
+        s: &mut ir::StaticPar,
+        comp: &mut ir::Component,
+        sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        self.compile(comp, sigs, |schedule| {
+            schedule.static_par_calculate_states(s, 0, &ir::Guard::True)
+        })
+    }
+
+    fn start_static_if(
+        &mut self,
+        s: &mut ir::StaticIf,
+        comp: &mut ir::Component,
+        sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        self.compile(comp, sigs, |schedule| {
+            schedule.static_if_calculate_states(s, 0, &ir::Guard::True)
+        })
+    }
+}
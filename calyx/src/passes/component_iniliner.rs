@@ -6,7 +6,7 @@ use itertools::Itertools;
 use crate::analysis;
 use crate::errors::Error;
 use crate::ir::traversal::{
-    Action, ConstructVisitor, Named, VisResult, Visitor,
+    Action, ConstructVisitor, Named, ParseVal, PassOpt, VisResult, Visitor,
 };
 use crate::ir::{self, CloneName, LibrarySignatures, RRC};
 
@@ -56,8 +56,8 @@ impl ConstructVisitor for ComponentInliner {
     where
         Self: Sized,
     {
-        let opts = Self::get_opts(&["always"], ctx);
-        Ok(ComponentInliner::new(opts[0]))
+        let opts = Self::get_opts(ctx)?;
+        Ok(ComponentInliner::new(opts["always"].bool()))
     }
 
     fn clear_data(&mut self) {
@@ -83,13 +83,14 @@ impl ComponentInliner {
                 name,
                 &param_binding.iter().map(|(_, v)| *v).collect_vec(),
             ),
-            ir::CellType::Component { name } => builder.add_component(
+            ir::CellType::Component { name, is_comb } => builder.add_component(
                 cn.clone(),
                 name.clone(),
                 cell.get_signature(),
+                *is_comb,
             ),
             ir::CellType::Constant { val, width } => {
-                builder.add_constant(*val, *width)
+                builder.add_wide_constant(val.clone(), *width)
             }
             ir::CellType::ThisComponent => unreachable!(),
         };
@@ -262,6 +263,14 @@ impl Named for ComponentInliner {
     fn description() -> &'static str {
         "inline all component instances marked with @inline attribute"
     }
+
+    fn opts() -> Vec<PassOpt> {
+        vec![PassOpt::new(
+            "always",
+            "inline all component instances",
+            ParseVal::Bool(false),
+        )]
+    }
 }
 
 impl Visitor for ComponentInliner {
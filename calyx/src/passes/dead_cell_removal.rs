@@ -57,13 +57,17 @@ impl Visitor for DeadCellRemoval {
         _comps: &[ir::Component],
     ) -> VisResult {
         let ir::Invoke {
-            inputs, outputs, ..
+            inputs,
+            outputs,
+            ref_cells,
+            ..
         } = s;
         let cells = inputs
             .iter()
             .map(|(_, p)| p)
             .chain(outputs.iter().map(|(_, p)| p))
             .map(|p| p.borrow().get_parent_name())
+            .chain(ref_cells.iter().map(|(_, c)| c.clone_name()))
             .chain(iter::once(s.comp.clone_name()));
         self.all_reads.extend(cells);
         Ok(Action::Continue)
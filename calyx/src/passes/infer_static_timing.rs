@@ -1,14 +1,14 @@
 use std::collections::HashMap;
 
-use crate::analysis::{GraphAnalysis, ReadWriteSet};
+use crate::analysis::StaticLatency;
 use crate::errors::{CalyxResult, Error};
 use crate::ir::traversal::{
-    Action, ConstructVisitor, Named, VisResult, Visitor,
+    Action, ConstructVisitor, Named, ParseVal, PassOpt, VisResult, Visitor,
 };
+use crate::ir::GetAttributes;
 use crate::ir::{self, LibrarySignatures};
-use crate::ir::{GetAttributes, RRC};
 use itertools::Itertools;
-use std::{cmp, ops::Add, rc::Rc};
+use std::{cmp, ops::Add};
 
 /// Infer "static" annotation for groups and add "@static" annotation when
 /// (conservatively) possible.
@@ -19,41 +19,30 @@ use std::{cmp, ops::Add, rc::Rc};
 /// annotation in a group that differs from an inferred value, this
 /// pass will throw an error. If a group's `done` signal relies on signals
 /// that are not only `done` signals, this pass will ignore that group.
+///
+/// The `max-latency` option limits the latency inferred for control
+/// statements. Statements that take longer are left dynamic so that they
+/// are not compiled into large static FSMs.
 pub struct InferStaticTiming {
-    /// primitive name -> (go signal, done signal, latency)
-    latency_data: HashMap<ir::Id, (ir::Id, ir::Id, u64)>,
+    /// Latency of groups
+    group_latency: StaticLatency,
     /// static timing information for components
     comp_latency: HashMap<ir::Id, u64>,
+    /// Largest latency inferred for control statements. Zero means no limit.
+    max_latency: u64,
 }
 
-// Override constructor to build latency_data information from the primitives
+// Override constructor to build latency information from the primitives
 // library.
 impl ConstructVisitor for InferStaticTiming {
     fn from(ctx: &ir::Context) -> CalyxResult<Self> {
-        let mut latency_data = HashMap::new();
-        let mut comp_latency = HashMap::new();
-        // Construct latency_data for each primitive
-        for prim in ctx.lib.signatures() {
-            if let Some(time) = prim.attributes.get("static") {
-                let mut go_port = None;
-                let mut done_port = None;
-                for port in &prim.signature {
-                    if port.attributes.has("go") {
-                        go_port = Some(port.name.clone());
-                    }
-                    if port.attributes.has("done") {
-                        done_port = Some(port.name.clone());
-                    }
-                }
-                if let (Some(go), Some(done)) = (go_port, done_port) {
-                    latency_data.insert(prim.name.clone(), (go, done, *time));
-                    comp_latency.insert(prim.name.clone(), *time);
-                }
-            }
-        }
+        let group_latency = StaticLatency::from(&ctx.lib);
+        let comp_latency = group_latency.primitive_latencies();
+        let opts = Self::get_opts(ctx)?;
         Ok(InferStaticTiming {
-            latency_data,
+            group_latency,
             comp_latency,
+            max_latency: opts["max-latency"].num(),
         })
     }
 
@@ -76,10 +65,7 @@ where
 {
     stmts
         .iter()
-        .map(|con| {
-            con.get_attributes()
-                .and_then(|attr| attr.get("static").copied())
-        })
+        .map(|con| con.get_attributes().and_then(|attr| attr.get("static")))
         .fold_options(start, acc)
 }
 
@@ -91,252 +77,23 @@ impl Named for InferStaticTiming {
     fn description() -> &'static str {
         "infers and annotates static timing for groups when possible"
     }
-}
-
-impl InferStaticTiming {
-    /// Return true if the edge (`src`, `dst`) meet one these criteria, and false otherwise:
-    ///   - `src` is an "out" port of a constant, and `dst` is a "go" port
-    ///   - `src` is a "done" port, and `dst` is a "go" port
-    ///   - `src` is a "done" port, and `dst` is the "done" port of a group
-    fn mem_wrt_dep_graph(&self, src: &ir::Port, dst: &ir::Port) -> bool {
-        match (&src.parent, &dst.parent) {
-            (
-                ir::PortParent::Cell(src_cell),
-                ir::PortParent::Cell(dst_cell),
-            ) => {
-                if let (
-                    ir::CellType::Primitive {
-                        name: dst_cell_prim_type,
-                        ..
-                    },
-                    ir::CellType::Primitive {
-                        name: src_cell_prim_type,
-                        ..
-                    },
-                ) = (
-                    &dst_cell.upgrade().borrow().prototype,
-                    &src_cell.upgrade().borrow().prototype,
-                ) {
-                    let data_dst = self.latency_data.get(dst_cell_prim_type);
-                    let data_src = self.latency_data.get(src_cell_prim_type);
-                    if let (Some((go_dst, _, _)), Some((_, done_src, _))) =
-                        (data_dst, data_src)
-                    {
-                        if dst.name == *go_dst && src.name == *done_src {
-                            return true;
-                        }
-                    }
-                }
-
-                // A constant writes to a cell: to be added to the graph, the cell needs to be a "done" port.
-                if let (
-                    ir::CellType::Primitive {
-                        name: dst_cell_prim_type,
-                        ..
-                    },
-                    ir::CellType::Constant { .. },
-                ) = (
-                    &dst_cell.upgrade().borrow().prototype,
-                    &src_cell.upgrade().borrow().prototype,
-                ) {
-                    let data = self.latency_data.get(dst_cell_prim_type);
-                    if let Some((go, _, _)) = data {
-                        if dst.name == *go {
-                            return true;
-                        }
-                    }
-                }
-
-                false
-            }
-
-            // Something is written to a group: to be added to the graph, this needs to be a "done" port.
-            (_, ir::PortParent::Group(_)) => dst.name == "done",
-
-            // If we encounter anything else, no need to add it to the graph.
-            _ => false,
-        }
-    }
-
-    /// Return a Vec of edges (`a`, `b`), where `a` is a "go" port and `b`
-    /// is a "done" port, and `a` and `b` have the same parent cell.
-    fn find_go_done_edges(
-        &self,
-        group: &ir::Group,
-    ) -> Vec<(RRC<ir::Port>, RRC<ir::Port>)> {
-        let rw_set = ReadWriteSet::uses(group.assignments.iter());
-        let mut go_done_edges: Vec<(RRC<ir::Port>, RRC<ir::Port>)> = Vec::new();
-        for cell_ref in rw_set {
-            let cell = cell_ref.borrow();
-            if let ir::CellType::Primitive {
-                name: cell_type, ..
-            } = &cell.prototype
-            {
-                if let Some((go, done, _)) = self.latency_data.get(cell_type) {
-                    let go_port =
-                        &cell.ports.iter().find(|p| p.borrow().name == *go);
-                    let done_port =
-                        &cell.ports.iter().find(|p| p.borrow().name == *done);
-
-                    if let (Some(g), Some(d)) = (go_port, done_port) {
-                        go_done_edges.push((Rc::clone(g), Rc::clone(d)));
-                    }
-                }
-            }
-        }
-        go_done_edges
-    }
-
-    /// Returns true if `port` is a "done" port, and we know the latency data
-    /// about `port`, or is a constant.
-    fn is_done_port_or_const(&self, port: &ir::Port) -> bool {
-        if let ir::PortParent::Cell(cell) = &port.parent {
-            if let ir::CellType::Primitive {
-                name: cell_type, ..
-            } = &cell.upgrade().borrow().prototype
-            {
-                if let Some((_, done, _)) = self.latency_data.get(cell_type) {
-                    if port.name == *done {
-                        return true;
-                    }
-                }
-            }
-
-            if let ir::CellType::Constant { val, .. } =
-                &cell.upgrade().borrow().prototype
-            {
-                if *val > 0 {
-                    return true;
-                }
-            }
-        }
-        false
-    }
-
-    /// Returns true if `graph` contains writes to "done" ports
-    /// that could have dynamic latencies, false otherwise.
-    fn contains_dyn_writes(&self, graph: GraphAnalysis) -> bool {
-        for port in &graph.ports() {
-            match &port.borrow().parent {
-                ir::PortParent::Cell(cell) => {
-                    if let ir::CellType::Primitive {
-                        name: cell_type, ..
-                    } = &cell.upgrade().borrow().prototype
-                    {
-                        if let Some((go, _, _)) =
-                            self.latency_data.get(cell_type)
-                        {
-                            if port.borrow().name == *go {
-                                for write_port in
-                                    graph.writes_to(&*port.borrow())
-                                {
-                                    if !self.is_done_port_or_const(
-                                        &*write_port.borrow(),
-                                    ) {
-                                        return true;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-
-                ir::PortParent::Group(_) => {
-                    if port.borrow().name == "done" {
-                        for write_port in graph.writes_to(&*port.borrow()) {
-                            if !self
-                                .is_done_port_or_const(&*write_port.borrow())
-                            {
-                                return true;
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        false
-    }
 
-    /// Returns true if `graph` contains any nodes with degree > 1.
-    fn contains_node_deg_gt_one(graph: GraphAnalysis) -> bool {
-        for port in graph.ports() {
-            if graph.writes_to(&*port.borrow()).count() > 1 {
-                return true;
-            }
-        }
-        false
+    fn opts() -> Vec<PassOpt> {
+        vec![PassOpt::new(
+            "max-latency",
+            "largest latency inferred for control statements (0 is unlimited)",
+            ParseVal::Num(0),
+        )]
     }
+}
 
-    /// Attempts to infer the number of cycles starting when
-    /// `group[go]` is high, and port is high. If inference is
-    /// not possible, returns None.
-    fn infer_latency(&self, group: &ir::Group) -> Option<u64> {
-        // Creates a write dependency graph, which contains an edge (`a`, `b`) if:
-        //   - `a` is a "done" port, and writes to `b`, which is a "go" port
-        //   - `a` is a "done" port, and writes to `b`, which is the "done" port of this group
-        //   - `a` is an "out" port, and is a constant, and writes to `b`, a "go" port
-        //   - `a` is a "go" port, and `b` is a "done" port, and `a` and `b` share a parent cell
-        // Nodes that are not part of any edges that meet these criteria are excluded.
-        //
-        // For example, this group:
-        // ```
-        // group g1 {
-        //   a.in = 32'd1;
-        //   a.write_en = 1'd1;
-        //   g1[done] = a.done;
-        // }
-        // ```
-        // corresponds to this graph:
-        // ```
-        // constant(1) -> a.write_en
-        // a.write_en -> a.done
-        // a.done -> g1[done]
-        // ```
-        let graph_unprocessed = GraphAnalysis::from(group);
-        if self.contains_dyn_writes(graph_unprocessed.clone()) {
-            return None;
-        }
-
-        let go_done_edges = self.find_go_done_edges(group);
-        let graph = graph_unprocessed
-            .edge_induced_subgraph(|src, dst| self.mem_wrt_dep_graph(src, dst))
-            .add_edges(&go_done_edges)
-            .remove_isolated_vertices();
-
-        // Give up if a port has multiple writes to it.
-        if Self::contains_node_deg_gt_one(graph.clone()) {
-            return None;
-        }
-
-        let mut tsort = graph.toposort();
-        let start = tsort.next().unwrap();
-        let finish = tsort.last().unwrap();
-
-        let paths = graph.paths(&*start.borrow(), &*finish.borrow());
-        // If there are no paths, give up.
-        if paths.is_empty() {
-            return None;
-        }
-        let first_path = paths.get(0).unwrap();
-
-        // Sum the latencies of each primitive along the path.
-        let mut latency_sum = 0;
-        for port in first_path {
-            if let ir::PortParent::Cell(cell) = &port.borrow().parent {
-                if let ir::CellType::Primitive { name, .. } =
-                    &cell.upgrade().borrow().prototype
-                {
-                    if let Some((go, _, latency)) = self.latency_data.get(name)
-                    {
-                        if port.borrow().name == go {
-                            latency_sum += latency;
-                        }
-                    }
-                }
-            }
+impl InferStaticTiming {
+    /// Annotate a control statement with the inferred latency `time` unless
+    /// it exceeds the `max-latency` option.
+    fn annotate(&self, attrs: &mut ir::Attributes, time: u64) {
+        if self.max_latency == 0 || time <= self.max_latency {
+            attrs.insert("static", time);
         }
-
-        Some(latency_sum)
     }
 }
 
@@ -355,11 +112,11 @@ impl Visitor for InferStaticTiming {
     ) -> VisResult {
         let mut latency_result: Option<u64>;
         for group in comp.groups.iter() {
-            if let Some(latency) = self.infer_latency(&group.borrow()) {
+            if let Some(latency) = self.group_latency.infer(&group.borrow()) {
                 let grp = group.borrow();
                 if let Some(curr_lat) = grp.attributes.get("static") {
                     // Inferred latency is not the same as the provided latency annotation.
-                    if *curr_lat != latency {
+                    if curr_lat != latency {
                         let msg1 = format!("Annotated latency: {}", curr_lat);
                         let msg2 = format!("Inferred latency: {}", latency);
                         let msg = format!(
@@ -395,10 +152,25 @@ impl Visitor for InferStaticTiming {
         _comps: &[ir::Component],
     ) -> VisResult {
         if let (Some(bound), Some(body_time)) = (
-            s.attributes.get("bound").cloned(),
+            s.attributes.get("bound"),
             s.body.get_attributes().and_then(|attr| attr.get("static")),
         ) {
-            s.attributes.insert("static", bound * body_time);
+            self.annotate(&mut s.attributes, bound * body_time);
+        }
+        Ok(Action::Continue)
+    }
+
+    fn finish_repeat(
+        &mut self,
+        s: &mut ir::Repeat,
+        _comp: &mut ir::Component,
+        _sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        if let Some(body_time) =
+            s.body.get_attributes().and_then(|attr| attr.get("static"))
+        {
+            self.annotate(&mut s.attributes, s.num_repeats * body_time);
         }
         Ok(Action::Continue)
     }
@@ -418,7 +190,7 @@ impl Visitor for InferStaticTiming {
                 .get_attributes()
                 .and_then(|attr| attr.get("static")),
         ) {
-            s.attributes.insert("static", 1 + cmp::max(ttime, ftime));
+            self.annotate(&mut s.attributes, 1 + cmp::max(ttime, ftime));
         }
 
         Ok(Action::Continue)
@@ -432,7 +204,7 @@ impl Visitor for InferStaticTiming {
         _comps: &[ir::Component],
     ) -> VisResult {
         if let Some(time) = accumulate_static_time(&s.stmts, 0, cmp::max) {
-            s.attributes.insert("static", time);
+            self.annotate(&mut s.attributes, time);
         }
         Ok(Action::Continue)
     }
@@ -445,6 +217,45 @@ impl Visitor for InferStaticTiming {
         _comps: &[ir::Component],
     ) -> VisResult {
         if let Some(time) = accumulate_static_time(&s.stmts, 0, Add::add) {
+            self.annotate(&mut s.attributes, time);
+        }
+        Ok(Action::Continue)
+    }
+
+    fn finish_static_seq(
+        &mut self,
+        s: &mut ir::StaticSeq,
+        _comp: &mut ir::Component,
+        _sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        if let Some(time) = s.latency() {
+            s.attributes.insert("static", time);
+        }
+        Ok(Action::Continue)
+    }
+
+    fn finish_static_par(
+        &mut self,
+        s: &mut ir::StaticPar,
+        _comp: &mut ir::Component,
+        _sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        if let Some(time) = s.latency() {
+            s.attributes.insert("static", time);
+        }
+        Ok(Action::Continue)
+    }
+
+    fn finish_static_if(
+        &mut self,
+        s: &mut ir::StaticIf,
+        _comp: &mut ir::Component,
+        _sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        if let Some(time) = s.latency() {
             s.attributes.insert("static", time);
         }
         Ok(Action::Continue)
@@ -458,7 +269,7 @@ impl Visitor for InferStaticTiming {
         _comps: &[ir::Component],
     ) -> VisResult {
         if let Some(time) = s.group.borrow().attributes.get("static") {
-            s.attributes.insert("static", *time);
+            s.attributes.insert("static", time);
         }
 
         Ok(Action::Continue)
@@ -496,8 +307,8 @@ impl Visitor for InferStaticTiming {
             .get_attributes()
             .and_then(|attrs| attrs.get("static"))
         {
-            comp.attributes.insert("static", *time);
-            self.comp_latency.insert(comp.name.clone(), *time);
+            comp.attributes.insert("static", time);
+            self.comp_latency.insert(comp.name.clone(), time);
         }
         Ok(Action::Continue)
     }
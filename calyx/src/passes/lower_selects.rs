@@ -0,0 +1,166 @@
+use crate::ir::{
+    self,
+    traversal::{Action, Named, VisResult, Visitor},
+    RRC,
+};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Lowers bit-selects on ports into `std_slice` cells. A select with a
+/// non-zero lower index first shifts the port using a `std_rsh` cell.
+///
+/// For example:
+/// ```calyx
+/// r.in = x.out[7:4];
+/// ```
+/// is transformed into:
+/// ```calyx
+/// rsh0.left = x.out;
+/// rsh0.right = 8'd4;
+/// slice0.in = rsh0.out;
+/// r.in = slice0.out;
+/// ```
+/// The cells are driven using continuous assignments and every distinct
+/// select in a component is lowered only once.
+#[derive(Default)]
+pub struct LowerSelects {
+    /// Lowered port for each bit-select in the current component.
+    lowered: HashMap<(ir::Canonical, u64, u64), RRC<ir::Port>>,
+}
+
+impl Named for LowerSelects {
+    fn name() -> &'static str {
+        "lower-selects"
+    }
+
+    fn description() -> &'static str {
+        "lower bit-selects on ports into std_slice cells"
+    }
+}
+
+impl LowerSelects {
+    /// Returns the port that computes the value of `port` if it is a
+    /// bit-select.
+    fn lower_port(
+        &mut self,
+        port: &RRC<ir::Port>,
+        builder: &mut ir::Builder,
+    ) -> Option<RRC<ir::Port>> {
+        let (msb, lsb) = port.borrow().select?;
+        let key = (port.borrow().canonical(), msb, lsb);
+        if let Some(lowered) = self.lowered.get(&key) {
+            return Some(Rc::clone(lowered));
+        }
+
+        let base = port.borrow().base_port();
+        let in_width = base.borrow().width;
+        let out_width = port.borrow().width;
+        let mut assigns = Vec::with_capacity(3);
+
+        let shifted = if lsb == 0 {
+            base
+        } else {
+            let rsh = builder.add_primitive("rsh", "std_rsh", &[in_width]);
+            let amount = builder.add_constant(lsb, in_width);
+            let rsh = rsh.borrow();
+            assigns.push(builder.build_assignment(
+                rsh.get("left"),
+                base,
+                ir::Guard::True,
+            ));
+            assigns.push(builder.build_assignment(
+                rsh.get("right"),
+                amount.borrow().get("out"),
+                ir::Guard::True,
+            ));
+            rsh.get("out")
+        };
+
+        let lowered = if out_width == in_width {
+            shifted
+        } else {
+            let slice = builder.add_primitive(
+                "slice",
+                "std_slice",
+                &[in_width, out_width],
+            );
+            let slice = slice.borrow();
+            assigns.push(builder.build_assignment(
+                slice.get("in"),
+                shifted,
+                ir::Guard::True,
+            ));
+            slice.get("out")
+        };
+
+        builder.component.continuous_assignments.extend(assigns);
+        self.lowered.insert(key, Rc::clone(&lowered));
+        Some(lowered)
+    }
+
+    fn lower_assigns(
+        &mut self,
+        assigns: &mut [ir::Assignment],
+        builder: &mut ir::Builder,
+    ) {
+        for assign in assigns {
+            assign.for_each_port(|port| self.lower_port(port, builder));
+        }
+    }
+}
+
+impl Visitor for LowerSelects {
+    fn start(
+        &mut self,
+        comp: &mut ir::Component,
+        sigs: &ir::LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        let mut builder = ir::Builder::new(comp, sigs);
+
+        let mut conts: Vec<_> =
+            builder.component.continuous_assignments.drain(..).collect();
+        self.lower_assigns(&mut conts, &mut builder);
+        builder.component.continuous_assignments.extend(conts);
+
+        let groups =
+            builder.component.groups.iter().cloned().collect::<Vec<_>>();
+        for group in groups {
+            self.lower_assigns(
+                &mut group.borrow_mut().assignments,
+                &mut builder,
+            );
+        }
+
+        let comb_groups = builder
+            .component
+            .comb_groups
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        for group in comb_groups {
+            self.lower_assigns(
+                &mut group.borrow_mut().assignments,
+                &mut builder,
+            );
+        }
+
+        Ok(Action::Continue)
+    }
+
+    fn invoke(
+        &mut self,
+        s: &mut ir::Invoke,
+        comp: &mut ir::Component,
+        sigs: &ir::LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        let mut builder = ir::Builder::new(comp, sigs);
+        for (_, port) in s.inputs.iter_mut() {
+            if let Some(lowered) = self.lower_port(port, &mut builder) {
+                *port = lowered;
+            }
+        }
+        Ok(Action::Continue)
+    }
+}
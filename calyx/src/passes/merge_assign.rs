@@ -1,6 +1,7 @@
+use crate::errors::CalyxResult;
 use crate::ir::{
     self,
-    traversal::{Named, Visitor},
+    traversal::{ComponentLocal, Named, Visitor},
     LibrarySignatures,
 };
 use ir::traversal::{Action, VisResult};
@@ -57,7 +58,16 @@ fn merge_assigns(assigns: Vec<ir::Assignment>) -> Vec<ir::Assignment> {
         .collect::<Vec<_>>()
 }
 
+impl ComponentLocal for MergeAssign {
+    /// The pass does not keep any state between components.
+    fn merge(&mut self, _visitor: Self) {}
+}
+
 impl Visitor for MergeAssign {
+    fn do_pass(&mut self, context: &mut ir::Context) -> CalyxResult<()> {
+        self.do_pass_parallel(context)
+    }
+
     fn start(
         &mut self,
         comp: &mut ir::Component,
@@ -3,7 +3,11 @@ use std::collections::HashMap;
 use super::sharing_components::ShareComponents;
 use crate::{
     analysis::LiveRangeAnalysis,
-    ir::{self, traversal::Named},
+    errors::CalyxResult,
+    ir::{
+        self,
+        traversal::{ConstructVisitor, Named, ParseVal, PassOpt},
+    },
 };
 
 /// Given a [LiveRangeAnalysis] that specifies the registers alive at each
@@ -19,10 +23,14 @@ use crate::{
 ///
 /// This pass only renames uses of registers. [DeadCellRemoval] should be run after this
 /// to actually remove the register definitions.
-#[derive(Default)]
+///
+/// Registers narrower than the `min-width` option are not shared since the
+/// multiplexers needed to share them can cost more than the registers.
 pub struct MinimizeRegs {
     live: LiveRangeAnalysis,
     rewrites: HashMap<ir::Id, ir::RRC<ir::Cell>>,
+    /// Only share registers that are at least this wide.
+    min_width: u64,
 }
 
 impl Named for MinimizeRegs {
@@ -32,6 +40,30 @@ impl Named for MinimizeRegs {
     fn description() -> &'static str {
         "use the fewest possible registers"
     }
+
+    fn opts() -> Vec<PassOpt> {
+        vec![PassOpt::new(
+            "min-width",
+            "only share registers that are at least this wide",
+            ParseVal::Num(1),
+        )]
+    }
+}
+
+impl ConstructVisitor for MinimizeRegs {
+    fn from(ctx: &ir::Context) -> CalyxResult<Self> {
+        let opts = Self::get_opts(ctx)?;
+        Ok(MinimizeRegs {
+            live: LiveRangeAnalysis::default(),
+            rewrites: HashMap::new(),
+            min_width: opts["min-width"].num(),
+        })
+    }
+
+    fn clear_data(&mut self) {
+        self.live = LiveRangeAnalysis::default();
+        self.rewrites = HashMap::new();
+    }
 }
 
 impl ShareComponents for MinimizeRegs {
@@ -50,6 +82,7 @@ impl ShareComponents for MinimizeRegs {
     fn cell_filter(&self, cell: &ir::Cell) -> bool {
         if let Some(name) = cell.type_name() {
             name == "std_reg"
+                && cell.get_parameter("WIDTH").unwrap_or(0) >= self.min_width
         } else {
             false
         }
@@ -5,6 +5,8 @@ mod collapse_control;
 mod comb_prop;
 mod compile_empty;
 mod compile_invoke;
+mod compile_ref;
+mod compile_static;
 mod component_iniliner;
 mod component_interface;
 mod dead_cell_removal;
@@ -15,6 +17,7 @@ mod group_to_invoke;
 mod hole_inliner;
 mod infer_static_timing;
 mod lower_guards;
+mod lower_selects;
 mod math_utilities;
 mod merge_assign;
 mod minimize_regs;
@@ -39,6 +42,8 @@ pub use collapse_control::CollapseControl;
 pub use comb_prop::CombProp;
 pub use compile_empty::CompileEmpty;
 pub use compile_invoke::CompileInvoke;
+pub use compile_ref::CompileRef;
+pub use compile_static::CompileStatic;
 pub use component_iniliner::ComponentInliner;
 pub use component_interface::ComponentInterface;
 pub use dead_cell_removal::DeadCellRemoval;
@@ -49,6 +54,7 @@ pub use group_to_invoke::GroupToInvoke;
 pub use hole_inliner::HoleInliner;
 pub use infer_static_timing::InferStaticTiming;
 pub use lower_guards::LowerGuards;
+pub use lower_selects::LowerSelects;
 pub use merge_assign::MergeAssign;
 pub use minimize_regs::MinimizeRegs;
 pub use papercut::Papercut;
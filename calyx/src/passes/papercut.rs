@@ -1,5 +1,5 @@
 use crate::analysis;
-use crate::errors::{CalyxResult, Error};
+use crate::errors::{CalyxResult, Diagnostics, Error};
 use crate::ir::traversal::{
     Action, ConstructVisitor, Named, VisResult, Visitor,
 };
@@ -12,7 +12,7 @@ use std::collections::{HashMap, HashSet};
 type ReadTogether = (ir::Id, HashSet<ir::Id>);
 
 /// Pass to check for common errors such as missing assignments to `done` holes
-/// of groups.
+/// of groups. Errors from all the components are reported together.
 pub struct Papercut {
     /// Map from (primitive name) -> Vec<(set of ports)>
     /// When any of the ports in a set is driven, all ports in that set must
@@ -28,6 +28,9 @@ pub struct Papercut {
 
     /// The cells that are driven through continuous assignments
     cont_cells: HashSet<ir::Id>,
+
+    /// Errors found so far.
+    diag: Diagnostics,
 }
 
 impl ConstructVisitor for Papercut {
@@ -40,11 +43,13 @@ impl ConstructVisitor for Papercut {
             write_together,
             read_together,
             cont_cells: HashSet::new(),
+            diag: Diagnostics::default(),
         })
     }
 
     fn clear_data(&mut self) {
-        // Library specifications are shared
+        // Library specifications are shared and errors are reported after all
+        // components have been checked.
         self.cont_cells = HashSet::new();
     }
 }
@@ -77,6 +82,23 @@ fn port_information(
     None
 }
 
+/// If the cell is an instance of a combinational primitive or component,
+/// returns the kind of the prototype and its name.
+fn comb_prototype(cell: &ir::Cell) -> Option<(&'static str, &ir::Id)> {
+    match &cell.prototype {
+        ir::CellType::Primitive {
+            is_comb: true,
+            name,
+            ..
+        } => Some(("primitive", name)),
+        ir::CellType::Component {
+            is_comb: true,
+            name,
+        } => Some(("component", name)),
+        _ => None,
+    }
+}
+
 impl Visitor for Papercut {
     fn start(
         &mut self,
@@ -85,7 +107,10 @@ impl Visitor for Papercut {
         _comps: &[ir::Component],
     ) -> VisResult {
         // If the control program is empty, check that the `done` signal has been assigned to.
-        if let ir::Control::Empty(..) = *comp.control.borrow() {
+        // Combinational components do not have a `done` signal.
+        if !comp.is_comb
+            && matches!(*comp.control.borrow(), ir::Control::Empty(..))
+        {
             let done_use =
                 comp.continuous_assignments.iter().find(|assign_ref| {
                     let assign = assign_ref.dst.borrow();
@@ -94,7 +119,7 @@ impl Visitor for Papercut {
                     assign.name == "done" && !assign.is_hole()
                 });
             if done_use.is_none() {
-                return Err(Error::papercut(format!("Component `{}` has an empty control program and does not assign to the `done` port. Without an assignment to the `done`, the component cannot return control flow.", comp.name)).with_pos(&comp.name));
+                self.diag.err(Error::papercut(format!("Component `{}` has an empty control program and does not assign to the `done` port. Without an assignment to the `done`, the component cannot return control flow.", comp.name)).with_pos(&comp.name));
             }
         }
 
@@ -146,8 +171,10 @@ impl Visitor for Papercut {
                                         read,
                                         missing,
                                         comp_type);
-                            return Err(Error::papercut(msg)
-                                .with_pos(&group.attributes));
+                            self.diag.err(
+                                Error::papercut(msg)
+                                    .with_pos(&group.attributes),
+                            );
                         }
                     }
                 }
@@ -177,8 +204,10 @@ impl Visitor for Papercut {
                                         first,
                                         missing,
                                         comp_type);
-                            return Err(Error::papercut(msg)
-                                .with_pos(&group.attributes));
+                            self.diag.err(
+                                Error::papercut(msg)
+                                    .with_pos(&group.attributes),
+                            );
                         }
                     }
                 }
@@ -207,19 +236,13 @@ impl Visitor for Papercut {
             if let ir::PortParent::Cell(cell_wref) = &port.parent {
                 let cell_ref = cell_wref.upgrade();
                 let cell = cell_ref.borrow();
-                if let ir::CellType::Primitive {
-                    is_comb,
-                    name: prim_name,
-                    ..
-                } = &cell.prototype
-                {
+                if let Some((kind, proto_name)) = comb_prototype(&cell) {
                     // If the cell is combinational and not driven by continuous assignments
-                    if *is_comb && !self.cont_cells.contains(cell.name()) {
-                        let msg = format!("Port `{}.{}` is an output port on combinational primitive `{}` and will always output 0. Add a `with` statement to the `while` statement to ensure it has a valid value during execution.", cell.name(), port.name, prim_name);
+                    if !self.cont_cells.contains(cell.name()) {
+                        let msg = format!("Port `{}.{}` is an output port on combinational {} `{}` and will always output 0. Add a `with` statement to the `while` statement to ensure it has a valid value during execution.", cell.name(), port.name, kind, proto_name);
                         // Use dummy Id to get correct source location for error
-                        return Err(
-                            Error::papercut(msg).with_pos(&s.attributes)
-                        );
+                        self.diag
+                            .err(Error::papercut(msg).with_pos(&s.attributes));
                     }
                 }
             }
@@ -239,23 +262,22 @@ impl Visitor for Papercut {
             if let ir::PortParent::Cell(cell_wref) = &port.parent {
                 let cell_ref = cell_wref.upgrade();
                 let cell = cell_ref.borrow();
-                if let ir::CellType::Primitive {
-                    is_comb,
-                    name: prim_name,
-                    ..
-                } = &cell.prototype
-                {
+                if let Some((kind, proto_name)) = comb_prototype(&cell) {
                     // If the cell is combinational and not driven by continuous assignments
-                    if *is_comb && !self.cont_cells.contains(cell.name()) {
-                        let msg = format!("Port `{}.{}` is an output port on combinational primitive `{}` and will always output 0. Add a `with` statement to the `if` statement to ensure it has a valid value during execution.", cell.name(), port.name, prim_name);
+                    if !self.cont_cells.contains(cell.name()) {
+                        let msg = format!("Port `{}.{}` is an output port on combinational {} `{}` and will always output 0. Add a `with` statement to the `if` statement to ensure it has a valid value during execution.", cell.name(), port.name, kind, proto_name);
                         // Use dummy Id to get correct source location for error
-                        return Err(
-                            Error::papercut(msg).with_pos(&s.attributes)
-                        );
+                        self.diag
+                            .err(Error::papercut(msg).with_pos(&s.attributes));
                     }
                 }
             }
         }
         Ok(Action::Continue)
     }
+
+    fn finish_context(&mut self, _ctx: &mut ir::Context) -> VisResult {
+        self.diag.take().into_result()?;
+        Ok(Action::Continue)
+    }
 }
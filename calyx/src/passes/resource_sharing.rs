@@ -36,13 +36,14 @@ impl ConstructVisitor for ResourceSharing {
         let mut shareable_components = HashSet::new();
         // add share=1 primitives to the shareable_components set
         for prim in ctx.lib.signatures() {
-            if let Some(&1) = prim.attributes.get("share") {
+            if let Some(1) = prim.attributes.get("share") {
                 shareable_components.insert(prim.name.clone());
             }
         }
-        // add share=1 user defined components to the shareable_components set
+        // add share=1 user defined components and combinational components to
+        // the shareable_components set
         for comp in &ctx.components {
-            if let Some(&1) = comp.attributes.get("share") {
+            if comp.is_comb || comp.attributes.get("share") == Some(1) {
                 shareable_components.insert(comp.name.clone());
             }
         }
@@ -1,6 +1,7 @@
 //! Defines common traits for methods that attempt to share components.
 use crate::{
     analysis::{GraphColoring, ScheduleConflicts},
+    errors::WithPos,
     ir,
 };
 use ir::{
@@ -167,6 +168,18 @@ impl<T: ShareComponents> Visitor for T {
             }
         }
 
+        // Shared cells that were generated by a pass take on the source
+        // location of a cell they replace.
+        for (old, new) in &coloring {
+            if new.borrow().copy_span().is_none() {
+                if let Some(span) =
+                    comp.find_cell(old).and_then(|c| c.borrow().copy_span())
+                {
+                    new.borrow_mut().attributes.set_span(Some(span));
+                }
+            }
+        }
+
         // Rewrite assignments using the coloring generated.
         let empty_map: ir::rewriter::PortRewriteMap = HashMap::new();
         let rewriter = ir::Rewriter::new(&coloring, &empty_map);
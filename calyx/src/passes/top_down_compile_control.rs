@@ -1,6 +1,8 @@
 use super::math_utilities::get_bit_width_from;
-use crate::errors::CalyxResult;
-use crate::ir::traversal::ConstructVisitor;
+use crate::errors::{CalyxResult, WithPos};
+use crate::ir::traversal::{
+    ComponentLocal, ConstructVisitor, ParseVal, PassOpt,
+};
 use crate::ir::GetAttributes;
 use crate::{build_assignments, guard, passes, structure};
 use crate::{
@@ -55,7 +57,7 @@ fn control_exits(
         ir::Control::Enable(ir::Enable { group, attributes }) => {
             if is_exit {
                 let cur_state = attributes.get(NODE_ID).unwrap();
-                exits.push((*cur_state, Rc::clone(group)))
+                exits.push((cur_state, Rc::clone(group)))
             }
         }
         ir::Control::Seq(ir::Seq { stmts, .. }) => {
@@ -76,13 +78,14 @@ fn control_exits(
         ),
         ir::Control::Invoke(_) => unreachable!("`invoke` statements should have been compiled away. Run `{}` before this pass.", passes::CompileInvoke::name()),
         ir::Control::Empty(_) => unreachable!("`empty` statements should have been compiled away. Run `{}` before this pass.", passes::CompileEmpty::name()),
-        ir::Control::Par(_) => unreachable!(),
+        ir::Control::Par(_) | ir::Control::Repeat(_) => unreachable!(),
+        ir::Control::StaticSeq(_) | ir::Control::StaticPar(_) | ir::Control::StaticIf(_) => unreachable!("`static` control should have been compiled away. Run `{}` before this pass.", passes::CompileStatic::name()),
     }
 }
 
-/// Adds the @NODE_ID attribute to [ir::Enable] and [ir::Par].
+/// Adds the @NODE_ID attribute to [ir::Enable], [ir::Par], and [ir::Repeat].
 /// Each [ir::Enable] gets a unique label within the context of a child of
-/// a [ir::Par] node.
+/// a [ir::Par] node or the body of a [ir::Repeat] node.
 ///
 /// ## Example:
 /// ```
@@ -115,6 +118,11 @@ fn compute_unique_ids(con: &mut ir::Control, cur_state: u64) -> u64 {
             });
             cur_state + 1
         }
+        ir::Control::Repeat(ir::Repeat { body, attributes, .. }) => {
+            attributes.insert(NODE_ID, cur_state);
+            compute_unique_ids(body, 0);
+            cur_state + 1
+        }
         ir::Control::Seq(ir::Seq { stmts, .. }) => {
             let mut cur = cur_state;
             stmts.iter_mut().for_each(|stmt| {
@@ -151,6 +159,7 @@ fn compute_unique_ids(con: &mut ir::Control, cur_state: u64) -> u64 {
         }
         ir::Control::Empty(_) => cur_state,
         ir::Control::Invoke(_) => unreachable!("`invoke` statements should have been compiled away. Run `{}` before this pass.", passes::CompileInvoke::name()),
+        ir::Control::StaticSeq(_) | ir::Control::StaticPar(_) | ir::Control::StaticIf(_) => unreachable!("`static` control should have been compiled away. Run `{}` before this pass.", passes::CompileStatic::name()),
     }
 }
 
@@ -322,7 +331,7 @@ fn calculate_states_recur(
     match con {
         // See explanation of FSM states generated in [ir::TopDownCompileControl].
         ir::Control::Enable(ir::Enable { group, attributes }) => {
-            let cur_state = *attributes.get(NODE_ID).unwrap_or_else(|| panic!("Group `{}` does not have node_id information", group.borrow().name()));
+            let cur_state = attributes.get(NODE_ID).unwrap_or_else(|| panic!("Group `{}` does not have node_id information", group.borrow().name()));
             // If there is exactly one previous transition state with a `true`
             // guard, then merge this state into previous state.
             // This happens when the first control statement is an enable not
@@ -335,6 +344,7 @@ fn calculate_states_recur(
 
             let not_done = !guard!(group["done"]);
             let signal_on = builder.add_constant(1, 1);
+            builder.set_span(attributes.copy_span());
 
             // Activate this group in the current state
             let mut en_go = build_assignments!(builder;
@@ -471,9 +481,10 @@ fn calculate_states_recur(
 
             Ok(all_prevs)
         }
-        ir::Control::Par(_) => unreachable!(),
+        ir::Control::Par(_) | ir::Control::Repeat(_) => unreachable!(),
         ir::Control::Invoke(_) => unreachable!("`invoke` statements should have been compiled away. Run `{}` before this pass.", passes::CompileInvoke::name()),
         ir::Control::Empty(_) => unreachable!("`empty` statements should have been compiled away. Run `{}` before this pass.", passes::CompileEmpty::name()),
+        ir::Control::StaticSeq(_) | ir::Control::StaticPar(_) | ir::Control::StaticIf(_) => unreachable!("`static` control should have been compiled away. Run `{}` before this pass.", passes::CompileStatic::name()),
     }
 }
 
@@ -594,6 +605,10 @@ fn calculate_states(
 /// If we tie the children to one top-level FSM, their transitions would become interdependent and
 /// reduce available concurrency.
 ///
+/// ## Compiling `repeat` programs
+/// The body of a `repeat` is compiled into its own FSM-based controller which is run to completion
+/// `num_repeats` times. An internal counter register tracks the number of completed iterations.
+///
 /// ## Compilation guarantee
 /// At the end of this pass, the control program will have no more than one
 /// group enable in it.
@@ -609,11 +624,11 @@ impl ConstructVisitor for TopDownCompileControl {
     where
         Self: Sized + Named,
     {
-        let opts = Self::get_opts(&["dump-fsm", "early-transitions"], ctx);
+        let opts = Self::get_opts(ctx)?;
 
         Ok(TopDownCompileControl {
-            dump_fsm: opts[0],
-            early_transitions: opts[1],
+            dump_fsm: opts["dump-fsm"].bool(),
+            early_transitions: opts["early-transitions"].bool(),
         })
     }
 
@@ -630,9 +645,71 @@ impl Named for TopDownCompileControl {
     fn description() -> &'static str {
         "Top-down compilation for removing control constructs"
     }
+
+    fn opts() -> Vec<PassOpt> {
+        vec![
+            PassOpt::new(
+                "dump-fsm",
+                "print the generated FSMs to stdout",
+                ParseVal::Bool(false),
+            ),
+            PassOpt::new(
+                "early-transitions",
+                "start the next group in the cycle the previous one is done",
+                ParseVal::Bool(false),
+            ),
+        ]
+    }
+}
+
+impl TopDownCompileControl {
+    /// Compile a control sub-program into a group that runs it to completion.
+    /// Enables are not compiled and their group is returned directly.
+    fn compile_sub_program(
+        &self,
+        con: &ir::Control,
+        builder: &mut ir::Builder,
+    ) -> CalyxResult<RRC<ir::Group>> {
+        match con {
+            // Do not compile enables
+            ir::Control::Enable(ir::Enable { group, .. }) => {
+                Ok(Rc::clone(group))
+            }
+            // Compile complex schedule and return the group.
+            _ => {
+                let schedule =
+                    calculate_states(con, builder, self.early_transitions)?;
+                // The FSM is attributed to the control program it implements.
+                builder.set_span(con.copy_span());
+                let group = builder.add_group("tdcc");
+                if self.dump_fsm {
+                    schedule.display(format!(
+                        "{}:{}",
+                        builder.component.name,
+                        group.borrow().name()
+                    ));
+                }
+                Ok(schedule.realize_schedule(group, builder))
+            }
+        }
+    }
+}
+
+impl ComponentLocal for TopDownCompileControl {
+    fn visit_in_parallel(&self) -> bool {
+        // Printing the FSMs requires visiting the components in order.
+        !self.dump_fsm
+    }
+
+    /// Only the options are kept between components.
+    fn merge(&mut self, _visitor: Self) {}
 }
 
 impl Visitor for TopDownCompileControl {
+    fn do_pass(&mut self, context: &mut ir::Context) -> CalyxResult<()> {
+        self.do_pass_parallel(context)
+    }
+
     fn start(
         &mut self,
         comp: &mut ir::Component,
@@ -663,6 +740,7 @@ impl Visitor for TopDownCompileControl {
         _comps: &[ir::Component],
     ) -> VisResult {
         let mut builder = ir::Builder::new(comp, sigs);
+        builder.set_span(s.attributes.copy_span());
 
         // Compilation group
         let par_group = builder.add_group("par");
@@ -676,29 +754,8 @@ impl Visitor for TopDownCompileControl {
 
         // For each child, build the enabling logic.
         for con in &s.stmts {
-            let group = match con {
-                // Do not compile enables
-                ir::Control::Enable(ir::Enable { group, .. }) => {
-                    Rc::clone(group)
-                }
-                // Compile complex schedule and return the group.
-                _ => {
-                    let schedule = calculate_states(
-                        con,
-                        &mut builder,
-                        self.early_transitions,
-                    )?;
-                    let group = builder.add_group("tdcc");
-                    if self.dump_fsm {
-                        schedule.display(format!(
-                            "{}:{}",
-                            builder.component.name,
-                            group.borrow().name()
-                        ));
-                    }
-                    schedule.realize_schedule(group, &mut builder)
-                }
-            };
+            let group = self.compile_sub_program(con, &mut builder)?;
+            builder.set_span(s.attributes.copy_span());
 
             // Build circuitry to enable and disable this group.
             structure!(builder;
@@ -750,7 +807,70 @@ impl Visitor for TopDownCompileControl {
         // Add NODE_ID to compiled group.
         let mut en = ir::Control::enable(par_group);
         let node_id = s.attributes.get(NODE_ID).unwrap();
-        en.get_mut_attributes().unwrap().insert(NODE_ID, *node_id);
+        let attrs = en.get_mut_attributes().unwrap();
+        attrs.insert(NODE_ID, node_id);
+        attrs.set_span(s.attributes.copy_span());
+
+        Ok(Action::Change(en))
+    }
+
+    /// Compile the body of `repeat` separately and run it `num_repeats`
+    /// times using a counter.
+    fn finish_repeat(
+        &mut self,
+        s: &mut ir::Repeat,
+        comp: &mut ir::Component,
+        sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        let mut builder = ir::Builder::new(comp, sigs);
+        let body = self.compile_sub_program(&s.body, &mut builder)?;
+        builder.set_span(s.attributes.copy_span());
+
+        // Compilation group
+        let repeat_group = builder.add_group("repeat");
+        let idx_size = get_bit_width_from(s.num_repeats + 1);
+        structure!(builder;
+            let idx = prim std_reg(idx_size);
+            let incr = prim std_add(idx_size);
+            let one = constant(1, idx_size);
+            let zero = constant(0, idx_size);
+            let num_repeats = constant(s.num_repeats, idx_size);
+            let signal_on = constant(1, 1);
+        );
+
+        // Run the body until the counter reaches `num_repeats` and increment
+        // the counter every time the body is done.
+        let not_finished = guard!(idx["out"]).lt(guard!(num_repeats["out"]));
+        let body_go = not_finished & !guard!(body["done"]);
+        let body_done = guard!(body["done"]);
+        let finished = guard!(idx["out"]).eq(guard!(num_repeats["out"]));
+        let mut assigns = build_assignments!(builder;
+            body["go"] = body_go ? signal_on["out"];
+            incr["left"] = ? idx["out"];
+            incr["right"] = ? one["out"];
+            idx["in"] = body_done ? incr["out"];
+            idx["write_en"] = body_done ? signal_on["out"];
+            repeat_group["done"] = finished ? signal_on["out"];
+        );
+        repeat_group.borrow_mut().assignments.append(&mut assigns);
+
+        // CLEANUP: Reset the counter once the group is finished.
+        let mut cleanup = build_assignments!(builder;
+            idx["in"] = finished ? zero["out"];
+            idx["write_en"] = finished ? signal_on["out"];
+        );
+        builder
+            .component
+            .continuous_assignments
+            .append(&mut cleanup);
+
+        // Add NODE_ID to compiled group.
+        let mut en = ir::Control::enable(repeat_group);
+        let node_id = s.attributes.get(NODE_ID).unwrap();
+        let attrs = en.get_mut_attributes().unwrap();
+        attrs.insert(NODE_ID, node_id);
+        attrs.set_span(s.attributes.copy_span());
 
         Ok(Action::Change(en))
     }
@@ -770,6 +890,7 @@ impl Visitor for TopDownCompileControl {
             &mut builder,
             self.early_transitions,
         )?;
+        builder.set_span(control.borrow().copy_span());
         let group = builder.add_group("tdcc");
         if self.dump_fsm {
             schedule.display(format!(
@@ -1,6 +1,6 @@
 use super::math_utilities::get_bit_width_from;
 use crate::errors::{CalyxResult, Error};
-use crate::ir::traversal::ConstructVisitor;
+use crate::ir::traversal::{ConstructVisitor, ParseVal, PassOpt};
 use crate::ir::{
     self,
     traversal::{Action, Named, VisResult, Visitor},
@@ -21,14 +21,16 @@ type Range = (u64, u64);
 /// 2. `transitions`: Transitions for the FSM registers. A static FSM normally
 ///    transitions from `state` to `state + 1`. However, special transitions
 ///    are needed for loops, conditionals, and reseting the FSM.
-struct Schedule<'a> {
-    enables: HashMap<Range, Vec<ir::Assignment>>,
-    transitions: HashSet<(u64, u64, ir::Guard)>,
-    builder: &'a mut ir::Builder<'a>,
+///
+/// Also used by [passes::CompileStatic] to compile `static` control.
+pub(super) struct Schedule<'a> {
+    pub(super) enables: HashMap<Range, Vec<ir::Assignment>>,
+    pub(super) transitions: HashSet<(u64, u64, ir::Guard)>,
+    pub(super) builder: &'a mut ir::Builder<'a>,
 }
 
 impl<'a> Schedule<'a> {
-    fn new(builder: &'a mut ir::Builder<'a>) -> Self {
+    pub(super) fn new(builder: &'a mut ir::Builder<'a>) -> Self {
         Self {
             enables: HashMap::default(),
             transitions: HashSet::default(),
@@ -41,7 +43,7 @@ impl<'a> Schedule<'a> {
         self.transitions.iter().map(|(_, e, _)| *e).max().unwrap()
     }
 
-    fn display(&self) {
+    pub(super) fn display(&self) {
         let out = &mut std::io::stdout();
         let (uncond, cond) =
             Self::calculate_runs(self.transitions.iter().cloned());
@@ -128,7 +130,7 @@ impl<'a> Schedule<'a> {
         }
     }
 
-    fn realize_schedule(self) -> RRC<ir::Group> {
+    pub(super) fn realize_schedule(self) -> RRC<ir::Group> {
         let final_state = self.last_state();
         let builder = self.builder;
         let (unconditional, conditional) =
@@ -254,12 +256,20 @@ impl Schedule<'_> {
         ir::Control::While(w) => {
             self.while_calculate_states(w, cur_state, pre_guard)
         }
+        ir::Control::Repeat(r) => {
+            self.repeat_calculate_states(r, cur_state, pre_guard)
+        }
         ir::Control::Invoke(_) => unreachable!(
             "`invoke` statements should have been compiled away. Run `{}` before this pass.",
             passes::CompileInvoke::name()),
         ir::Control::Empty(_) => unreachable!(
             "`empty` statements should have been compiled away. Run `{}` before this pass.",
             passes::CompileEmpty::name()),
+        ir::Control::StaticSeq(_)
+        | ir::Control::StaticPar(_)
+        | ir::Control::StaticIf(_) => unreachable!(
+            "`static` control should have been compiled away. Run `{}` before this pass.",
+            passes::CompileStatic::name()),
     }
     }
 
@@ -392,7 +402,7 @@ impl Schedule<'_> {
 
         let mut body_exit = cur_state;
 
-        for _ in 0..*con.attributes.get("bound").unwrap() {
+        for _ in 0..con.attributes.get("bound").unwrap() {
             let preds = self.calculate_states(
                 &con.body,
                 body_exit,
@@ -409,6 +419,25 @@ impl Schedule<'_> {
         Ok(vec![(body_exit, pre_guard.clone())])
     }
 
+    fn repeat_calculate_states(
+        &mut self,
+        con: &ir::Repeat,
+        cur_state: u64,
+        pre_guard: &ir::Guard,
+    ) -> CalyxResult<Vec<PredEdge>> {
+        // Schedule the body like a `seq` with `num_repeats` copies of it.
+        let mut preds = vec![];
+        let default_pred = (cur_state, pre_guard.clone());
+        for _ in 0..con.num_repeats {
+            let new_state = self.seq_add_transitions(&preds, &default_pred);
+            preds = self.calculate_states(&con.body, new_state, pre_guard)?;
+        }
+
+        self.seq_add_transitions(&preds, &default_pred);
+
+        Ok(preds)
+    }
+
     /// Compiled to:
     /// ```
     /// group[go] = (fsm >= cur_start & fsm < cur_state + static) & pre_guard ? 1'd1;
@@ -461,6 +490,7 @@ impl Schedule<'_> {
 ///
 /// `while` control blocks can only be statically compiled when they additionally have a `@bound`
 /// annotation which mentions the expected number of times a loop will iterate.
+/// `repeat` control blocks are statically compiled when their body is static.
 pub struct TopDownStaticTiming {
     /// Print out the FSM representation to STDOUT.
     dump_fsm: bool,
@@ -471,9 +501,11 @@ impl ConstructVisitor for TopDownStaticTiming {
     where
         Self: Sized + Named,
     {
-        let opts = Self::get_opts(&["dump-fsm"], ctx);
+        let opts = Self::get_opts(ctx)?;
 
-        Ok(TopDownStaticTiming { dump_fsm: opts[0] })
+        Ok(TopDownStaticTiming {
+            dump_fsm: opts["dump-fsm"].bool(),
+        })
     }
 
     fn clear_data(&mut self) {
@@ -489,6 +521,14 @@ impl Named for TopDownStaticTiming {
     fn description() -> &'static str {
         "Top-down latency-sensitive compilation for removing control constructs"
     }
+
+    fn opts() -> Vec<PassOpt> {
+        vec![PassOpt::new(
+            "dump-fsm",
+            "print the generated FSMs to stdout",
+            ParseVal::Bool(false),
+        )]
+    }
 }
 
 impl Visitor for TopDownStaticTiming {
@@ -583,6 +623,36 @@ impl Visitor for TopDownStaticTiming {
         Ok(Action::Change(ir::Control::enable(group)))
     }
 
+    fn start_repeat(
+        &mut self,
+        con: &mut ir::Repeat,
+        comp: &mut ir::Component,
+        sigs: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        let time_option = con.attributes.get("static");
+
+        // If sub-tree is not static, skip this node.
+        if time_option.is_none() {
+            return Ok(Action::Continue);
+        }
+
+        // Compile control program and save schedule.
+        let mut builder = ir::Builder::new(comp, sigs);
+        let mut schedule = Schedule::new(&mut builder);
+        schedule.repeat_calculate_states(con, 0, &ir::Guard::True)?;
+
+        // Dump FSM if requested.
+        if self.dump_fsm {
+            schedule.display();
+        }
+
+        // Realize the schedule in a replacement control group.
+        let group = schedule.realize_schedule();
+
+        Ok(Action::Change(ir::Control::enable(group)))
+    }
+
     fn start_if(
         &mut self,
         con: &mut ir::If,
@@ -1,11 +1,30 @@
+use crate::errors::CalyxResult;
 use crate::ir::{
     self,
-    traversal::{Action, Named, VisResult, Visitor},
+    traversal::{
+        Action, ConstructVisitor, Named, ParseVal, PassOpt, VisResult, Visitor,
+    },
 };
 
-/// Fully unroll all `while` loops with a given `@bound`.
-#[derive(Default)]
-pub struct UnrollBounded;
+/// Unroll all `while` loops with a given `@bound`.
+///
+/// By default, loops are fully unrolled. With `-x unroll-bound:factor=<n>`,
+/// the body is only copied `n` times and the copies are run using a
+/// `repeat`. For example, with `factor=2`:
+/// ```calyx
+/// @bound(5) while lt.out with cond { body; }
+/// ```
+/// is transformed into:
+/// ```calyx
+/// seq {
+///   repeat 2 { seq { body; body; } }
+///   seq { body; }
+/// }
+/// ```
+pub struct UnrollBounded {
+    /// Number of copies of the loop body. Zero fully unrolls the loop.
+    factor: u64,
+}
 
 impl Named for UnrollBounded {
     fn name() -> &'static str {
@@ -15,6 +34,27 @@ impl Named for UnrollBounded {
     fn description() -> &'static str {
         "fully unroll loops with a given @bound"
     }
+
+    fn opts() -> Vec<PassOpt> {
+        vec![PassOpt::new(
+            "factor",
+            "number of copies of the loop body (0 fully unrolls the loop)",
+            ParseVal::Num(0),
+        )]
+    }
+}
+
+impl ConstructVisitor for UnrollBounded {
+    fn from(ctx: &ir::Context) -> CalyxResult<Self> {
+        let opts = Self::get_opts(ctx)?;
+        Ok(UnrollBounded {
+            factor: opts["factor"].num(),
+        })
+    }
+
+    fn clear_data(&mut self) {
+        /* All data can be transferred between components */
+    }
 }
 
 impl Visitor for UnrollBounded {
@@ -28,12 +68,23 @@ impl Visitor for UnrollBounded {
         if let Some(bound) = s.attributes.get("bound") {
             let body =
                 *std::mem::replace(&mut s.body, Box::new(ir::Control::empty()));
-            let nb = ir::Control::seq(
-                (0..*bound)
-                    .into_iter()
-                    .map(|_| ir::Control::clone(&body))
-                    .collect(),
-            );
+            let copies = |n: u64| {
+                ir::Control::seq(
+                    (0..n).map(|_| ir::Control::clone(&body)).collect(),
+                )
+            };
+            let nb = if self.factor == 0 || self.factor >= bound {
+                copies(bound)
+            } else {
+                let mut stmts = vec![ir::Control::repeat(
+                    bound / self.factor,
+                    Box::new(copies(self.factor)),
+                )];
+                if bound % self.factor != 0 {
+                    stmts.push(copies(bound % self.factor));
+                }
+                ir::Control::seq(stmts)
+            };
             Ok(Action::Change(nb))
         } else {
             Ok(Action::Continue)
@@ -1,18 +1,26 @@
 use itertools::Itertools;
 
-use crate::errors::{CalyxResult, Error, WithPos};
-use crate::ir::traversal::{Action, Named, VisResult, Visitor};
+use crate::analysis::{ReadWriteSet, StaticLatency};
+use crate::errors::{CalyxResult, Diagnostics, Error, WithPos};
+use crate::ir::traversal::{
+    Action, ConstructVisitor, Named, VisResult, Visitor,
+};
 use crate::ir::{
     self, CloneName, Component, LibrarySignatures, RESERVED_NAMES,
 };
 use std::collections::HashSet;
 
 /// Pass to check if the program is well-formed.
+/// Errors from all the components are reported together.
 ///
 /// Catches the following errors:
 /// 1. Programs that don't use a defined group or combinational group.
 /// 2. Groups that don't write to their done signal.
 /// 3. Groups that write to another group's done signal.
+/// 4. Combinational components that define groups or instantiate stateful
+///    cells, and invokes of combinational components.
+/// 5. `static<n>` groups whose latency cannot be derived from their body or
+///    does not match `n`.
 pub struct WellFormed {
     /// Reserved names
     reserved_names: HashSet<String>,
@@ -20,18 +28,30 @@ pub struct WellFormed {
     used_groups: HashSet<ir::Id>,
     /// Names of combinational groups used in the control.
     used_comb_groups: HashSet<ir::Id>,
+    /// Infers the latency of `static` groups.
+    static_latency: StaticLatency,
+    /// Errors found so far.
+    diag: Diagnostics,
 }
 
-impl Default for WellFormed {
-    fn default() -> Self {
+impl ConstructVisitor for WellFormed {
+    fn from(ctx: &ir::Context) -> CalyxResult<Self> {
         let reserved_names =
             RESERVED_NAMES.iter().map(|s| s.to_string()).collect();
 
-        WellFormed {
+        Ok(WellFormed {
             reserved_names,
             used_groups: HashSet::new(),
             used_comb_groups: HashSet::new(),
-        }
+            static_latency: StaticLatency::from(&ctx.lib),
+            diag: Diagnostics::default(),
+        })
+    }
+
+    fn clear_data(&mut self) {
+        // Errors are reported after all components have been checked.
+        self.used_groups = HashSet::new();
+        self.used_comb_groups = HashSet::new();
     }
 }
 
@@ -78,6 +98,136 @@ where
     Ok(())
 }
 
+/// Returns an error if a statement in the `static` control statement `kind`
+/// is not statically timed or if the latency of the statement does not match
+/// its `"static"` annotation.
+fn check_static<'a, I>(
+    kind: &str,
+    stmts: I,
+    latency: Option<u64>,
+    attributes: &ir::Attributes,
+) -> CalyxResult<()>
+where
+    I: Iterator<Item = &'a ir::Control>,
+{
+    for stmt in stmts {
+        if stmt.static_latency().is_some() {
+            continue;
+        }
+        let msg = match stmt {
+            ir::Control::Enable(ir::Enable { group, .. }) => format!(
+                "Group `{}` does not have a static latency. Use `static<n> group` to define its latency.",
+                group.borrow().name()
+            ),
+            _ => "Only group enables, `static` control, and `repeat` with a statically timed body can be used."
+                .to_string(),
+        };
+        return Err(Error::malformed_control(format!(
+            "`{}` can only contain statically timed control. {}",
+            kind, msg
+        ))
+        .with_pos(stmt));
+    }
+
+    // Latency is always defined when all statements are statically timed.
+    let latency = latency.unwrap();
+    match attributes.get("static") {
+        Some(annotated) if annotated != latency => {
+            Err(Error::malformed_control(format!(
+                "`{}` has latency {} but is annotated with \"static\"={}",
+                kind, latency, annotated
+            ))
+            .with_pos(attributes))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns an error if the invoke refers to ports or `ref` cells that are not
+/// defined by the invoked instance.
+fn check_invoke(s: &ir::Invoke, comps: &[ir::Component]) -> CalyxResult<()> {
+    // Only refers to ports defined in the invoked instance.
+    let cell = s.comp.borrow();
+    if cell.is_comb_cell() {
+        return Err(Error::malformed_control(format!(
+            "Cannot invoke combinational cell `{}`",
+            cell.name()
+        ))
+        .with_pos(&s.attributes));
+    }
+    let ports: HashSet<_> =
+        cell.ports.iter().map(|p| p.borrow().name.clone()).collect();
+
+    s.inputs
+        .iter()
+        .chain(s.outputs.iter())
+        .try_for_each(|(port, _)| {
+            if !ports.contains(port) {
+                Err(Error::malformed_structure(format!(
+                    "`{}` does not have port named `{}`",
+                    cell.name(),
+                    port
+                ))
+                .with_pos(&s.attributes))
+            } else {
+                Ok(())
+            }
+        })?;
+
+    // `ref` cell bindings refer to `ref` cells of the invoked component
+    // and provide cells of the same type.
+    let callee = match &cell.prototype {
+        ir::CellType::Component { name, .. } => {
+            comps.iter().find(|c| c.name == *name)
+        }
+        _ => None,
+    };
+    let ref_cells = callee
+        .map(|c| {
+            c.cells
+                .iter()
+                .filter(|cr| cr.borrow().is_reference())
+                .cloned()
+                .collect_vec()
+        })
+        .unwrap_or_default();
+    for (name, bound) in &s.ref_cells {
+        let ref_cell = ref_cells
+            .iter()
+            .find(|cr| cr.borrow().name() == name)
+            .ok_or_else(|| {
+            Error::malformed_control(format!(
+                "`{}` does not have a ref cell named `{}`",
+                cell.name(),
+                name
+            ))
+            .with_pos(&s.attributes)
+        })?;
+        if ref_cell.borrow().prototype != bound.borrow().prototype {
+            return Err(Error::malformed_control(format!(
+                "Cell `{}` bound to ref cell `{}` has a different type",
+                bound.borrow().name(),
+                name
+            ))
+            .with_pos(&s.attributes));
+        }
+    }
+    if let Some(unbound) = ref_cells.iter().find(|cr| {
+        !s.ref_cells
+            .iter()
+            .any(|(name, _)| cr.borrow().name() == name)
+    }) {
+        return Err(Error::malformed_control(format!(
+            "Invoke of `{}` does not bind ref cell `{}`",
+            cell.name(),
+            unbound.borrow().name()
+        ))
+        .with_pos(&s.attributes));
+    }
+
+    Ok(())
+}
+
 impl Visitor for WellFormed {
     fn start(
         &mut self,
@@ -85,64 +235,143 @@ impl Visitor for WellFormed {
         _ctx: &LibrarySignatures,
         _comps: &[ir::Component],
     ) -> VisResult {
+        let prev_errors = self.diag.len();
+
         // Check if any of the cells use a reserved name.
         for cell_ref in comp.cells.iter() {
             let cell = cell_ref.borrow();
             if self.reserved_names.contains(&cell.name().id) {
-                return Err(Error::reserved_name(cell.clone_name())
-                    .with_pos(cell.name()));
+                self.diag.err(
+                    Error::reserved_name(cell.clone_name())
+                        .with_pos(cell.name()),
+                );
+            }
+        }
+
+        // Combinational components may only use combinational cells and
+        // continuous assignments.
+        if comp.is_comb {
+            for cell_ref in
+                comp.cells.iter().filter(|c| !c.borrow().is_comb_cell())
+            {
+                let cell = cell_ref.borrow();
+                self.diag.err(Error::malformed_structure(format!(
+                    "Combinational component `{}` instantiates non-combinational cell `{}`",
+                    comp.name,
+                    cell.name()
+                ))
+                .with_pos(cell.name()));
+            }
+            for group in comp.groups.iter() {
+                let group = group.borrow();
+                self.diag.err(Error::malformed_structure(format!(
+                    "Combinational component `{}` defines group `{}`. Use continuous assignments instead.",
+                    comp.name,
+                    group.name()
+                ))
+                .with_pos(&group.attributes));
+            }
+            for group in comp.comb_groups.iter() {
+                let group = group.borrow();
+                self.diag.err(Error::malformed_structure(format!(
+                    "Combinational component `{}` defines combinational group `{}`. Use continuous assignments instead.",
+                    comp.name,
+                    group.name()
+                ))
+                .with_pos(&group.attributes));
             }
         }
 
         // For each non-combinational group, check if there is at least one write to the done
         // signal of that group and that the write is to the group's done signal.
-        comp.groups.iter().try_for_each(|group_ref| {
+        for group_ref in comp.groups.iter() {
             let group = group_ref.borrow();
             let gname = group.name();
             // Find an assignment writing to this group's done condition.
-            let done = group.assignments.iter().filter(|assign| {
-                let dst = assign.dst.borrow();
-                dst.is_hole()
-                    && dst.name == "done"
-            }).map(|assign| {
+            let mut has_done = false;
+            for assign in &group.assignments {
                 let dst = assign.dst.borrow();
+                if !(dst.is_hole() && dst.name == "done") {
+                    continue;
+                }
+                has_done = true;
                 if gname != &dst.get_parent_name() {
-                    Err(Error::malformed_structure(
+                    self.diag.err(Error::malformed_structure(
                             format!("Group `{}` refers to the done condition of another group (`{}`).",
                             group.name(),
-                            dst.get_parent_name())).with_pos(&dst.attributes))
-                } else {
-                    Ok(())
+                            dst.get_parent_name())).with_pos(&dst.attributes));
+                }
+            }
+            if !has_done {
+                self.diag.err(
+                    Error::malformed_structure(format!(
+                        "No writes to the `done' hole for group `{gname}'",
+                    ))
+                    .with_pos(&group.attributes),
+                );
+            }
+            // The latency of a `static<n>` group must follow from its body.
+            // Groups that use the component's signature depend on the
+            // environment, so their annotation is trusted unless it
+            // contradicts the inferred latency.
+            if let Some(latency) = group.attributes.get("static") {
+                let uses_signature =
+                    ReadWriteSet::uses(group.assignments.iter()).any(|cell| {
+                        matches!(
+                            cell.borrow().prototype,
+                            ir::CellType::ThisComponent
+                        )
+                    });
+                match self.static_latency.infer(&group) {
+                    Some(inferred) if inferred == latency => (),
+                    None if uses_signature => (),
+                    Some(inferred) => self.diag.err(
+                        Error::malformed_structure(format!(
+                            "Group `{gname}' is annotated with latency {latency} but its body takes {inferred} cycles",
+                        ))
+                        .with_pos(&group.attributes),
+                    ),
+                    None => self.diag.err(
+                        Error::malformed_structure(format!(
+                            "Cannot derive the latency of static group `{gname}' from its body",
+                        ))
+                        .with_pos(&group.attributes),
+                    ),
                 }
-            }).collect::<CalyxResult<Vec<_>>>()?;
-            if done.is_empty() {
-                Err(Error::malformed_structure(format!(
-                    "No writes to the `done' hole for group `{gname}'",
-                )).with_pos(&group.attributes))
-            } else {
-                Ok(())
             }
-        })?;
-
-        // Check for obvious conflicting assignments
-        for gr in comp.groups.iter() {
-            obvious_conflicts(
-                gr.borrow()
-                    .assignments
-                    .iter()
-                    .chain(comp.continuous_assignments.iter()),
-            )?;
         }
-        for cgr in comp.comb_groups.iter() {
-            obvious_conflicts(
-                cgr.borrow()
-                    .assignments
-                    .iter()
-                    .chain(comp.continuous_assignments.iter()),
-            )?;
+
+        // Check for obvious conflicting assignments. Conflicts between
+        // continuous assignments would be reported again for each group so
+        // the groups are only checked if there are none.
+        if self
+            .diag
+            .check(obvious_conflicts(comp.continuous_assignments.iter()))
+            .is_some()
+        {
+            for gr in comp.groups.iter() {
+                self.diag.check(obvious_conflicts(
+                    gr.borrow()
+                        .assignments
+                        .iter()
+                        .chain(comp.continuous_assignments.iter()),
+                ));
+            }
+            for cgr in comp.comb_groups.iter() {
+                self.diag.check(obvious_conflicts(
+                    cgr.borrow()
+                        .assignments
+                        .iter()
+                        .chain(comp.continuous_assignments.iter()),
+                ));
+            }
         }
-        obvious_conflicts(comp.continuous_assignments.iter())?;
 
+        // The checks on the control program assume that the structure is
+        // well-formed.
+        if self.diag.len() > prev_errors {
+            return Ok(Action::Stop);
+        }
         Ok(Action::Continue)
     }
 
@@ -161,17 +390,17 @@ impl Visitor for WellFormed {
             asgn.guard.is_true() && asgn.src.borrow().is_constant(1, 1);
 
         if const_done_assign {
-            return Err(Error::malformed_structure("Group with constant done condition is invalid. Use `comb group` instead to define a combinational group.").with_pos(&group.attributes));
+            self.diag.err(Error::malformed_structure("Group with constant done condition is invalid. Use `comb group` instead to define a combinational group.").with_pos(&group.attributes));
         }
 
         // A group with "static"=0 annotation
         if group
             .attributes
             .get("static")
-            .map(|v| *v == 0)
+            .map(|v| v == 0)
             .unwrap_or(false)
         {
-            return Err(Error::malformed_structure("Group with annotation \"static\"=0 is invalid. Use `comb group` instead to define a combinational group or if the group's done condition is not constant, provide the correct \"static\" annotation.").with_pos(&group.attributes));
+            self.diag.err(Error::malformed_structure("Group with annotation \"static\"=0 is invalid. Use `comb group` instead to define a combinational group or if the group's done condition is not constant, provide the correct \"static\" annotation.").with_pos(&group.attributes));
         }
 
         Ok(Action::Continue)
@@ -182,32 +411,12 @@ impl Visitor for WellFormed {
         s: &mut ir::Invoke,
         _comp: &mut Component,
         _ctx: &LibrarySignatures,
-        _comps: &[ir::Component],
+        comps: &[ir::Component],
     ) -> VisResult {
         if let Some(c) = &s.comb_group {
             self.used_comb_groups.insert(c.clone_name());
         }
-        // Only refers to ports defined in the invoked instance.
-        let cell = s.comp.borrow();
-        let ports: HashSet<_> =
-            cell.ports.iter().map(|p| p.borrow().name.clone()).collect();
-
-        s.inputs
-            .iter()
-            .chain(s.outputs.iter())
-            .try_for_each(|(port, _)| {
-                if !ports.contains(port) {
-                    Err(Error::malformed_structure(format!(
-                        "`{}` does not have port named `{}`",
-                        cell.name(),
-                        port
-                    ))
-                    .with_pos(&s.attributes))
-                } else {
-                    Ok(())
-                }
-            })?;
-
+        self.diag.check(check_invoke(s, comps));
         Ok(Action::Continue)
     }
 
@@ -239,6 +448,54 @@ impl Visitor for WellFormed {
         Ok(Action::Continue)
     }
 
+    fn finish_static_seq(
+        &mut self,
+        s: &mut ir::StaticSeq,
+        _comp: &mut Component,
+        _ctx: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        self.diag.check(check_static(
+            "static seq",
+            s.stmts.iter(),
+            s.latency(),
+            &s.attributes,
+        ));
+        Ok(Action::Continue)
+    }
+
+    fn finish_static_par(
+        &mut self,
+        s: &mut ir::StaticPar,
+        _comp: &mut Component,
+        _ctx: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        self.diag.check(check_static(
+            "static par",
+            s.stmts.iter(),
+            s.latency(),
+            &s.attributes,
+        ));
+        Ok(Action::Continue)
+    }
+
+    fn finish_static_if(
+        &mut self,
+        s: &mut ir::StaticIf,
+        _comp: &mut Component,
+        _ctx: &LibrarySignatures,
+        _comps: &[ir::Component],
+    ) -> VisResult {
+        self.diag.check(check_static(
+            "static if",
+            [&*s.tbranch, &*s.fbranch].iter().copied(),
+            s.latency(),
+            &s.attributes,
+        ));
+        Ok(Action::Continue)
+    }
+
     fn finish(
         &mut self,
         comp: &mut Component,
@@ -259,31 +516,29 @@ impl Visitor for WellFormed {
         // Find unused groups
         let all_groups: HashSet<ir::Id> =
             comp.groups.iter().map(|g| g.clone_name()).collect();
-        if let Some(group) =
-            all_groups.difference(&self.used_groups).into_iter().next()
-        {
+        for group in all_groups.difference(&self.used_groups) {
             let gr = comp.find_group(&group).unwrap();
             let gr = gr.borrow();
-            return Err(
-                Error::unused(group.clone(), "group").with_pos(&gr.attributes)
+            self.diag.err(
+                Error::unused(group.clone(), "group").with_pos(&gr.attributes),
             );
-        };
+        }
 
         let all_comb_groups: HashSet<ir::Id> =
             comp.comb_groups.iter().map(|g| g.clone_name()).collect();
-        if let Some(comb_group) = all_comb_groups
-            .difference(&self.used_comb_groups)
-            .into_iter()
-            .next()
-        {
+        for comb_group in all_comb_groups.difference(&self.used_comb_groups) {
             let cgr = comp.find_comb_group(&comb_group).unwrap();
             let cgr = cgr.borrow();
-            return Err(Error::unused(
-                comb_group.clone(),
-                "combinational group",
-            )
-            .with_pos(&cgr.attributes));
+            self.diag.err(
+                Error::unused(comb_group.clone(), "combinational group")
+                    .with_pos(&cgr.attributes),
+            );
         }
         Ok(Action::Continue)
     }
+
+    fn finish_context(&mut self, _ctx: &mut ir::Context) -> VisResult {
+        self.diag.take().into_result()?;
+        Ok(Action::Continue)
+    }
 }
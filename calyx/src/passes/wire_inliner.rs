@@ -1,8 +1,10 @@
 use crate::{
-    build_assignments, guard,
+    build_assignments,
+    errors::CalyxResult,
+    guard,
     ir::{self, LibrarySignatures},
     ir::{
-        traversal::{Action, Named, VisResult, Visitor},
+        traversal::{Action, ComponentLocal, Named, VisResult, Visitor},
         CloneName,
     },
     structure,
@@ -50,7 +52,16 @@ fn rewrite_assign(map: &HoleMapping, assign: &mut ir::Assignment) {
     });
 }
 
+impl ComponentLocal for WireInliner {
+    /// The pass does not keep any state between components.
+    fn merge(&mut self, _visitor: Self) {}
+}
+
 impl Visitor for WireInliner {
+    fn do_pass(&mut self, context: &mut ir::Context) -> CalyxResult<()> {
+        self.do_pass_parallel(context)
+    }
+
     fn start(
         &mut self,
         comp: &mut ir::Component,
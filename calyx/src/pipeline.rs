@@ -0,0 +1,199 @@
+//! Pass pipelines loaded from TOML configuration files.
+//!
+//! A pipeline file defines aliases, the passes to run, the passes to disable,
+//! and options for the passes:
+//! ```toml
+//! passes = ["validate", "my-opt", "compile", "lower"]
+//! disable = ["minimize-regs"]
+//!
+//! [[alias]]
+//! name = "my-opt"
+//! passes = ["pre-opt", "post-opt"]
+//!
+//! # Repeat the passes until the program stops changing.
+//! [[alias]]
+//! name = "cleanup"
+//! passes = ["comb-prop", "dead-cell-removal", "dead-group-removal"]
+//! max-iterations = 8
+//!
+//! [options.tdcc]
+//! dump-fsm = true
+//!
+//! [options.verilog]
+//! synthesis = true
+//! ```
+use crate::errors::{CalyxResult, Error, Span};
+use crate::ir;
+use crate::ir::traversal::{PassOpt, PassOpts};
+use crate::pass_manager::PassManager;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use toml::Spanned;
+
+/// Passes, disabled passes, and pass options defined by a pipeline file.
+/// The aliases defined by the file are registered with the [PassManager].
+#[derive(Default, Debug)]
+pub struct Pipeline {
+    /// Passes and aliases to run in order.
+    pub passes: Vec<String>,
+    /// Passes and aliases that are disabled.
+    pub disable: Vec<String>,
+    /// Pass and backend options of the form `<name>:<key>=<value>`.
+    pub extra_opts: Vec<String>,
+}
+
+/// Format of pipeline files.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PipelineFile {
+    #[serde(default)]
+    passes: Vec<Spanned<String>>,
+    #[serde(default)]
+    disable: Vec<Spanned<String>>,
+    #[serde(default)]
+    alias: Vec<AliasDef>,
+    #[serde(default)]
+    options: BTreeMap<String, BTreeMap<String, Spanned<toml::Value>>>,
+}
+
+/// Definition of an alias in a pipeline file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AliasDef {
+    name: Spanned<String>,
+    passes: Vec<Spanned<String>>,
+    /// Repeat the passes until the program stops changing, running at most
+    /// this many iterations.
+    #[serde(rename = "max-iterations")]
+    max_iterations: Option<usize>,
+}
+
+/// Source of a pipeline file used to construct [Span]s.
+struct Source {
+    file: Arc<str>,
+    input: Arc<str>,
+}
+
+impl Source {
+    fn span(&self, (start, end): (usize, usize)) -> Span {
+        let span = pest::Span::new(&self.input, start, end)
+            .expect("TOML span outside of the input");
+        Span::new(span, Arc::clone(&self.file), Arc::clone(&self.input))
+    }
+
+    fn id(&self, name: &Spanned<String>) -> ir::Id {
+        ir::Id::new(name.get_ref(), Some(self.span(name.span())))
+    }
+
+    /// Convert a TOML error into an [Error] pointing to its location.
+    fn toml_error(&self, err: toml::de::Error) -> Error {
+        let msg = format!("Malformed pipeline file: {}", err);
+        match err.line_col() {
+            Some((line, col)) => {
+                let start = self
+                    .input
+                    .split_inclusive('\n')
+                    .take(line)
+                    .map(str::len)
+                    .sum::<usize>()
+                    + col;
+                let start = start.min(self.input.len());
+                let end = (start + 1).min(self.input.len());
+                Error::invalid_file(msg).with_pos(&self.span((start, end)))
+            }
+            None => Error::invalid_file(msg),
+        }
+    }
+}
+
+impl PassManager {
+    /// Load the pipeline file at `path`. Registers the aliases defined by
+    /// the file and returns the passes, disabled passes, and options it
+    /// defines. `backends` are the names and options of the backends that
+    /// may be configured by the file. Unknown names and invalid options are
+    /// reported with their position in the file.
+    pub fn load_pipeline(
+        &mut self,
+        path: &Path,
+        backends: &[(&str, Vec<PassOpt>)],
+    ) -> CalyxResult<Pipeline> {
+        let input = std::fs::read_to_string(path).map_err(|err| {
+            Error::invalid_file(format!(
+                "Failed to read pipeline file {}: {}",
+                path.display(),
+                err
+            ))
+        })?;
+        let src = Source {
+            file: path.to_string_lossy().into(),
+            input: input.into(),
+        };
+        let file: PipelineFile =
+            toml::from_str(&src.input).map_err(|err| src.toml_error(err))?;
+
+        for alias in &file.alias {
+            let name = src.id(&alias.name);
+            let passes: Vec<ir::Id> =
+                alias.passes.iter().map(|p| src.id(p)).collect();
+            match alias.max_iterations {
+                Some(max) => self.add_fixed_point_alias(name, passes, max)?,
+                None => self.add_alias(name, passes)?,
+            }
+        }
+
+        let names = |list: &[Spanned<String>]| -> CalyxResult<Vec<String>> {
+            list.iter()
+                .map(|name| {
+                    if self.is_defined(name.get_ref()) {
+                        Ok(name.get_ref().clone())
+                    } else {
+                        Err(Error::undefined(
+                            src.id(name),
+                            "pass or alias".to_string(),
+                        ))
+                    }
+                })
+                .collect()
+        };
+        let passes = names(&file.passes)?;
+        let disable = names(&file.disable)?;
+
+        let mut extra_opts = vec![];
+        for (pass, opts) in &file.options {
+            for (key, val) in opts {
+                let val_str = match val.get_ref() {
+                    toml::Value::Boolean(b) => b.to_string(),
+                    toml::Value::Integer(n) => n.to_string(),
+                    toml::Value::String(s) => s.clone(),
+                    v => {
+                        return Err(Error::invalid_file(format!(
+                            "Option `{}' for `{}' must be a bool, integer, or string, found {}",
+                            key,
+                            pass,
+                            v.type_str()
+                        ))
+                        .with_pos(&src.span(val.span())))
+                    }
+                };
+                let opt = format!("{}:{}={}", pass, key, val_str);
+                match backends.iter().find(|(name, _)| name == pass) {
+                    Some((name, opts)) => {
+                        PassOpts::parse(name, opts, std::slice::from_ref(&opt))
+                            .map(|_| ())
+                    }
+                    None => self.validate_opts(std::slice::from_ref(&opt)),
+                }
+                .map_err(|err| err.with_pos(&src.span(val.span())))?;
+                extra_opts.push(opt);
+            }
+        }
+
+        Ok(Pipeline {
+            passes,
+            disable,
+            extra_opts,
+        })
+    }
+}
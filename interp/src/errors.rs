@@ -85,6 +85,8 @@ pub enum InterpreterError {
     InvalidIfState,
     #[error("invalid internal while state. This should never happen, please report it")]
     InvalidWhileState,
+    #[error("invalid internal repeat state. This should never happen, please report it")]
+    InvalidRepeatState,
 
     #[error("{mem_dim} Memory given initialization data with invalid dimension.
     When flattened, expected {expected} entries, but the memory was supplied with {given} entries instead.
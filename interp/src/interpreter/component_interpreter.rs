@@ -9,7 +9,7 @@ use super::{
 };
 use crate::{
     debugger::{name_tree::ActiveTreeNode, PrintCode},
-    environment::{InterpreterState, MutStateView, StateView},
+    environment::{InterpreterState, MutStateView, PrimitiveMap, StateView},
     errors::InterpreterResult,
     interpreter_ir as iir,
     primitives::{Named, Primitive},
@@ -64,6 +64,9 @@ pub struct ComponentInterpreter {
     done_port: RRC<Port>,
     go_port: RRC<Port>,
     input_hash_set: Rc<HashSet<*const ir::Port>>,
+    /// The primitives of this component, shared with its environment. Used to
+    /// bind the `ref` cells of the component.
+    cell_map: PrimitiveMap,
     qual_name: ComponentQualifiedInstanceName,
     /// used to satisfy the Named requirement for primitives, primarially for error messages
     full_name_clone: ir::Id,
@@ -131,6 +134,7 @@ impl ComponentInterpreter {
         }
 
         let input_hash_set = Rc::new(override_set);
+        let cell_map = Rc::clone(&env.cell_map);
 
         let interp = if control_is_empty(&control) {
             StructuralInterpreter::from_component(comp, env).into()
@@ -157,6 +161,7 @@ impl ComponentInterpreter {
             go_port,
             done_port,
             input_hash_set,
+            cell_map,
             qual_name: qin,
             full_name_clone: full_clone,
         }
@@ -207,6 +212,64 @@ impl ComponentInterpreter {
         self.get_env_mut().insert(raw, Value::bit_low())
     }
 
+    /// Bind the `ref` cell `name` of this component to `prim`, the primitive
+    /// of the cell passed in by an invocation. `values` are the current values
+    /// of the ports of the passed cell.
+    pub fn bind_ref(
+        &mut self,
+        name: &ir::Id,
+        prim: Box<dyn Primitive>,
+        values: Vec<(ir::Id, Value)>,
+    ) {
+        let cell = self.ref_cell(name);
+        let cell_ref = cell.borrow();
+        self.cell_map.borrow_mut().insert(cell_ref.as_raw(), prim);
+        let mut env = self.get_env_mut();
+        for (port, value) in values {
+            env.insert(cell_ref.get(port).as_raw(), value);
+        }
+    }
+
+    /// Remove the primitive bound to the `ref` cell `name` and return it
+    /// along with the current values of the output ports of the cell.
+    pub fn unbind_ref(
+        &mut self,
+        name: &ir::Id,
+    ) -> (Box<dyn Primitive>, Vec<(ir::Id, Value)>) {
+        let cell = self.ref_cell(name);
+        let cell_ref = cell.borrow();
+        let prim = self
+            .cell_map
+            .borrow_mut()
+            .remove(&cell_ref.as_raw())
+            .unwrap_or_else(|| panic!("`ref` cell `{}` is not bound", name));
+        let env = self.get_env();
+        let values = cell_ref
+            .ports
+            .iter()
+            .filter(|port| port.borrow().direction == ir::Direction::Output)
+            .map(|port| {
+                (
+                    port.borrow().name.clone(),
+                    env.lookup(port.as_raw()).clone(),
+                )
+            })
+            .collect();
+        (prim, values)
+    }
+
+    fn ref_cell(&self, name: &ir::Id) -> RRC<ir::Cell> {
+        self.comp_ref
+            .find_cell(name)
+            .filter(|cell| cell.borrow().is_reference())
+            .unwrap_or_else(|| {
+                panic!(
+                    "Component `{}` does not have a `ref` cell named `{}`",
+                    self.comp_ref.name, name
+                )
+            })
+    }
+
     /// Interpret a calyx program from the root
     pub fn interpret_program(
         env: InterpreterState,
@@ -476,4 +539,10 @@ impl Primitive for ComponentInterpreter {
     fn get_comp_interpreter(&self) -> Option<&ComponentInterpreter> {
         Some(self)
     }
+
+    fn get_comp_interpreter_mut(
+        &mut self,
+    ) -> Option<&mut ComponentInterpreter> {
+        Some(self)
+    }
 }
@@ -119,16 +119,12 @@ impl EnableHolder {
 
     fn pos_tag(&self) -> Option<u64> {
         match self {
-            EnableHolder::Group(g) => g
-                .borrow()
-                .get_attributes()
-                .and_then(|x| x.get(POS_TAG))
-                .cloned(),
-            EnableHolder::CombGroup(g) => g
-                .borrow()
-                .get_attributes()
-                .and_then(|x| x.get(POS_TAG))
-                .cloned(),
+            EnableHolder::Group(g) => {
+                g.borrow().get_attributes().and_then(|x| x.get(POS_TAG))
+            }
+            EnableHolder::CombGroup(g) => {
+                g.borrow().get_attributes().and_then(|x| x.get(POS_TAG))
+            }
             EnableHolder::Vec(_) => None,
         }
     }
@@ -778,14 +774,10 @@ impl WhileInterpreter {
         env: InterpreterState,
         info: ComponentInfo,
     ) -> Self {
-        let bound =
-            ctrl_while
-                .attributes
-                .get("bound")
-                .map(|target| BoundValidator {
-                    target: *target,
-                    current: 0,
-                });
+        let bound = ctrl_while
+            .attributes
+            .get("bound")
+            .map(|target| BoundValidator { target, current: 0 });
 
         let mut out = Self {
             info,
@@ -991,6 +983,151 @@ impl Interpreter for WhileInterpreter {
         }
     }
 }
+
+#[derive(Default)]
+enum RepeatFsm {
+    #[default]
+    Err, // Transient error state
+    Iterating(ControlInterpreter, u64),
+    Done(InterpreterState),
+}
+
+pub struct RepeatInterpreter {
+    internal_state: RepeatFsm,
+    info: ComponentInfo,
+    repeat: Rc<iir::Repeat>,
+}
+
+impl RepeatInterpreter {
+    pub fn new(
+        repeat: Rc<iir::Repeat>,
+        env: InterpreterState,
+        info: ComponentInfo,
+    ) -> Self {
+        let internal_state = if repeat.num_repeats == 0 {
+            RepeatFsm::Done(env)
+        } else {
+            let interp =
+                ControlInterpreter::new(repeat.body.clone(), env, &info);
+            RepeatFsm::Iterating(interp, 1)
+        };
+
+        Self {
+            repeat,
+            internal_state,
+            info,
+        }
+    }
+}
+
+impl Interpreter for RepeatInterpreter {
+    fn step(&mut self) -> InterpreterResult<()> {
+        match &mut self.internal_state {
+            RepeatFsm::Iterating(interp, _) => {
+                // step the body
+                if !interp.is_done() {
+                    interp.step()?;
+                }
+                // start the next iteration or finish
+                else if let RepeatFsm::Iterating(interp, iteration) =
+                    std::mem::take(&mut self.internal_state)
+                {
+                    let env = interp.deconstruct()?;
+
+                    if iteration < self.repeat.num_repeats {
+                        let interp = ControlInterpreter::new(
+                            self.repeat.body.clone(),
+                            env,
+                            &self.info,
+                        );
+                        self.internal_state =
+                            RepeatFsm::Iterating(interp, iteration + 1);
+                    } else {
+                        self.internal_state = RepeatFsm::Done(env);
+                    }
+                } else {
+                    unreachable!();
+                }
+                Ok(())
+            }
+            RepeatFsm::Done(_) => Ok(()),
+            RepeatFsm::Err => Err(InterpreterError::InvalidRepeatState),
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        matches!(&self.internal_state, RepeatFsm::Done(_))
+    }
+
+    fn deconstruct(self) -> InterpreterResult<InterpreterState> {
+        match self.internal_state {
+            RepeatFsm::Done(e) => Ok(e),
+            RepeatFsm::Iterating(_, _) | RepeatFsm::Err => {
+                Err(InterpreterError::InvalidRepeatState)
+            }
+        }
+    }
+
+    fn get_env(&self) -> StateView<'_> {
+        match &self.internal_state {
+            RepeatFsm::Iterating(i, _) => i.get_env(),
+            RepeatFsm::Done(e) => e.into(),
+            RepeatFsm::Err => unreachable!("There is an error in the Repeat state transition. Please report this."),
+        }
+    }
+
+    fn currently_executing_group(&self) -> HashSet<GroupQIN> {
+        match &self.internal_state {
+            RepeatFsm::Iterating(i, _) => i.currently_executing_group(),
+            RepeatFsm::Done(_) => HashSet::new(),
+            RepeatFsm::Err => unreachable!("There is an error in the Repeat state transition. Please report this."),
+        }
+    }
+
+    fn get_env_mut(&mut self) -> MutStateView<'_> {
+        match &mut self.internal_state {
+            RepeatFsm::Iterating(i, _) => i.get_env_mut(),
+            RepeatFsm::Done(e) => e.into(),
+            RepeatFsm::Err => unreachable!("There is an error in the Repeat state transition. Please report this."),
+        }
+    }
+
+    fn converge(&mut self) -> InterpreterResult<()> {
+        match &mut self.internal_state {
+            RepeatFsm::Err => Err(InterpreterError::InvalidRepeatState),
+            RepeatFsm::Iterating(i, _) => i.converge(),
+            RepeatFsm::Done(_) => {
+                if let RepeatFsm::Done(env) =
+                    std::mem::take(&mut self.internal_state)
+                {
+                    let mut interp = EnableInterpreter::new(
+                        vec![],
+                        None,
+                        env,
+                        self.info.continuous_assignments.clone(),
+                        &self.info.qin,
+                    );
+                    interp.converge()?;
+                    let env = interp.deconstruct()?;
+
+                    self.internal_state = RepeatFsm::Done(env);
+                    Ok(())
+                } else {
+                    unreachable!()
+                }
+            }
+        }
+    }
+
+    fn get_active_tree(&self) -> Vec<ActiveTreeNode> {
+        match &self.internal_state {
+            RepeatFsm::Iterating(i, _) => i.get_active_tree(),
+            RepeatFsm::Done(_) => vec![],
+            RepeatFsm::Err => unreachable!("There is an error in the Repeat state transition. Please report this."),
+        }
+    }
+}
+
 pub struct InvokeInterpreter {
     invoke: Rc<iir::Invoke>,
     assign_interp: AssignmentInterpreter,
@@ -1036,6 +1173,42 @@ impl InvokeInterpreter {
             assignment_vec.extend(w_ref.assignments.iter().cloned());
         }
 
+        // bind the ref cells of the invoked component to the cells passed in
+        // for the duration of the invoke
+        if !invoke.ref_cells.is_empty() {
+            let mut cell_map = env.cell_map.borrow_mut();
+            let bindings = invoke
+                .ref_cells
+                .iter()
+                .map(|(name, cell)| {
+                    let cell = cell.borrow();
+                    let prim =
+                        cell_map.remove(&cell.as_raw()).unwrap_or_else(|| {
+                            panic!(
+                                "Cell `{}` is passed to more than one `ref` cell at once",
+                                cell.name()
+                            )
+                        });
+                    let values = cell
+                        .ports
+                        .iter()
+                        .map(|port| {
+                            let port = port.borrow();
+                            (port.name.clone(), env.get_from_port(&*port).clone())
+                        })
+                        .collect();
+                    (name, prim, values)
+                })
+                .collect::<Vec<_>>();
+            let callee = cell_map
+                .get_mut(&comp_cell.as_raw())
+                .and_then(|prim| prim.get_comp_interpreter_mut())
+                .unwrap();
+            for (name, prim, values) in bindings {
+                callee.bind_ref(name, prim, values);
+            }
+        }
+
         let go_port = comp_cell.get_with_attr("go");
         // insert one into the go_port
         // should probably replace with an actual assignment from a constant one
@@ -1077,6 +1250,30 @@ impl Interpreter for InvokeInterpreter {
         // should probably replace with an actual assignment from a constant one
         env.insert(go_port, Value::bit_low());
 
+        // return the cells passed to the ref cells of the invoked component
+        if !self.invoke.ref_cells.is_empty() {
+            let comp_cell = self.invoke.comp.borrow();
+            let map = Rc::clone(&env.cell_map);
+            let mut cell_map = map.borrow_mut();
+            let callee = cell_map
+                .get_mut(&comp_cell.as_raw())
+                .and_then(|prim| prim.get_comp_interpreter_mut())
+                .unwrap();
+            let unbound = self
+                .invoke
+                .ref_cells
+                .iter()
+                .map(|(name, cell)| (cell, callee.unbind_ref(name)))
+                .collect::<Vec<_>>();
+            for (cell, (prim, values)) in unbound {
+                let cell = cell.borrow();
+                cell_map.insert(cell.as_raw(), prim);
+                for (port, value) in values {
+                    env.insert(cell.get(port), value);
+                }
+            }
+        }
+
         Ok(env)
     }
 
@@ -1106,7 +1303,7 @@ impl Interpreter for InvokeInterpreter {
             &(format!("invoke {}", self.invoke.comp.borrow().name()).into()),
         );
 
-        let pos_tag = self.invoke.attributes.get(POS_TAG).cloned();
+        let pos_tag = self.invoke.attributes.get(POS_TAG);
 
         vec![ActiveTreeNode::new(name.with_tag(pos_tag))]
     }
@@ -1123,6 +1320,7 @@ macro_rules! control_match {
             ControlInterpreter::Par($name) => $exp,
             ControlInterpreter::If($name) => $exp,
             ControlInterpreter::While($name) => $exp,
+            ControlInterpreter::Repeat($name) => $exp,
             ControlInterpreter::Invoke($name) => $exp,
         }
     }};
@@ -1135,6 +1333,7 @@ pub enum ControlInterpreter {
     Par(Box<ParInterpreter>),
     If(Box<IfInterpreter>),
     While(Box<WhileInterpreter>),
+    Repeat(Box<RepeatInterpreter>),
     Invoke(Box<InvokeInterpreter>),
 }
 
@@ -1157,6 +1356,9 @@ impl ControlInterpreter {
             iir::Control::While(w) => Self::While(Box::new(
                 WhileInterpreter::new(w, env, info.clone()),
             )),
+            iir::Control::Repeat(r) => Self::Repeat(Box::new(
+                RepeatInterpreter::new(r, env, info.clone()),
+            )),
             iir::Control::Invoke(i) => {
                 Self::Invoke(Box::new(InvokeInterpreter::new(
                     i,
@@ -239,12 +239,12 @@ impl AssignmentInterpreter {
                     let old_val =
                         self.state.get_from_port(&assignment.dst.borrow());
                     let new_val_ref =
-                        self.state.get_from_port(&assignment.src.borrow());
+                        self.state.read_port(&assignment.src.borrow());
                     // no need to make updates if the value has not changed
 
-                    if old_val != new_val_ref {
+                    if *old_val != *new_val_ref {
                         let port = assignment.dst.clone(); // Rc clone
-                        let new_val = new_val_ref.clone();
+                        let new_val = new_val_ref.into_owned();
 
                         if cfg!(feature = "change-based-sim") {
                             let pref = port.borrow();
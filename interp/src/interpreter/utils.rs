@@ -74,6 +74,9 @@ pub fn control_is_empty(control: &iir::Control) -> bool {
         iir::Control::Par(p) => p.stmts.iter().all(control_is_empty),
         iir::Control::If(_) => false,
         iir::Control::While(_) => false,
+        iir::Control::Repeat(r) => {
+            r.num_repeats == 0 || control_is_empty(&r.body)
+        }
         iir::Control::Invoke(_) => false,
         iir::Control::Enable(_) => false,
         iir::Control::Empty(_) => true,
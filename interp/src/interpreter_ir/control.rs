@@ -52,6 +52,17 @@ pub struct While {
     pub attributes: Attributes,
 }
 
+/// Data for the `repeat` control statement.
+#[derive(Debug)]
+pub struct Repeat {
+    /// Number of times the body is executed.
+    pub num_repeats: u64,
+    /// Control for the loop body.
+    pub body: Control,
+    /// Attributes attached to this control statement.
+    pub attributes: Attributes,
+}
+
 /// Control AST nodes.
 #[derive(Debug, Clone)]
 pub enum Control {
@@ -63,6 +74,8 @@ pub enum Control {
     If(Rc<If>),
     /// Standard imperative while statement
     While(Rc<While>),
+    /// Runs the body a fixed number of times
+    Repeat(Rc<Repeat>),
     /// Invoke a sub-component with the given port assignments
     Invoke(Rc<Invoke>),
     /// Runs the control for a list of subcomponents.
@@ -78,9 +91,17 @@ impl From<CalyxControl> for Control {
             CalyxControl::Par(p) => Control::Par(Rc::new(p.into())),
             CalyxControl::If(i) => Control::If(Rc::new(i.into())),
             CalyxControl::While(wh) => Control::While(Rc::new(wh.into())),
+            CalyxControl::Repeat(r) => Control::Repeat(Rc::new(r.into())),
             CalyxControl::Invoke(invoke) => Control::Invoke(Rc::new(invoke)),
             CalyxControl::Enable(enable) => Control::Enable(Rc::new(enable)),
             CalyxControl::Empty(empty) => Control::Empty(Rc::new(empty)),
+            // The interpreter executes static control like its dynamic
+            // counterpart. The values are the same but the cycle counts are
+            // not: for example, `static if` does not spend a cycle saving
+            // its condition. See docs/interpreter.md.
+            CalyxControl::StaticSeq(s) => Control::Seq(Rc::new(s.into())),
+            CalyxControl::StaticPar(p) => Control::Par(Rc::new(p.into())),
+            CalyxControl::StaticIf(i) => Control::If(Rc::new(i.into())),
         }
     }
 }
@@ -94,6 +115,15 @@ impl From<ir::Seq> for Seq {
     }
 }
 
+impl From<ir::StaticSeq> for Seq {
+    fn from(seq: ir::StaticSeq) -> Self {
+        Self {
+            stmts: seq.stmts.into_iter().map(|x| x.into()).collect(),
+            attributes: seq.attributes,
+        }
+    }
+}
+
 impl From<ir::Par> for Par {
     fn from(par: ir::Par) -> Self {
         Self {
@@ -103,6 +133,15 @@ impl From<ir::Par> for Par {
     }
 }
 
+impl From<ir::StaticPar> for Par {
+    fn from(par: ir::StaticPar) -> Self {
+        Self {
+            stmts: par.stmts.into_iter().map(|x| x.into()).collect(),
+            attributes: par.attributes,
+        }
+    }
+}
+
 impl From<ir::If> for If {
     fn from(i: ir::If) -> Self {
         Self {
@@ -115,6 +154,18 @@ impl From<ir::If> for If {
     }
 }
 
+impl From<ir::StaticIf> for If {
+    fn from(i: ir::StaticIf) -> Self {
+        Self {
+            port: i.port,
+            cond: None,
+            tbranch: (*i.tbranch).into(),
+            fbranch: (*i.fbranch).into(),
+            attributes: i.attributes,
+        }
+    }
+}
+
 impl From<ir::While> for While {
     fn from(wh: ir::While) -> Self {
         Self {
@@ -125,3 +176,13 @@ impl From<ir::While> for While {
         }
     }
 }
+
+impl From<ir::Repeat> for Repeat {
+    fn from(r: ir::Repeat) -> Self {
+        Self {
+            num_repeats: r.num_repeats,
+            body: (*r.body).into(),
+            attributes: r.attributes,
+        }
+    }
+}
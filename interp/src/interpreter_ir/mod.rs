@@ -12,7 +12,9 @@ mod component;
 mod control;
 
 pub use component::Component;
-pub use control::{Control, Empty, Enable, If, Invoke, Par, Seq, While};
+pub use control::{
+    Control, Empty, Enable, If, Invoke, Par, Repeat, Seq, While,
+};
 
 use std::rc::Rc;
 pub type ComponentCtx = Rc<Vec<Rc<component::Component>>>;
@@ -1,4 +1,8 @@
-use calyx::{frontend, ir, pass_manager::PassManager, utils::OutputFile};
+use calyx::{
+    frontend, ir,
+    pass_manager::{IrDump, PassManager},
+    utils::OutputFile,
+};
 use interp::{
     configuration,
     debugger::{source::SourceMap, Debugger},
@@ -64,6 +68,31 @@ pub struct Opts {
     #[argh(switch, long = "raw")]
     raw: bool,
 
+    /// check the structure of the IR after every pass
+    #[argh(switch, long = "verify-each")]
+    verify_each: bool,
+
+    /// dump the IR after this pass (`all` dumps after every pass)
+    #[argh(option, long = "dump-ir-after")]
+    dump_ir_after: Vec<String>,
+
+    /// dump the IR before this pass (`all` dumps before every pass)
+    #[argh(option, long = "dump-ir-before")]
+    dump_ir_before: Vec<String>,
+
+    /// directory for the IR dumps
+    #[argh(
+        option,
+        long = "dump-ir-dir",
+        default = "Path::new(\"ir-dumps\").into()"
+    )]
+    dump_ir_dir: PathBuf,
+
+    /// print a unified diff of each component changed since the previous IR
+    /// dump
+    #[argh(switch, long = "dump-ir-diff")]
+    dump_ir_diff: bool,
+
     #[argh(subcommand)]
     comm: Option<Command>,
 }
@@ -135,7 +164,16 @@ fn main() -> InterpreterResult<()> {
     // Construct IR
     let ws = frontend::Workspace::construct(&opts.file, &opts.lib_path)?;
     let mut ctx = ir::from_ast::ast_to_ir(ws)?;
-    let pm = PassManager::default_passes()?;
+    let mut pm = PassManager::default_passes()?;
+    pm.verify_each(opts.verify_each);
+    if !opts.dump_ir_after.is_empty() || !opts.dump_ir_before.is_empty() {
+        pm.dump_ir(IrDump {
+            dir: opts.dump_ir_dir.clone(),
+            before: opts.dump_ir_before.clone(),
+            after: opts.dump_ir_after.clone(),
+            diff: opts.dump_ir_diff,
+        })?;
+    }
 
     if !opts.skip_verification {
         pm.execute_plan(&mut ctx, &["validate".to_string()], &[])?;
@@ -20,7 +20,7 @@ pub struct StdConst {
 }
 
 impl StdConst {
-    pub fn from_constants(value: u64, width: u64, name: ir::Id) -> Self {
+    pub fn from_constants(value: ir::UBig, width: u64, name: ir::Id) -> Self {
         StdConst {
             value: Value::from(value, width),
             full_name: name,
@@ -34,12 +34,7 @@ impl StdConst {
         let init_value = get_param(params, "VALUE")
             .expect("Missing `value` param from std_const binding");
 
-        let value = Value::from(init_value, width);
-
-        Self {
-            value,
-            full_name: name,
-        }
+        Self::from_constants(ir::UBig::from(init_value), width, name)
     }
 }
 
@@ -70,6 +70,12 @@ pub trait Primitive: Named {
     fn get_comp_interpreter(&self) -> Option<&ComponentInterpreter> {
         None
     }
+
+    fn get_comp_interpreter_mut(
+        &mut self,
+    ) -> Option<&mut ComponentInterpreter> {
+        None
+    }
 }
 
 /// An enum wrapping over a tuple representing the shape of a multi-dimensional
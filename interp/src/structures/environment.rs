@@ -20,6 +20,7 @@ use crate::utils::MemoryMap;
 use crate::values::Value;
 use calyx::ir::{self, RRC};
 use serde::Serialize;
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::iter::once;
@@ -344,6 +345,12 @@ impl InterpreterState {
         for cell in comp.cells.iter() {
             let cl: &ir::Cell = &cell.borrow();
 
+            // The primitives of `ref` cells are bound by the invoking
+            // component.
+            if cl.is_reference() {
+                continue;
+            }
+
             match &cl.prototype {
                 ir::CellType::Primitive {
                     name,
@@ -362,7 +369,7 @@ impl InterpreterState {
                         )?,
                     );
                 }
-                ir::CellType::Component { name } => {
+                ir::CellType::Component { name, .. } => {
                     let inner_comp =
                         ctx.iter().find(|x| x.name == name).unwrap();
                     let qin = qin_name
@@ -403,7 +410,10 @@ impl InterpreterState {
                 ir::CellType::Constant { val, width } => {
                     for port in &cll.ports {
                         let pt: &ir::Port = &port.borrow();
-                        map.insert(pt as ConstPort, Value::from(*val, *width));
+                        map.insert(
+                            pt as ConstPort,
+                            Value::from(val.clone(), *width),
+                        );
                     }
                 }
                 ir::CellType::Primitive { .. } => {
@@ -439,6 +449,19 @@ impl InterpreterState {
         self.port_map.get(&port.as_raw()).unwrap()
     }
 
+    /// Return the value read by the given port. Bit-selects are evaluated
+    /// by slicing the value of the port they select from.
+    pub fn read_port(&self, port: &ir::Port) -> Cow<'_, Value> {
+        match port.select {
+            Some((msb, lsb)) => {
+                let base = port.base_port();
+                let val = self.get_from_port(base.borrow()).clone();
+                Cow::Owned(val.slice(msb as usize, lsb as usize))
+            }
+            None => Cow::Borrowed(self.get_from_port(port)),
+        }
+    }
+
     /// Outputs the cell state;
     // TODO (write to a specified output in the future) We could do the printing
     // of values here for tracing purposes as discussed. Could also have a
@@ -551,8 +574,8 @@ impl InterpreterState {
             }
             ir::Guard::Not(g) => !self.eval_guard(g)?,
             ir::Guard::CompOp(op, g1, g2) => {
-                let p1 = self.get_from_port(&g1.borrow());
-                let p2 = self.get_from_port(&g2.borrow());
+                let p1 = self.read_port(&g1.borrow());
+                let p2 = self.read_port(&g2.borrow());
                 match op {
                     ir::PortComp::Eq => p1 == p2,
                     ir::PortComp::Neq => p1 != p2,
@@ -563,7 +586,7 @@ impl InterpreterState {
                 }
             }
             ir::Guard::Port(p) => {
-                let val = self.get_from_port(&p.borrow());
+                let val = self.read_port(&p.borrow());
                 if val.len() != 1 {
                     let can = p.borrow().canonical();
                     return Err(InterpreterError::InvalidBoolCast(
@@ -581,11 +604,13 @@ impl InterpreterState {
     pub fn sub_component_currently_executing(&self) -> HashSet<GroupQIN> {
         let lookup = self.cell_map.borrow();
 
+        // Sub-components passed to an active invoke as `ref` cells are
+        // missing from the map.
         self.sub_comp_set
             .iter()
-            .flat_map(|x| {
-                lookup[x]
-                    .get_comp_interpreter()
+            .filter_map(|x| lookup.get(x))
+            .flat_map(|prim| {
+                prim.get_comp_interpreter()
                     .unwrap()
                     .currently_executing_group()
             })
@@ -600,8 +625,9 @@ impl InterpreterState {
 
         self.sub_comp_set
             .iter()
-            .flat_map(|x| {
-                lookup[x].get_comp_interpreter().unwrap().get_active_tree()
+            .filter_map(|x| lookup.get(x))
+            .flat_map(|prim| {
+                prim.get_comp_interpreter().unwrap().get_active_tree()
             })
             .collect()
     }
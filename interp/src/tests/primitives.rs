@@ -497,19 +497,20 @@ fn test_std_mem_d4() {
     assert_eq!(rd.as_u64(), 16);
 }
 
-/* #[test]
+#[test]
 fn test_std_const() {
-let val_31 = Value::try_from_init(31, 5).unwrap();
-let const_31 = comb::StdConst::from_constants(5, val_31, "".into());
-assert_eq!(const_31.read_val().as_u64(), 31); //can rust check this equality?
-assert_eq!(const_31.read_u64(), 31);
+    let mut const_31 =
+        comb::StdConst::from_constants(ir::UBig::from(31u8), 5, "".into());
+    let out = const_31.execute(&[]).unwrap();
+    assert_eq!(out[0].1.as_u64(), 31);
 }
 #[test]
-#[should_panic]
-fn test_std_const_panic() {
-let val = Value::try_from_init(75, 7).unwrap();
-comb::StdConst::from_constants(5, val, "".into());
-} */
+fn test_std_const_wide() {
+    let val = (ir::UBig::from(1u8) << 100) + ir::UBig::from(1u8);
+    let mut wide = comb::StdConst::from_constants(val.clone(), 128, "".into());
+    let out = wide.execute(&[]).unwrap();
+    assert_eq!(out[0].1, Value::from(val, 128));
+}
 #[test]
 fn test_std_lsh() {
     // lsh with overflow
@@ -1,5 +0,0 @@
-//! Backends for the Calyx compiler.
-pub mod mlir;
-pub mod traits;
-pub mod verilog;
-pub mod xilinx;
@@ -1,11 +1,12 @@
-use crate::backend::traits::Backend;
-use crate::backend::{
-    mlir::MlirBackend, verilog::VerilogBackend, xilinx::XilinxInterfaceBackend,
-    xilinx::XilinxXmlBackend,
-};
 use argh::FromArgs;
 use calyx::errors::Error;
+use calyx::ir::traversal::PassOpts;
 use calyx::{errors::CalyxResult, ir, utils::OutputFile};
+use calyx_backend::{
+    firrtl::FirrtlBackend, json::JsonBackend, mlir::MlirBackend,
+    verilog::VerilogBackend, xilinx::XilinxInterfaceBackend,
+    xilinx::XilinxXmlBackend, Backend,
+};
 use itertools::Itertools;
 use std::path::Path;
 use std::path::PathBuf;
@@ -18,6 +19,10 @@ pub struct Opts {
     #[argh(positional, from_str_fn(read_path))]
     pub file: Option<PathBuf>,
 
+    /// format of the input program: `calyx` or `json`
+    #[argh(option, long = "input-format", default = "InputFormat::default()")]
+    pub input_format: InputFormat,
+
     /// output file
     #[argh(option, short = 'o', default = "OutputFile::default()")]
     pub output: OutputFile,
@@ -30,22 +35,33 @@ pub struct Opts {
     #[argh(option, short = 'm', default = "CompileMode::default()")]
     pub compile_mode: CompileMode,
 
-    /// enable synthesis mode
+    /// enable synthesis mode (same as `-x verilog:synthesis`)
     #[argh(switch, long = "synthesis")]
     pub enable_synthesis: bool,
 
-    /// disable verification checks emitted by backends
+    /// disable verification checks emitted by backends (same as
+    /// `-x verilog:verify=false`)
     #[argh(switch)]
     pub disable_verify: bool,
 
-    /// do not initialize input ports
+    /// do not initialize input ports (same as `-x verilog:init=false`)
     #[argh(switch)]
     pub disable_init: bool,
 
+    /// annotate the generated code with the source locations of cells and
+    /// assignments (same as `-x verilog:src-locations`)
+    #[argh(switch)]
+    pub src_locations: bool,
+
     /// select a backend
     #[argh(option, short = 'b', default = "BackendOpt::default()")]
     pub backend: BackendOpt,
 
+    /// load aliases, passes, disabled passes, and pass options from a TOML
+    /// pipeline file
+    #[argh(option, from_str_fn(read_path))]
+    pub pipeline: Option<PathBuf>,
+
     /// run this pass during execution
     #[argh(option, short = 'p')]
     pub pass: Vec<String>,
@@ -54,17 +70,71 @@ pub struct Opts {
     #[argh(option, short = 'd', long = "disable-pass")]
     pub disable_pass: Vec<String>,
 
-    /// extra options passed to the context
+    /// options for passes and backends of the form `<pass>:<key>=<value>`
     #[argh(option, short = 'x', long = "extra-opt")]
     pub extra_opts: Vec<String>,
 
+    /// number of threads used to compile components in parallel (default:
+    /// number of CPUs)
+    #[argh(option, short = 'j')]
+    pub jobs: Option<usize>,
+
+    /// check the structure of the IR after every pass
+    #[argh(switch, long = "verify-each")]
+    pub verify_each: bool,
+
+    /// print the wall time and the IR size before and after every pass
+    #[argh(switch, long = "profile-passes")]
+    pub profile_passes: bool,
+
+    /// write the pass profile as JSON to this file
+    #[argh(option, long = "profile-json", from_str_fn(read_path))]
+    pub profile_json: Option<PathBuf>,
+
+    /// dump the IR after this pass (`all` dumps after every pass)
+    #[argh(option, long = "dump-ir-after")]
+    pub dump_ir_after: Vec<String>,
+
+    /// dump the IR before this pass (`all` dumps before every pass)
+    #[argh(option, long = "dump-ir-before")]
+    pub dump_ir_before: Vec<String>,
+
+    /// directory for the IR dumps
+    #[argh(
+        option,
+        long = "dump-ir-dir",
+        default = "Path::new(\"ir-dumps\").into()"
+    )]
+    pub dump_ir_dir: PathBuf,
+
+    /// print a unified diff of each component changed since the previous IR
+    /// dump
+    #[argh(switch, long = "dump-ir-diff")]
+    pub dump_ir_diff: bool,
+
     /// list all avaliable pass options
     #[argh(switch, long = "list-passes")]
     pub list_passes: bool,
 
+    /// list all avaliable backends and their options
+    #[argh(switch, long = "list-backends")]
+    pub list_backends: bool,
+
     /// enable verbose printing
     #[argh(option, long = "log", default = "log::LevelFilter::Warn")]
     pub log_level: log::LevelFilter,
+
+    /// maximum number of errors to report (0 reports all errors)
+    #[argh(option, long = "max-errors", default = "20")]
+    pub max_errors: usize,
+
+    /// format of the reported errors: `human` or `json`
+    #[argh(
+        option,
+        long = "diagnostic-format",
+        default = "DiagnosticFormat::default()"
+    )]
+    pub diagnostic_format: DiagnosticFormat,
 }
 
 fn read_path(path: &str) -> Result<PathBuf, String> {
@@ -98,16 +168,70 @@ impl FromStr for CompileMode {
     }
 }
 
+// Formats of the input program
+#[derive(PartialEq, Eq)]
+pub enum InputFormat {
+    /// A Calyx program.
+    Calyx,
+    /// The JSON representation of the IR generated by `-b json`.
+    Json,
+}
+
+impl Default for InputFormat {
+    fn default() -> Self {
+        InputFormat::Calyx
+    }
+}
+
+impl FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "calyx" => Ok(InputFormat::Calyx),
+            "json" => Ok(InputFormat::Json),
+            s => Err(format!(
+                "Unknown input format: {}. Valid options are `calyx` or `json`",
+                s
+            )),
+        }
+    }
+}
+
+// Formats for reporting errors
+#[derive(PartialEq, Eq, Default)]
+pub enum DiagnosticFormat {
+    /// Errors annotate the source program.
+    #[default]
+    Human,
+    /// Errors are printed as a JSON array of diagnostics.
+    Json,
+}
+
+impl FromStr for DiagnosticFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(DiagnosticFormat::Human),
+            "json" => Ok(DiagnosticFormat::Json),
+            s => Err(format!("Unknown diagnostic format: {}. Valid options are `human` or `json`", s))
+        }
+    }
+}
+
 // ================== Backend Variant and Parsing ===================== //
 
 /// Enumeration of valid backends
 #[derive(Debug, Clone, PartialEq)]
 pub enum BackendOpt {
     Verilog,
+    Firrtl,
     Xilinx,
     XilinxXml,
     Calyx,
     Mlir,
+    Json,
     None,
 }
 
@@ -116,15 +240,37 @@ pub enum BackendOpt {
 fn backends() -> Vec<(&'static str, BackendOpt)> {
     vec![
         ("verilog", BackendOpt::Verilog),
+        ("firrtl", BackendOpt::Firrtl),
         ("xilinx", BackendOpt::Xilinx),
         ("xilinx-xml", BackendOpt::XilinxXml),
         ("futil", BackendOpt::Calyx),
         ("calyx", BackendOpt::Calyx),
         ("mlir", BackendOpt::Mlir),
+        ("json", BackendOpt::Json),
         ("none", BackendOpt::None),
     ]
 }
 
+/// List the backends and the options they accept.
+pub fn show_backends() -> String {
+    let mut ret = String::from("Backends:\n");
+    for (name, _) in backends() {
+        ret.push_str(&format!("- {}\n", name));
+        let opts = calyx_backend::find_backend(name)
+            .map(|backend| backend.opts())
+            .unwrap_or_default();
+        for opt in opts {
+            ret.push_str(&format!(
+                "  * {}: {} (default: {})\n",
+                opt.name(),
+                opt.description(),
+                opt.default()
+            ));
+        }
+    }
+    ret
+}
+
 impl Default for BackendOpt {
     fn default() -> Self {
         BackendOpt::Calyx
@@ -163,7 +309,9 @@ impl ToString for BackendOpt {
     fn to_string(&self) -> String {
         match self {
             Self::Mlir => "mlir",
+            Self::Json => "json",
             Self::Verilog => "verilog",
+            Self::Firrtl => "firrtl",
             Self::Xilinx => "xilinx",
             Self::XilinxXml => "xilinx-xml",
             Self::Calyx => "calyx",
@@ -179,19 +327,27 @@ impl Opts {
         match self.backend {
             BackendOpt::Mlir => {
                 let backend = MlirBackend::default();
-                backend.run(context, self.output)
+                backend.run(&context, &mut self.output.get_write())
+            }
+            BackendOpt::Json => {
+                let backend = JsonBackend::default();
+                backend.run(&context, &mut self.output.get_write())
             }
             BackendOpt::Verilog => {
                 let backend = VerilogBackend::default();
-                backend.run(context, self.output)
+                backend.run(&context, &mut self.output.get_write())
+            }
+            BackendOpt::Firrtl => {
+                let backend = FirrtlBackend::default();
+                backend.run(&context, &mut self.output.get_write())
             }
             BackendOpt::Xilinx => {
                 let backend = XilinxInterfaceBackend::default();
-                backend.run(context, self.output)
+                backend.run(&context, &mut self.output.get_write())
             }
             BackendOpt::XilinxXml => {
                 let backend = XilinxXmlBackend::default();
-                backend.run(context, self.output)
+                backend.run(&context, &mut self.output.get_write())
             }
             BackendOpt::Calyx => {
                 for (path, prims) in context.lib.externs() {
@@ -216,9 +372,90 @@ impl Opts {
         }
     }
 
+    /// Remove the options for backends from the extra options and return
+    /// them. The switches that configure the Verilog backend are turned into
+    /// options as well. Returns an error if an option is not accepted by
+    /// its backend.
+    pub fn backend_opts(&mut self) -> CalyxResult<Vec<String>> {
+        let shorthands = [
+            (self.enable_synthesis, "verilog:synthesis"),
+            (self.disable_verify, "verilog:verify=false"),
+            (self.disable_init, "verilog:init=false"),
+            (self.src_locations, "verilog:src-locations"),
+        ];
+        let mut backend_opts = shorthands
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, opt)| opt.to_string())
+            .collect_vec();
+
+        let (opts, pass_opts): (Vec<_>, Vec<_>) =
+            self.extra_opts.drain(..).partition(|opt| {
+                let name = opt.split(':').next().unwrap_or_default();
+                calyx_backend::find_backend(name).is_some()
+            });
+        self.extra_opts = pass_opts;
+
+        for opt in &opts {
+            let name = opt.split(':').next().unwrap_or_default();
+            let backend = calyx_backend::find_backend(name).unwrap();
+            PassOpts::parse(
+                backend.name(),
+                &backend.opts(),
+                std::slice::from_ref(opt),
+            )?;
+        }
+        backend_opts.extend(opts);
+        Ok(backend_opts)
+    }
+
+    /// Parse the command line invocation. argh only accepts `--flag value`,
+    /// so `--flag=value` is split into two arguments first.
+    fn from_env() -> Opts {
+        let mut args = std::env::args();
+        let cmd = args.next().unwrap_or_default();
+        let cmd = Path::new(&cmd)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(&cmd)
+            .to_string();
+        let mut positional_only = false;
+        let args = args
+            .flat_map(|arg| {
+                if arg == "--" {
+                    positional_only = true;
+                }
+                match arg.split_once('=') {
+                    Some((flag, value))
+                        if !positional_only && flag.starts_with("--") =>
+                    {
+                        vec![flag.to_string(), value.to_string()]
+                    }
+                    _ => vec![arg],
+                }
+            })
+            .collect_vec();
+        let args = args.iter().map(String::as_str).collect_vec();
+        Opts::from_args(&[&cmd], &args).unwrap_or_else(|early_exit| {
+            std::process::exit(match early_exit.status {
+                Ok(()) => {
+                    println!("{}", early_exit.output);
+                    0
+                }
+                Err(()) => {
+                    eprintln!(
+                        "{}\nRun {} --help for more information.",
+                        early_exit.output, cmd
+                    );
+                    1
+                }
+            })
+        })
+    }
+
     /// Get the current set of options from the command line invocation.
     pub fn get_opts() -> CalyxResult<Opts> {
-        let mut opts: Opts = argh::from_env();
+        let mut opts = Opts::from_env();
 
         if opts.compile_mode == CompileMode::File
             && !matches!(opts.backend, BackendOpt::Calyx | BackendOpt::None)
@@ -230,8 +467,8 @@ impl Opts {
         }
 
         // argh doesn't allow us to specify a default for this so we fill it
-        // in manually.
-        if opts.pass.is_empty() {
+        // in manually. A pipeline file may provide the passes instead.
+        if opts.pass.is_empty() && opts.pipeline.is_none() {
             opts.pass = vec!["all".into()];
         }
 
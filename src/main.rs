@@ -1,14 +1,36 @@
-mod backend;
 mod cmdline;
 
-use calyx::{errors::CalyxResult, frontend, ir, pass_manager::PassManager};
-use cmdline::{BackendOpt, CompileMode, Opts};
+use calyx::{
+    errors::{CalyxResult, Error},
+    frontend, ir,
+    pass_manager::{IrDump, PassManager},
+    pass_profile::PassProfile,
+};
+use cmdline::{
+    show_backends, BackendOpt, CompileMode, DiagnosticFormat, InputFormat, Opts,
+};
 use itertools::Itertools;
+use std::io::Read;
 
 fn main() -> CalyxResult<()> {
     // parse the command line arguments into Opts struct
-    let mut opts = Opts::get_opts()?;
+    let opts = Opts::get_opts()?;
+    let max_errors = opts.max_errors;
+    let json_diagnostics = opts.diagnostic_format == DiagnosticFormat::Json;
+    match compile(opts).map_err(|err| err.limit(max_errors)) {
+        Err(err) if json_diagnostics => {
+            eprintln!(
+                "{}",
+                serde_json::to_string_pretty(&err.diagnostics())
+                    .expect("Failed to serialize diagnostics")
+            );
+            std::process::exit(1)
+        }
+        res => res,
+    }
+}
 
+fn compile(mut opts: Opts) -> CalyxResult<()> {
     // enable tracing
     env_logger::Builder::new()
         .format_timestamp(None)
@@ -16,7 +38,36 @@ fn main() -> CalyxResult<()> {
         .target(env_logger::Target::Stderr)
         .init();
 
-    let pm = PassManager::default_passes()?;
+    // Limit the number of threads used by passes that compile components in
+    // parallel.
+    if let Some(jobs) = opts.jobs {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(jobs)
+            .build_global()
+            .map_err(|err| {
+                Error::misc(format!("Failed to set --jobs: {err}"))
+            })?;
+    }
+
+    let mut pm = PassManager::default_passes()?;
+
+    // Load the aliases, passes, and options defined by the pipeline file.
+    // Passes given on the command line override the ones from the file.
+    if let Some(path) = &opts.pipeline {
+        let backends = calyx_backend::backends()
+            .iter()
+            .map(|b| (b.name(), b.opts()))
+            .collect_vec();
+        let pipeline = pm.load_pipeline(path, &backends)?;
+        if opts.pass.is_empty() {
+            opts.pass = pipeline.passes;
+        }
+        if opts.pass.is_empty() {
+            opts.pass = vec!["all".into()];
+        }
+        opts.disable_pass.extend(pipeline.disable);
+        opts.extra_opts.splice(0..0, pipeline.extra_opts);
+    }
 
     // list all the avaliable pass options when flag --list-passes is enabled
     if opts.list_passes {
@@ -24,24 +75,61 @@ fn main() -> CalyxResult<()> {
         return Ok(());
     }
 
-    // Construct the namespace.
-    let mut ws = frontend::Workspace::construct(&opts.file, &opts.lib_path)?;
+    // list all the avaliable backends and their options when flag
+    // --list-backends is enabled
+    if opts.list_backends {
+        print!("{}", show_backends());
+        return Ok(());
+    }
+
+    // Options for the backends are checked now but only added to the
+    // context after the passes run.
+    let backend_opts = opts.backend_opts()?;
 
-    let imports = ws.original_imports.drain(..).collect_vec();
+    pm.verify_each(opts.verify_each);
+    pm.profile_passes(opts.profile_passes || opts.profile_json.is_some());
+
+    // Dump the IR around the selected passes
+    if !opts.dump_ir_after.is_empty() || !opts.dump_ir_before.is_empty() {
+        pm.dump_ir(IrDump {
+            dir: opts.dump_ir_dir.clone(),
+            before: opts.dump_ir_before.drain(..).collect(),
+            after: opts.dump_ir_after.drain(..).collect(),
+            diff: opts.dump_ir_diff,
+        })?;
+    }
 
     // Build the IR representation
-    let mut ctx = ir::from_ast::ast_to_ir(ws)?;
-    // Configuration for the backend
-    ctx.bc = ir::BackendConf {
-        synthesis_mode: opts.enable_synthesis,
-        enable_verification: !opts.disable_verify,
-        initialize_inputs: !opts.disable_init,
+    let (mut ctx, imports) = match opts.input_format {
+        InputFormat::Calyx => {
+            // Construct the namespace.
+            let mut ws =
+                frontend::Workspace::construct(&opts.file, &opts.lib_path)?;
+            let imports = ws.original_imports.drain(..).collect_vec();
+            (ir::from_ast::ast_to_ir(ws)?, imports)
+        }
+        // The JSON representation contains the primitive definitions instead
+        // of the imports.
+        InputFormat::Json => {
+            (ir::Context::from_json(&read_input(&opts)?)?, vec![])
+        }
     };
     // Extra options for the passes
     ctx.extra_opts = opts.extra_opts.drain(..).collect();
 
     // Run all passes specified by the command line
     pm.execute_plan(&mut ctx, &opts.pass, &opts.disable_pass)?;
+    ctx.extra_opts.extend(backend_opts);
+
+    // Report the pass profile
+    if opts.profile_passes {
+        eprint!("{}", PassProfile::table(&pm.profile()));
+    }
+    if let Some(path) = &opts.profile_json {
+        let json = serde_json::to_string_pretty(&pm.profile())
+            .expect("Failed to serialize pass profile");
+        std::fs::write(path, json)?;
+    }
 
     // Print out the Calyx program after transformation.
     if opts.backend == BackendOpt::Calyx {
@@ -68,3 +156,21 @@ fn main() -> CalyxResult<()> {
         opts.run_backend(ctx)
     }
 }
+
+/// Read the input program from the input file or the standard input.
+fn read_input(opts: &Opts) -> CalyxResult<String> {
+    match &opts.file {
+        Some(path) => std::fs::read_to_string(path).map_err(|err| {
+            Error::invalid_file(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                err
+            ))
+        }),
+        None => {
+            let mut buf = String::new();
+            std::io::stdin().read_to_string(&mut buf)?;
+            Ok(buf)
+        }
+    }
+}
@@ -64,6 +64,10 @@ document.getElementById("compile").onclick = function() {
     compile();
 };
 
+document.getElementById("backend-select").onchange = function() {
+    compile();
+};
+
 function compile() {
     EDIT_MODE = false;
     // get passes to run
@@ -71,11 +75,19 @@ function compile() {
     // collect libraries into a single string
     let libraryCode = CURRENT_CODE.libraries.map(x => x.code).join("\n");
     // compile the code
-    var compiledCode = calyx.run(
+    var result = calyx.run(
         passList,
         libraryCode,
-        CURRENT_CODE.code
+        CURRENT_CODE.code,
+        document.getElementById("backend-select").value
     );
+    var compiledCode = result.output;
+    if (compiledCode === null) {
+        compiledCode = result.diagnostics.map(d => {
+            let loc = d.line === null ? "" : `${d.line}:${d.column}: `;
+            return `Error: ${loc}${d.message}`;
+        }).join("\n");
+    }
     // update the diff editor
     const editor = document.getElementById("diffEditor");
     const srcDiv = editor.querySelector("#input");
@@ -4,8 +4,17 @@ use calyx::{
     frontend, ir,
     pass_manager::PassManager,
 };
+use serde_derive::Serialize;
 use wasm_bindgen::prelude::*;
 
+/// Result of compiling a program. Exactly one of `output` and `diagnostics`
+/// is non-empty.
+#[derive(Serialize)]
+struct Output {
+    output: Option<String>,
+    diagnostics: Vec<errors::Diagnostic>,
+}
+
 // Contruct a workspace from a namspace
 fn ws_from_ns(ns: frontend::NamespaceDef) -> CalyxResult<frontend::Workspace> {
     if !ns.imports.is_empty() {
@@ -28,6 +37,7 @@ fn compile(
     passes: &[String],
     library: &str,
     namespace: &str,
+    backend: &str,
 ) -> Result<String, errors::Error> {
     let pm = PassManager::default_passes()?;
 
@@ -42,18 +52,62 @@ fn compile(
     pm.execute_plan(&mut rep, passes, &[])?;
 
     let mut buffer: Vec<u8> = vec![];
-    for comp in &rep.components {
-        ir::Printer::write_component(comp, &mut buffer)?;
+    if backend == "calyx" {
+        for comp in &rep.components {
+            ir::Printer::write_component(comp, &mut buffer)?;
+        }
+    } else {
+        let backend =
+            calyx_backend::find_backend(backend).ok_or_else(|| {
+                errors::Error::misc(format!("Unknown backend `{}`", backend))
+            })?;
+        // The extern files of the primitives are not available in the
+        // browser so they are not linked.
+        backend.validate(&rep)?;
+        backend.emit(&rep, &mut buffer)?;
     }
     Ok(String::from_utf8(buffer).unwrap())
 }
 
+/// The program is parsed after the library so locations in the program are
+/// offset by the library. Make them relative to the program instead.
+fn program_diagnostics(
+    err: errors::Error,
+    library: &str,
+) -> Vec<errors::Diagnostic> {
+    let offset = library.len() + 1;
+    let line_offset = library.matches('\n').count() + 1;
+    let mut diags = err.diagnostics();
+    for diag in &mut diags {
+        if matches!(diag.start, Some(start) if start >= offset) {
+            diag.start = diag.start.map(|s| s - offset);
+            diag.end = diag.end.map(|e| e - offset);
+            diag.line = diag.line.map(|l| l - line_offset);
+        }
+    }
+    diags
+}
+
+/// Compile `namespace` using the `passes` and print it using `backend`.
+/// `backend` is either `calyx` or the name of a backend from `calyx-backend`.
 #[wasm_bindgen]
-pub fn run(passes: &JsValue, library: &str, namespace: &str) -> String {
+pub fn run(
+    passes: &JsValue,
+    library: &str,
+    namespace: &str,
+    backend: &str,
+) -> JsValue {
     std::panic::set_hook(Box::new(console_error_panic_hook::hook));
     let test: Vec<String> = passes.into_serde().unwrap();
-    match compile(&test, library, namespace) {
-        Ok(s) => s,
-        Err(e) => format!("Error:\n{:?}", e),
-    }
+    let out = match compile(&test, library, namespace, backend) {
+        Ok(s) => Output {
+            output: Some(s),
+            diagnostics: vec![],
+        },
+        Err(e) => Output {
+            output: None,
+            diagnostics: program_diagnostics(e, library),
+        },
+    };
+    JsValue::from_serde(&out).unwrap()
 }